@@ -0,0 +1,98 @@
+// Parses typical beacons in a loop and prints the throughput.
+// There is no dependency on a benchmark framework, so this runs on stable.
+//
+// The unit dispatch is compared with the chain of string compares the comment parsers
+// used before Unit::from_suffix, on the same suffixes.
+//
+// Usage: cargo bench --bench comments
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ognparser::utils::{split_value_unit, Unit};
+use ognparser::Message;
+
+const ITERATIONS: usize = 100_000;
+
+const BEACONS: [(&str, &str); 3] = [
+    (
+        "position with units",
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 !W26! id0ADDFAA3 -019fpm +0.0rot 5.5dB 3e -4.3kHz gps2x3 -75.0dBm",
+    ),
+    (
+        "receiver status",
+        r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>074550h v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 3.3V 0.5A",
+    ),
+    (
+        "unknown units",
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 1rpm 2kVA 3mph 4hPa 5lux 6x",
+    ),
+];
+
+// The baseline: the position comment units first, then the status comment units
+fn chained_compares(unit: &str) -> Option<Unit> {
+    if unit == "fpm" {
+        Some(Unit::FeetPerMinute)
+    } else if unit == "rot" {
+        Some(Unit::Rotation)
+    } else if unit == "dB" {
+        Some(Unit::Decibel)
+    } else if unit == "kHz" {
+        Some(Unit::Kilohertz)
+    } else if unit == "e" {
+        Some(Unit::Errors)
+    } else if unit == "dBm" {
+        Some(Unit::DecibelMilliwatt)
+    } else if unit == "C" {
+        Some(Unit::Celsius)
+    } else if unit == "V" {
+        Some(Unit::Volt)
+    } else if unit == "A" {
+        Some(Unit::Ampere)
+    } else {
+        None
+    }
+}
+
+fn time_per_call<F: Fn(&str) -> Option<Unit>>(units: &[&str], dispatch: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for unit in units {
+            black_box(dispatch(black_box(unit)));
+        }
+    }
+    start.elapsed().as_nanos() as f64 / (ITERATIONS * units.len()) as f64
+}
+
+fn main() {
+    for (name, beacon) in BEACONS {
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(black_box(beacon).parse::<Message>().unwrap());
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "{:<20} {:>8.0} ns/beacon",
+            name,
+            elapsed.as_nanos() as f64 / ITERATIONS as f64
+        );
+    }
+
+    let units = BEACONS
+        .iter()
+        .flat_map(|(_, beacon)| beacon.split(' '))
+        .filter_map(|token| split_value_unit(token).map(|(_, unit)| unit))
+        .collect::<Vec<_>>();
+    for unit in &units {
+        assert_eq!(Unit::from_suffix(unit), chained_compares(unit));
+    }
+    let baseline = time_per_call(&units, chained_compares);
+    let byte_match = time_per_call(&units, Unit::from_suffix);
+    println!("{:<20} {:>8.2} ns/unit", "chained compares", baseline);
+    println!(
+        "{:<20} {:>8.2} ns/unit ({:.2}x)",
+        "byte match",
+        byte_match,
+        baseline / byte_match
+    );
+}
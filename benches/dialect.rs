@@ -0,0 +1,70 @@
+// Parses a feed of senders with and without lenient tokens and prints the time per beacon
+// of plain parse, of parse with a lenient retry on unparsed tokens and of the DialectCache.
+// The cache gets the comments of parse_lenient for the senders that need it without the
+// retry, it is not faster than plain parse, which doesn't parse these tokens at all.
+//
+// Usage: cargo bench --bench dialect
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ognparser::dialect::DialectCache;
+use ognparser::Message;
+
+const ITERATIONS: usize = 1_000;
+const SENDERS: usize = 100;
+
+fn feed() -> Vec<String> {
+    (0..SENDERS)
+        .flat_map(|sender| {
+            let position = format!(
+                r"FLR{:06X}>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06{:06X} -019fpm +0.0rot 5.5dB 3e -4.3kHz",
+                sender, sender
+            );
+            let status = format!(
+                r"FLR{:06X}>OGFLR,qAS,Letzi:>074550h h00 v00 9sat/1 164m 3.34V",
+                sender
+            );
+            // Every third sender sends miscased tokens
+            let gps = if sender % 3 == 0 { "GPS2x3" } else { "gps2x3" };
+            [format!("{} {} s7.07", position, gps), status]
+        })
+        .collect()
+}
+
+fn time_per_beacon<F: FnMut(&str) -> Message>(feed: &[String], mut parse: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for line in feed {
+            black_box(parse(black_box(line)));
+        }
+    }
+    start.elapsed().as_nanos() as f64 / (ITERATIONS * feed.len()) as f64
+}
+
+fn main() {
+    let feed = feed();
+    let parse = time_per_beacon(&feed, |line| line.parse::<Message>().unwrap());
+    let retry = time_per_beacon(&feed, |line| {
+        let message = line.parse::<Message>().unwrap();
+        match message
+            .position_comment
+            .as_ref()
+            .is_some_and(|comment| comment.unparsed.is_some())
+        {
+            true => Message::parse_lenient(line),
+            false => message,
+        }
+    });
+    let mut cache = DialectCache::new();
+    let cached = time_per_beacon(&feed, |line| cache.parse(line));
+    println!("{:<20} {:>8.0} ns/beacon", "parse", parse);
+    println!("{:<20} {:>8.0} ns/beacon", "lenient retry", retry);
+    println!(
+        "{:<20} {:>8.0} ns/beacon ({:.2}x of the retry)",
+        "dialect cache",
+        cached,
+        retry / cached
+    );
+    println!("{:?}", cache.statistics);
+}
@@ -0,0 +1,33 @@
+// Scans a raw log once and writes a sparse time index next to it,
+// so later reads can seek directly to a time window.
+//
+// Usage: cargo run --example build_time_index -- <raw log> [date as YYYY-MM-DD] [interval in seconds]
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use ognparser::archive::TimeIndex;
+use ognparser::time;
+
+fn main() -> std::io::Result<()> {
+    let args = env::args().collect::<Vec<_>>();
+    let Some(path) = args.get(1) else {
+        eprintln!("Usage: build_time_index <raw log> [date as YYYY-MM-DD] [interval in seconds]");
+        std::process::exit(1);
+    };
+    // The APRS timestamps have no date, so they are resolved around noon of the log date
+    let reference_time = match args.get(2) {
+        Some(date) => time::parse_date(date)
+            .map(|day_start| day_start + 43200)
+            .unwrap_or_else(|| panic!("Invalid date: {}", date)),
+        None => time::now(),
+    };
+    let interval = args.get(3).and_then(|s| s.parse().ok()).unwrap_or(60);
+
+    let index = TimeIndex::build(BufReader::new(File::open(path)?), reference_time, interval)?;
+    let index_path = format!("{}.idx", path);
+    index.write_to(BufWriter::new(File::create(&index_path)?))?;
+    println!("Wrote {} entries to {}", index.entries().len(), index_path);
+    Ok(())
+}
@@ -0,0 +1,97 @@
+// Runs a corpus through this crate and through python-ogn-client and reports
+// the fields where both parsers disagree.
+//
+// Requires python-ogn-client: pip install ogn-client
+// Usage: cargo run --example compare_ogn_python -- <corpus file> [python executable]
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use ognparser::compare::compare;
+use ognparser::Message;
+use serde_json::Value;
+
+// Reads lines from stdin and prints one JSON result per line
+const SCRIPT: &str = r#"
+import json, sys
+from ogn.parser import parse
+for line in sys.stdin:
+    try:
+        result = parse(line.rstrip("\n"))
+    except Exception as e:
+        result = {"parser_error": repr(e)}
+    print(json.dumps(result, default=str))
+"#;
+
+fn main() -> io::Result<()> {
+    let args = env::args().collect::<Vec<_>>();
+    let corpus = match args.get(1) {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            eprintln!("Usage: compare_ogn_python <corpus file> [python executable]");
+            std::process::exit(1);
+        }
+    };
+    let python = args.get(2).map(String::as_str).unwrap_or("python3");
+    let lines = corpus
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let mut child = Command::new(python)
+        .args(["-c", SCRIPT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Feed the lines from another thread, so neither pipe can fill up and block
+    let mut stdin = child.stdin.take().unwrap();
+    let input = lines.join("\n") + "\n";
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut per_field = BTreeMap::<String, usize>::new();
+    let mut with_disagreements = 0;
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    for (line, result) in lines.iter().zip(stdout.lines()) {
+        let theirs = serde_json::from_str::<Value>(&result?)?;
+        if theirs.get("parser_error").is_some() {
+            println!(
+                "python-ogn-client failed: {}\n  {}",
+                line, theirs["parser_error"]
+            );
+            continue;
+        }
+
+        let message = line.parse::<Message>().unwrap();
+        let disagreements = compare(&message, &theirs);
+        if disagreements.is_empty() {
+            continue;
+        }
+        with_disagreements += 1;
+        println!("{}", line);
+        for disagreement in disagreements {
+            println!(
+                "  {}: ours={} theirs={}",
+                disagreement.field, disagreement.ours, disagreement.theirs
+            );
+            *per_field.entry(disagreement.field).or_default() += 1;
+        }
+    }
+    writer.join().unwrap()?;
+    child.wait()?;
+
+    println!(
+        "\n{} of {} lines with disagreements",
+        with_disagreements,
+        lines.len()
+    );
+    for (field, count) in per_field {
+        println!("  {}: {}", field, count);
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// Samples the live OGN APRS feed and writes anonymized, deduplicated test fixtures
+// grouped by tocall.
+//
+// Usage: cargo run --example extract_corpus -- <output dir> [number of lines] [max fixtures per tocall]
+
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ognparser::corpus::CorpusBuilder;
+
+const SERVER: &str = "aprs.glidernet.org:10152";
+
+fn main() -> std::io::Result<()> {
+    let args = env::args().collect::<Vec<_>>();
+    let output_dir = args.get(1).map(String::as_str).unwrap_or("corpus");
+    let line_count = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(100_000);
+    let max_per_group = args.get(3).and_then(|s| s.parse().ok()).unwrap_or(50);
+
+    // A fresh salt per run, so the pseudonyms can't be linked between corpora
+    let salt = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    let mut corpus = CorpusBuilder::new(salt, max_per_group);
+
+    let mut stream = TcpStream::connect(SERVER)?;
+    writeln!(
+        stream,
+        "user OGNPARSER pass -1 vers ogn-parser-rs {}",
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    let mut added = 0;
+    for line in BufReader::new(stream).lines().take(line_count) {
+        let line = line?;
+        // Server messages and keepalives
+        if line.starts_with('#') {
+            continue;
+        }
+        if corpus.add(&line) {
+            added += 1;
+        }
+    }
+
+    corpus.write_to_dir(Path::new(output_dir))?;
+    println!(
+        "Wrote {} fixtures for {} tocalls to {}",
+        added,
+        corpus.groups().len(),
+        output_dir
+    );
+    Ok(())
+}
@@ -0,0 +1,193 @@
+// Reads a raw APRS stream from stdin and processes it with one worker per core.
+// Beacons are sharded by the address of the sender, so every worker owns the complete
+// state of its aircraft (deduplication and tracks) and no locks are needed.
+//
+// Usage: cargo run --release --features ingest --example ingest -- [workers] < raw.log
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::Instant;
+
+use ognparser::geo::distance;
+use ognparser::time;
+use ognparser::track::Fix;
+use ognparser::{AprsHeader, Limits, Message};
+
+// Lines are sent in batches, so the channel overhead doesn't dominate
+const BATCH_SIZE: usize = 256;
+// Batches in flight per worker before the reader blocks
+const QUEUE_SIZE: usize = 16;
+// The same beacon relayed by several receivers within this many seconds is a duplicate
+const DEDUPE_WINDOW: i64 = 30;
+
+#[derive(Default)]
+struct Track {
+    last: Option<Fix>,
+    fixes: u64,
+    // in meters
+    distance: f64,
+}
+
+#[derive(Default)]
+struct ShardStatistics {
+    lines: u64,
+    rejected: u64,
+    duplicates: u64,
+    fixes: u64,
+    aircraft: usize,
+    distance: f64,
+}
+
+// The stateful stages of one shard
+struct Worker {
+    limits: Limits,
+    reference_time: i64,
+    // (sender, information field) -> time of the first reception
+    seen: HashMap<(String, String), i64>,
+    tracks: HashMap<String, Track>,
+    statistics: ShardStatistics,
+}
+
+impl Worker {
+    fn new(reference_time: i64) -> Self {
+        Worker {
+            limits: Limits::default(),
+            reference_time,
+            seen: HashMap::new(),
+            tracks: HashMap::new(),
+            statistics: ShardStatistics::default(),
+        }
+    }
+
+    fn process(&mut self, line: &str) {
+        self.statistics.lines += 1;
+        let message = Message::parse_with_limits(line, &self.limits);
+        let Ok(packet) = &message.aprs_packet else {
+            self.statistics.rejected += 1;
+            return;
+        };
+        let Some(fix) = Fix::from_message(&message, self.reference_time) else {
+            return;
+        };
+
+        let information = line
+            .split_once(':')
+            .map_or("", |(_, information)| information);
+        let key = (packet.from.to_string(), information.to_string());
+        match self.seen.get(&key) {
+            Some(first) if fix.time - first < DEDUPE_WINDOW => {
+                self.statistics.duplicates += 1;
+                return;
+            }
+            _ => {
+                self.seen.insert(key, fix.time);
+            }
+        }
+        if self.seen.len() > 100_000 {
+            self.seen
+                .retain(|_, first| fix.time - *first < DEDUPE_WINDOW);
+        }
+
+        let track = self.tracks.entry(packet.from.to_string()).or_default();
+        if let Some(last) = &track.last {
+            track.distance += distance(last.latitude, last.longitude, fix.latitude, fix.longitude);
+        }
+        track.fixes += 1;
+        track.last = Some(fix);
+        self.statistics.fixes += 1;
+    }
+
+    fn run(mut self, receiver: Receiver<Vec<String>>) -> ShardStatistics {
+        for batch in receiver {
+            for line in &batch {
+                self.process(line);
+            }
+        }
+        self.statistics.aircraft = self.tracks.len();
+        self.statistics.distance = self.tracks.values().map(|track| track.distance).sum();
+        self.statistics
+    }
+}
+
+// The device address is the last 6 characters of the sender for all OGN devices,
+// non OGN senders are sharded by their complete callsign
+fn shard(line: &str, workers: usize) -> usize {
+    let from = AprsHeader::parse(line).map_or(line, |header| header.from);
+    let address = from.get(from.len().saturating_sub(6)..).unwrap_or(from);
+    let mut hasher = DefaultHasher::new();
+    address.hash(&mut hasher);
+    (hasher.finish() % workers as u64) as usize
+}
+
+fn main() -> io::Result<()> {
+    let workers = env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+    let reference_time = time::now();
+    let start = Instant::now();
+
+    let (senders, handles): (Vec<SyncSender<Vec<String>>>, Vec<_>) = (0..workers)
+        .map(|_| {
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_SIZE);
+            let worker = Worker::new(reference_time);
+            (sender, thread::spawn(move || worker.run(receiver)))
+        })
+        .unzip();
+
+    let mut batches = vec![Vec::with_capacity(BATCH_SIZE); workers];
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let idx = shard(&line, workers);
+        batches[idx].push(line);
+        if batches[idx].len() == BATCH_SIZE {
+            let batch = std::mem::replace(&mut batches[idx], Vec::with_capacity(BATCH_SIZE));
+            senders[idx].send(batch).expect("worker stopped");
+        }
+    }
+    for (sender, batch) in senders.iter().zip(batches) {
+        sender.send(batch).expect("worker stopped");
+    }
+    drop(senders);
+
+    let mut total = ShardStatistics::default();
+    for (idx, handle) in handles.into_iter().enumerate() {
+        let statistics = handle.join().expect("worker panicked");
+        println!(
+            "worker {}: {} lines, {} rejected, {} duplicates, {} fixes of {} aircraft",
+            idx,
+            statistics.lines,
+            statistics.rejected,
+            statistics.duplicates,
+            statistics.fixes,
+            statistics.aircraft
+        );
+        total.lines += statistics.lines;
+        total.rejected += statistics.rejected;
+        total.duplicates += statistics.duplicates;
+        total.fixes += statistics.fixes;
+        total.aircraft += statistics.aircraft;
+        total.distance += statistics.distance;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{} lines in {:.2}s ({:.0} lines/s), {} fixes of {} aircraft, {:.0} km flown",
+        total.lines,
+        elapsed,
+        total.lines as f64 / elapsed,
+        total.fixes,
+        total.aircraft,
+        total.distance / 1000.0
+    );
+    Ok(())
+}
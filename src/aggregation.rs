@@ -0,0 +1,545 @@
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+use serde::ser::SerializeStruct;
+use serde::Serialize;
+
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::status_comment::StatusComment;
+use crate::time::seconds_of_day;
+
+// Trade-off between accuracy and size of the quantile estimation
+const DIGEST_COMPRESSION: f64 = 100.0;
+const DIGEST_BUFFER_SIZE: usize = 500;
+// Receivers send their status every 5 minutes, a longer silence means they were down
+const STATUS_TIMEOUT: i64 = 900;
+// A larger jump of the frame counter is a restart of the tracker, not lost frames
+const MAX_FRAME_GAP: u32 = 1000;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Statistic {
+    pub count: u64,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    digest: Digest,
+}
+
+impl Statistic {
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        self.digest.add(value);
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count => Some(self.sum / count as f64),
+        }
+    }
+
+    // Estimated quantile, e.g. 0.95 for the 95th percentile
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.digest.quantile(q)
+    }
+}
+
+impl Serialize for Statistic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Statistic", 7)?;
+        state.serialize_field("count", &self.count)?;
+        state.serialize_field("sum", &self.sum)?;
+        state.serialize_field("min", &self.min)?;
+        state.serialize_field("max", &self.max)?;
+        state.serialize_field("mean", &self.mean())?;
+        state.serialize_field("p50", &self.quantile(0.5))?;
+        state.serialize_field("p95", &self.quantile(0.95))?;
+        state.end()
+    }
+}
+
+// A merging t-digest: the values are clustered into centroids which are small
+// at the tails and large around the median, so the memory stays bounded
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Digest {
+    // (mean, weight) sorted by mean
+    centroids: Vec<(f64, f64)>,
+    buffer: Vec<f64>,
+}
+
+impl Digest {
+    fn add(&mut self, value: f64) {
+        self.buffer.push(value);
+        if self.buffer.len() >= DIGEST_BUFFER_SIZE {
+            self.centroids = self.merged();
+            self.buffer.clear();
+        }
+    }
+
+    fn merged(&self) -> Vec<(f64, f64)> {
+        let mut points = self
+            .centroids
+            .iter()
+            .copied()
+            .chain(self.buffer.iter().map(|value| (*value, 1.0)))
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total = points.iter().map(|(_, weight)| weight).sum::<f64>();
+        let scale =
+            |q: f64| DIGEST_COMPRESSION / (2.0 * PI) * (2.0 * q.clamp(0.0, 1.0) - 1.0).asin();
+        let mut result: Vec<(f64, f64)> = vec![];
+        // The weight of all centroids before the last one
+        let mut cumulative = 0.0;
+        for (mean, weight) in points {
+            if let Some(last) = result.last_mut() {
+                let q_left = cumulative / total;
+                let q_right = (cumulative + last.1 + weight) / total;
+                if scale(q_right) - scale(q_left) <= 1.0 {
+                    last.0 += (mean - last.0) * weight / (last.1 + weight);
+                    last.1 += weight;
+                    continue;
+                }
+                cumulative += last.1;
+            }
+            result.push((mean, weight));
+        }
+        result
+    }
+
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let centroids = self.merged();
+        let total = centroids.iter().map(|(_, weight)| weight).sum::<f64>();
+        let target = q.clamp(0.0, 1.0) * total;
+
+        // Interpolate linearly between the centers of the neighbouring centroids
+        let mut cumulative = 0.0;
+        let mut previous: Option<(f64, f64)> = None;
+        for (mean, weight) in &centroids {
+            let center = cumulative + weight / 2.0;
+            if target < center {
+                return Some(match previous {
+                    Some((previous_center, previous_mean)) => {
+                        previous_mean
+                            + (mean - previous_mean) * (target - previous_center)
+                                / (center - previous_center)
+                    }
+                    None => *mean,
+                });
+            }
+            previous = Some((center, *mean));
+            cumulative += weight;
+        }
+        centroids.last().map(|(mean, _)| *mean)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct ReceiverStatistics {
+    pub beacons: u64,
+    // in dB
+    pub signal_quality: Statistic,
+    // in dBm
+    pub signal_power: Statistic,
+    // From the status of the receiver, in dB
+    pub noise: Statistic,
+    // From the status of the receiver, in s
+    pub latency: Statistic,
+    pub restarts: u64,
+    // Status beacons announcing maintenance, their restarts are not counted
+    pub maintenance: u64,
+    // Seconds since the last restart
+    pub uptime: Option<i64>,
+}
+
+// What we need from the previous status to detect a restart
+struct ReceiverState {
+    version: Option<String>,
+    senders_messages: Option<u32>,
+    maintenance: bool,
+    seconds_of_day: i64,
+    uptime: i64,
+}
+
+impl ReceiverState {
+    fn from_status(status: &StatusComment, maintenance: bool, seconds_of_day: i64) -> Self {
+        ReceiverState {
+            version: status.version.clone(),
+            senders_messages: status.senders_messages,
+            maintenance,
+            seconds_of_day,
+            uptime: 0,
+        }
+    }
+
+    // The counters starting over together with a new version or a gap in the status
+    // beacons, the counters alone also drop when the receiver resets its statistics.
+    // Without counters a new version or a gap is enough.
+    fn is_restart(&self, status: &StatusComment, elapsed: i64) -> bool {
+        let changed = match (&self.version, &status.version) {
+            (Some(previous), Some(current)) => previous != current,
+            _ => false,
+        };
+        let down = changed || elapsed > STATUS_TIMEOUT;
+        match (self.senders_messages, status.senders_messages) {
+            (Some(previous), Some(current)) => current < previous && down,
+            _ => down,
+        }
+    }
+}
+
+// Collects statistics of the aircraft beacons and the status per receiving station
+#[derive(Default)]
+pub struct Aggregator {
+    calibration: HashMap<String, f64>,
+    // Operator-defined keywords in addition to MAINTENANCE_KEYWORDS
+    maintenance_keywords: Vec<String>,
+    // Survives take_receivers, the uptime goes on over several periods
+    states: HashMap<String, ReceiverState>,
+    receivers: BTreeMap<String, ReceiverStatistics>,
+}
+
+impl Aggregator {
+    pub fn new() -> Self {
+        Aggregator::default()
+    }
+
+    // The offset in dB is added to the signal values of this receiver,
+    // so uncalibrated receivers don't skew the network-wide statistics
+    pub fn with_calibration(mut self, receiver: &str, offset: f64) -> Self {
+        self.set_calibration(receiver, offset);
+        self
+    }
+
+    pub fn set_calibration(&mut self, receiver: &str, offset: f64) {
+        self.calibration.insert(receiver.to_string(), offset);
+    }
+
+    pub fn with_maintenance_keywords(mut self, keywords: &[&str]) -> Self {
+        self.maintenance_keywords = keywords.iter().map(|keyword| keyword.to_string()).collect();
+        self
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        if let (Ok(packet), Some(status)) = (&message.aprs_packet, &message.status_comment) {
+            let maintenance = status.is_maintenance(&self.maintenance_keywords);
+            let receiver = packet.from.to_string();
+            let time = match &packet.data {
+                AprsData::Status(aprs_status) => aprs_status.timestamp.as_ref(),
+                _ => None,
+            }
+            .and_then(seconds_of_day);
+            let statistics = self.receivers.entry(receiver.clone()).or_default();
+            if maintenance {
+                statistics.maintenance += 1;
+            }
+            if let Some(time) = time {
+                let state = match self.states.remove(&receiver) {
+                    Some(previous) => {
+                        // The timestamps wrap around at midnight
+                        let elapsed = (time - previous.seconds_of_day).rem_euclid(86400);
+                        // Duplicates and beacons delivered out of order say nothing about
+                        // a restart, they would look like a gap of almost a day
+                        if elapsed == 0 || elapsed >= 86400 - STATUS_TIMEOUT {
+                            previous
+                        } else {
+                            let mut state = ReceiverState::from_status(status, maintenance, time);
+                            if previous.is_restart(status, elapsed) {
+                                // Planned downtime is not a restart
+                                if !previous.maintenance && !maintenance {
+                                    statistics.restarts += 1;
+                                }
+                            } else {
+                                state.uptime = previous.uptime + elapsed;
+                            }
+                            state
+                        }
+                    }
+                    None => ReceiverState::from_status(status, maintenance, time),
+                };
+                statistics.uptime = Some(state.uptime);
+                self.states.insert(receiver, state);
+            }
+            if let Some(noise) = status.noise {
+                statistics.noise.add(f64::from(noise));
+            }
+            if let Some(latency) = status.latency {
+                statistics.latency.add(f64::from(latency));
+            }
+            return;
+        }
+
+        let comment = match (&message.aprs_packet, &message.position_comment) {
+            // Only aircraft beacons carry an id, receiver beacons are skipped
+            (Ok(_), Some(comment)) if comment.id.is_some() => comment,
+            _ => return,
+        };
+        // Aircraft beacons are forwarded by the receiver: FLRDDFAA3>APRS,qAS,Letzi:...
+        let receiver = match message.receiver() {
+            Some(receiver) => receiver.to_string(),
+            None => return,
+        };
+        let offset = self.calibration.get(&receiver).copied().unwrap_or(0.0);
+        let statistics = self.receivers.entry(receiver).or_default();
+        statistics.beacons += 1;
+        if let Some(signal_quality) = comment.signal_quality {
+            statistics
+                .signal_quality
+                .add(f64::from(signal_quality) + offset);
+        }
+        if let Some(signal_power) = comment.signal_power {
+            statistics
+                .signal_power
+                .add(f64::from(signal_power) + offset);
+        }
+    }
+
+    pub fn receiver(&self, receiver: &str) -> Option<&ReceiverStatistics> {
+        self.receivers.get(receiver)
+    }
+
+    pub fn receivers(&self) -> &BTreeMap<String, ReceiverStatistics> {
+        &self.receivers
+    }
+
+    // Starts over with empty statistics, the calibration is kept
+    pub fn take_receivers(&mut self) -> BTreeMap<String, ReceiverStatistics> {
+        std::mem::take(&mut self.receivers)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize)]
+pub struct FrameLoss {
+    pub received: u64,
+    // Frames sent according to the frame counters
+    pub expected: u64,
+}
+
+impl FrameLoss {
+    // Fraction of the frames which were lost, between 0 and 1
+    pub fn loss_rate(&self) -> Option<f64> {
+        (self.expected > 0).then(|| 1.0 - self.received as f64 / self.expected as f64)
+    }
+}
+
+// Estimates the packet loss per tracker from the gaps in the frame counters ("FN:1234").
+// Duplicates don't count, counters going backwards or jumping far start a new sequence.
+#[derive(Default)]
+pub struct FrameLossEstimator {
+    last_frames: HashMap<String, u32>,
+    senders: BTreeMap<String, FrameLoss>,
+}
+
+impl FrameLossEstimator {
+    pub fn new() -> Self {
+        FrameLossEstimator::default()
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        let (Ok(packet), Some(frame_counter)) = (
+            &message.aprs_packet,
+            message
+                .position_comment
+                .as_ref()
+                .and_then(|comment| comment.frame_counter),
+        ) else {
+            return;
+        };
+        let sender = packet.from.to_string();
+        let gap = match self.last_frames.get(&sender) {
+            Some(&last) if frame_counter == last => return,
+            Some(&last) if frame_counter > last && frame_counter - last <= MAX_FRAME_GAP => {
+                frame_counter - last
+            }
+            _ => 1,
+        };
+        let loss = self.senders.entry(sender.clone()).or_default();
+        loss.received += 1;
+        loss.expected += u64::from(gap);
+        self.last_frames.insert(sender, frame_counter);
+    }
+
+    pub fn sender(&self, sender: &str) -> Option<&FrameLoss> {
+        self.senders.get(sender)
+    }
+
+    pub fn senders(&self) -> &BTreeMap<String, FrameLoss> {
+        &self.senders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beacon(receiver: &str, signal: &str) -> Message {
+        format!(
+            r"FLRDDFAA3>APRS,qAS,{}:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 {}",
+            receiver, signal
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_statistic() {
+        let mut statistic = Statistic::default();
+        assert_eq!(statistic.mean(), None);
+        for value in [1.0, 5.0, 3.0] {
+            statistic.add(value);
+        }
+        assert_eq!(statistic.mean(), Some(3.0));
+        assert_eq!((statistic.min, statistic.max), (Some(1.0), Some(5.0)));
+        assert_eq!(statistic.quantile(0.5), Some(3.0));
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut statistic = Statistic::default();
+        assert_eq!(statistic.quantile(0.5), None);
+        // More values than the buffer, in a bad order
+        for idx in 0..10_000 {
+            statistic.add(((idx * 7919) % 10_000) as f64);
+        }
+        let p50 = statistic.quantile(0.5).unwrap();
+        let p95 = statistic.quantile(0.95).unwrap();
+        assert!((p50 - 5000.0).abs() < 100.0, "p50: {}", p50);
+        assert!((p95 - 9500.0).abs() < 50.0, "p95: {}", p95);
+        assert!(statistic.digest.centroids.len() < 200);
+
+        let value = serde_json::to_value(&statistic).unwrap();
+        assert_eq!(value["count"], 10_000);
+        assert_eq!(value["p95"], p95);
+    }
+
+    #[test]
+    fn test_receiver_status() {
+        let mut aggregator = Aggregator::new();
+        for noise in ["+0.5dB", "+1.5dB", "+4.5dB"] {
+            let status = format!(
+                "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 3/4Acfts[1h] RF:+54-1.1ppm/{}",
+                noise
+            );
+            aggregator.update(&status.parse::<Message>().unwrap());
+        }
+        let letzi = aggregator.receiver("Letzi").unwrap();
+        assert_eq!(letzi.beacons, 0);
+        assert_eq!(letzi.noise.count, 3);
+        assert_eq!(letzi.noise.quantile(0.5), Some(1.5));
+    }
+
+    fn status(timestamp: &str, version: &str, messages: u32) -> Message {
+        format!(
+            "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>{} v{}.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 3/4Acfts[1h] RF:+54-1.1ppm/+0.5dB/+2.5dB@10km[{}]",
+            timestamp, version, messages
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_restarts() {
+        let mut aggregator = Aggregator::new();
+        let mut update = |message: Message| {
+            aggregator.update(&message);
+            let letzi = aggregator.receiver("Letzi").unwrap();
+            (letzi.restarts, letzi.uptime.unwrap())
+        };
+        assert_eq!(update(status("085623h", "0.2.7", 100)), (0, 0));
+        assert_eq!(update(status("090123h", "0.2.7", 200)), (0, 300));
+        // New version, the counters start over
+        assert_eq!(update(status("090623h", "0.2.8", 5)), (1, 0));
+        assert_eq!(update(status("091123h", "0.2.8", 100)), (1, 300));
+        // The counters alone are reset
+        assert_eq!(update(status("091623h", "0.2.8", 10)), (1, 600));
+        // Gap of 30 minutes, the counters start over
+        assert_eq!(update(status("094623h", "0.2.8", 5)), (2, 0));
+        // A gap alone is a network outage
+        assert_eq!(update(status("101623h", "0.2.8", 50)), (2, 1800));
+        // Out of order and duplicated beacons are skipped
+        assert_eq!(update(status("101123h", "0.2.8", 40)), (2, 1800));
+        assert_eq!(update(status("101623h", "0.2.8", 50)), (2, 1800));
+        assert_eq!(update(status("102123h", "0.2.8", 60)), (2, 2100));
+
+        // The uptime is kept over several periods
+        aggregator.take_receivers();
+        aggregator.update(&status("102623h", "0.2.8", 70));
+        let letzi = aggregator.receiver("Letzi").unwrap();
+        assert_eq!((letzi.restarts, letzi.uptime), (0, Some(2400)));
+
+        // Over midnight
+        aggregator.update(&status("235823h", "0.2.8", 80));
+        aggregator.update(&status("000323h", "0.2.8", 90));
+        let letzi = aggregator.receiver("Letzi").unwrap();
+        assert_eq!(letzi.restarts, 0);
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let mut aggregator = Aggregator::new().with_maintenance_keywords(&["WARTUNG"]);
+        let mut update = |message: Message| {
+            aggregator.update(&message);
+            let letzi = aggregator.receiver("Letzi").unwrap();
+            (letzi.restarts, letzi.maintenance)
+        };
+        let maintenance = |timestamp: &str, keyword: &str| {
+            format!(
+                "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>{} v0.2.8.RPI-GPU CPU:0.7 {}",
+                timestamp, keyword
+            )
+            .parse::<Message>()
+            .unwrap()
+        };
+        assert_eq!(update(status("085623h", "0.2.7", 100)), (0, 0));
+        assert_eq!(update(maintenance("090123h", "MAINT")), (0, 1));
+        // The gap and the new version after the maintenance are no restart
+        assert_eq!(update(status("094623h", "0.2.8", 10)), (0, 1));
+        assert_eq!(update(maintenance("095123h", "wartung")), (0, 2));
+        assert_eq!(update(status("095623h", "0.2.8", 20)), (0, 2));
+        assert_eq!(update(status("103123h", "0.2.8", 5)), (1, 2));
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut aggregator = Aggregator::new().with_calibration("Letzi", -2.5);
+        aggregator.update(&beacon("Letzi", "10.0dB"));
+        aggregator.update(&beacon("Letzi", "-80.0dBm"));
+        aggregator.update(&beacon("dl4mea", "10.0dB"));
+
+        let letzi = aggregator.receiver("Letzi").unwrap();
+        assert_eq!(letzi.beacons, 2);
+        assert_eq!(letzi.signal_quality.mean(), Some(7.5));
+        assert_eq!(letzi.signal_power.mean(), Some(-82.5));
+        assert_eq!(
+            aggregator.receiver("dl4mea").unwrap().signal_quality.mean(),
+            Some(10.0)
+        );
+        assert_eq!(aggregator.receivers().len(), 2);
+    }
+
+    #[test]
+    fn test_frame_loss() {
+        let mut estimator = FrameLossEstimator::new();
+        for frame in [10, 11, 11, 14, 15, 3, 4] {
+            let message = format!(
+                r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 FN:{}",
+                frame
+            )
+            .parse::<Message>()
+            .unwrap();
+            estimator.update(&message);
+        }
+        // 10, 11, (12, 13 lost), 14, 15, restart: 3, 4
+        let loss = estimator.sender("FLRDDFAA3").unwrap();
+        assert_eq!((loss.received, loss.expected), (6, 8));
+        assert_eq!(loss.loss_rate(), Some(0.25));
+        assert_eq!(FrameLoss::default().loss_rate(), None);
+    }
+}
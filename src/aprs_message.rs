@@ -0,0 +1,131 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::cursor::FieldCursor;
+use crate::error::AprsError;
+use crate::utils::parse_digits;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum MessageKind {
+    Message,
+    // "ack42": the message with the id 42 was received
+    Ack,
+    // "rej42": the message with the id 42 can't be handled
+    Reject,
+}
+
+impl MessageKind {
+    // The kind and the id of acks and rejects, which have no "{id"
+    pub fn classify(text: &str) -> (MessageKind, Option<u32>) {
+        let kind = match text.get(..3) {
+            Some("ack") => MessageKind::Ack,
+            Some("rej") => MessageKind::Reject,
+            _ => return (MessageKind::Message, None),
+        };
+        match parse_digits::<u32>(&text[3..]) {
+            Some(id) => (kind, Some(id)),
+            None => (MessageKind::Message, None),
+        }
+    }
+}
+
+// A standalone APRS message ":ADDRESSEE:text{id"
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct AprsMessage {
+    pub addressee: String,
+    // For acks and rejects "ack42" or "rej42"
+    pub text: String,
+    // For acks and rejects the id of the message they answer
+    pub id: Option<u32>,
+    pub kind: MessageKind,
+}
+
+impl FromStr for AprsMessage {
+    type Err = AprsError;
+
+    // Parses the information field without the data type identifier ':'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The addressee is padded with spaces to 9 characters
+        let mut cursor = FieldCursor::new(s);
+        let addressee = match (cursor.take_n(9), cursor.expect(":")) {
+            (Some(addressee), Some(())) if !addressee.trim_end().is_empty() => addressee,
+            _ => return Err(AprsError::InvalidMessageDestination(s.to_string())),
+        };
+        let body = cursor.rest();
+        let (text, id) = match body.rsplit_once('{') {
+            Some((text, id)) => {
+                let id = id
+                    .parse::<u32>()
+                    .map_err(|_| AprsError::InvalidMessageId(id.to_string()))?;
+                (text, Some(id))
+            }
+            None => (body, None),
+        };
+        let (kind, id) = match id {
+            Some(id) => (MessageKind::Message, Some(id)),
+            None => MessageKind::classify(text),
+        };
+        Ok(AprsMessage {
+            addressee: addressee.trim_end().to_string(),
+            text: text.to_string(),
+            id,
+            kind,
+        })
+    }
+}
+
+impl Display for AprsMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, ":{:<9}:{}", self.addressee, self.text)?;
+        if let (MessageKind::Message, Some(id)) = (self.kind, self.id) {
+            write!(f, "{{{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_message() {
+    let message = "OGNDDFAA3:Hello world{42".parse::<AprsMessage>().unwrap();
+    assert_eq!(
+        message,
+        AprsMessage {
+            addressee: "OGNDDFAA3".to_string(),
+            text: "Hello world".to_string(),
+            id: Some(42),
+            kind: MessageKind::Message,
+        }
+    );
+    assert_eq!(message.to_string(), ":OGNDDFAA3:Hello world{42");
+
+    let message = "BLN1     :Fly safe".parse::<AprsMessage>().unwrap();
+    assert_eq!(message.addressee, "BLN1");
+    assert_eq!(message.id, None);
+    assert_eq!(message.to_string(), ":BLN1     :Fly safe");
+
+    let message = "OGNDDFAA3:ack42".parse::<AprsMessage>().unwrap();
+    assert_eq!(message.kind, MessageKind::Ack);
+    assert_eq!(message.id, Some(42));
+    assert_eq!(message.to_string(), ":OGNDDFAA3:ack42");
+
+    let message = "OGNDDFAA3:rej7".parse::<AprsMessage>().unwrap();
+    assert_eq!(message.kind, MessageKind::Reject);
+    assert_eq!(message.id, Some(7));
+
+    let message = "OGNDDFAA3:acknowledged{3".parse::<AprsMessage>().unwrap();
+    assert_eq!(message.kind, MessageKind::Message);
+    assert_eq!(message.id, Some(3));
+
+    assert_eq!(
+        "BLN1:Fly safe".parse::<AprsMessage>(),
+        Err(AprsError::InvalidMessageDestination(
+            "BLN1:Fly safe".to_string()
+        ))
+    );
+    assert_eq!(
+        "BLN1     :Fly safe{x".parse::<AprsMessage>(),
+        Err(AprsError::InvalidMessageId("x".to_string()))
+    );
+}
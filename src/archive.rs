@@ -0,0 +1,292 @@
+#[cfg(feature = "mmap")]
+use std::fs::File;
+use std::io::{self, BufRead, Seek, SeekFrom, Write};
+#[cfg(feature = "mmap")]
+use std::path::Path;
+
+use crate::message::Message;
+use crate::packet::AprsHeader;
+use crate::time;
+use crate::timestamp::Timestamp;
+
+// Random access to a raw log: only the line offsets are indexed up front,
+// lines are parsed when they are accessed
+pub struct ArchiveReader<D: AsRef<[u8]>> {
+    data: D,
+    // Start of every line, followed by the end of the data
+    offsets: Vec<usize>,
+    reference_time: i64,
+}
+
+#[cfg(feature = "mmap")]
+impl ArchiveReader<memmap2::Mmap> {
+    // Maps the file into memory, so even multi-GB logs are not loaded completely
+    pub fn open<P: AsRef<Path>>(path: P, reference_time: i64) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the log must not be truncated while it is mapped
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(ArchiveReader::new(mmap, reference_time))
+    }
+}
+
+impl<D: AsRef<[u8]>> ArchiveReader<D> {
+    // reference_time: seconds since 1970-01-01 close to the content of the log (e.g. its date),
+    // used to resolve the APRS timestamps of the lines
+    pub fn new(data: D, reference_time: i64) -> Self {
+        let bytes = data.as_ref();
+        let mut offsets = vec![0];
+        offsets.extend(
+            bytes
+                .iter()
+                .enumerate()
+                .filter(|(idx, byte)| **byte == b'\n' && idx + 1 < bytes.len())
+                .map(|(idx, _)| idx + 1),
+        );
+        if bytes.is_empty() {
+            offsets.clear();
+        }
+        offsets.push(bytes.len());
+        ArchiveReader {
+            data,
+            offsets,
+            reference_time,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn line(&self, idx: usize) -> Option<&str> {
+        let (start, end) = (*self.offsets.get(idx)?, *self.offsets.get(idx + 1)?);
+        let line = std::str::from_utf8(&self.data.as_ref()[start..end]).ok()?;
+        Some(line.trim_end_matches(['\r', '\n']))
+    }
+
+    pub fn get(&self, idx: usize) -> Option<Message> {
+        let line = self.line(idx)?;
+        if line.is_empty() {
+            return None;
+        }
+        Some(line.parse::<Message>().unwrap())
+    }
+
+    pub fn time(&self, idx: usize) -> Option<i64> {
+        line_time(self.line(idx)?, self.reference_time)
+    }
+
+    // The index of the first line at or after the time, the log must be in chronological order.
+    // Lines without a timestamp belong to the next line with one.
+    pub fn seek(&self, time: i64) -> usize {
+        let next_time = |idx: usize| (idx..self.len()).find_map(|idx| self.time(idx));
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match next_time(middle) {
+                Some(line_time) if line_time < time => low = middle + 1,
+                _ => high = middle,
+            }
+        }
+        low
+    }
+
+    // All messages in the time window [start, end)
+    pub fn range(&self, start: i64, end: i64) -> impl Iterator<Item = Message> + '_ {
+        (self.seek(start)..self.seek(end)).filter_map(|idx| self.get(idx))
+    }
+}
+
+// The resolved timestamp of a position or status, only the timestamp is parsed
+fn line_time(line: &str, reference_time: i64) -> Option<i64> {
+    let header = AprsHeader::parse(line).ok()?;
+    let timestamp = match header.data_type()? {
+        '/' | '@' | '>' => header.body().get(..7)?,
+        _ => return None,
+    };
+    time::resolve(&timestamp.parse::<Timestamp>().ok()?, reference_time)
+}
+
+// A sparse index of a raw log: the byte offset of the first line of every interval,
+// so readers can seek close to a time window instead of scanning the whole log
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TimeIndex {
+    // (time, byte offset), ascending
+    entries: Vec<(i64, u64)>,
+}
+
+impl TimeIndex {
+    // interval: seconds between the index entries
+    pub fn build<R: BufRead>(
+        mut reader: R,
+        reference_time: i64,
+        interval: i64,
+    ) -> io::Result<Self> {
+        let mut entries: Vec<(i64, u64)> = vec![];
+        let (mut offset, mut line) = (0, String::new());
+        loop {
+            line.clear();
+            let length = reader.read_line(&mut line)?;
+            if length == 0 {
+                break;
+            }
+            if let Some(time) = line_time(line.trim_end(), reference_time) {
+                match entries.last() {
+                    Some((last, _)) if time < last + interval => {}
+                    _ => entries.push((time, offset)),
+                }
+            }
+            offset += length as u64;
+        }
+        Ok(TimeIndex { entries })
+    }
+
+    // The offset to start reading from to get all lines at or after the time
+    pub fn offset(&self, time: i64) -> u64 {
+        let idx = self
+            .entries
+            .partition_point(|(entry_time, _)| *entry_time <= time);
+        match idx {
+            0 => 0,
+            idx => self.entries[idx - 1].1,
+        }
+    }
+
+    pub fn seek<S: Seek>(&self, reader: &mut S, time: i64) -> io::Result<u64> {
+        reader.seek(SeekFrom::Start(self.offset(time)))
+    }
+
+    pub fn entries(&self) -> &[(i64, u64)] {
+        &self.entries
+    }
+
+    // One "time offset" pair per line
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (time, offset) in &self.entries {
+            writeln!(writer, "{} {}", time, offset)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut entries = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            let entry = line
+                .split_once(' ')
+                .and_then(|(time, offset)| Some((time.parse().ok()?, offset.parse().ok()?)))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid entry: {}", line),
+                    )
+                })?;
+            entries.push(entry);
+        }
+        Ok(TimeIndex { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-08-01T12:00:00Z
+    const REFERENCE_TIME: i64 = 1722513600;
+
+    fn log() -> String {
+        [
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3",
+            r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>074550h v0.2.7.RPI-GPU",
+            r"FLRDDFAA3>APRS,qAS,Letzi:T#005,199,000,255,073,123,01101001",
+            "",
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074600h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3",
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074610h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3",
+        ]
+        .join("\r\n")
+    }
+
+    #[test]
+    fn test_lines() {
+        let log = log();
+        let archive = ArchiveReader::new(log.as_bytes(), REFERENCE_TIME);
+        assert_eq!(archive.len(), 6);
+        assert!(archive.line(1).unwrap().ends_with("v0.2.7.RPI-GPU"));
+        assert_eq!(archive.get(3), None);
+        assert!(archive.get(5).unwrap().position_comment.is_some());
+        assert_eq!(archive.line(6), None);
+
+        assert_eq!(
+            archive.time(0).map(time::to_iso8601),
+            Some("2024-08-01T07:45:48Z".to_string())
+        );
+        assert_eq!(archive.time(2), None);
+
+        assert!(ArchiveReader::new(vec![], REFERENCE_TIME).is_empty());
+        assert_eq!(ArchiveReader::new(b"a\n".to_vec(), REFERENCE_TIME).len(), 1);
+    }
+
+    #[test]
+    fn test_seek() {
+        let log = log();
+        let archive = ArchiveReader::new(log.as_bytes(), REFERENCE_TIME);
+        // 2024-08-01T00:00:00Z
+        let time = |hhmmss: &str| {
+            let value = hhmmss.parse::<i64>().unwrap();
+            1722470400 + value / 10000 * 3600 + value / 100 % 100 * 60 + value % 100
+        };
+        assert_eq!(archive.seek(time("000000")), 0);
+        assert_eq!(archive.seek(time("074549")), 1);
+        // The lines without a timestamp belong to the next fix
+        assert_eq!(archive.seek(time("074551")), 2);
+        assert_eq!(archive.seek(time("074605")), 5);
+        assert_eq!(archive.seek(time("235959")), 6);
+
+        let messages = archive
+            .range(time("074549"), time("074605"))
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].status_comment.is_some());
+    }
+
+    #[test]
+    fn test_time_index() {
+        let log = log();
+        let index = TimeIndex::build(log.as_bytes(), REFERENCE_TIME, 10).unwrap();
+        // 07:45:48, 07:46:00 and 07:46:10, the status at 07:45:50 is within the first interval
+        assert_eq!(index.entries().len(), 3);
+        assert_eq!(
+            index.entries()[1].1 as usize,
+            log.find("FLRDDFAA3>APRS,qAS,Letzi:/074600h").unwrap()
+        );
+
+        // 2024-08-01T07:46:05Z
+        let mut reader = io::Cursor::new(log.as_bytes());
+        index.seek(&mut reader, 1722498365).unwrap();
+        let first = reader.lines().next().unwrap().unwrap();
+        assert!(first.contains("/074600h"));
+        assert_eq!(index.offset(0), 0);
+
+        let mut serialized = vec![];
+        index.write_to(&mut serialized).unwrap();
+        assert_eq!(TimeIndex::read_from(serialized.as_slice()).unwrap(), index);
+        assert!(TimeIndex::read_from(&b"no index"[..]).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open() {
+        let path = std::env::temp_dir().join(format!("ogn-archive-{}.txt", std::process::id()));
+        std::fs::write(&path, log()).unwrap();
+        let archive = ArchiveReader::open(&path, REFERENCE_TIME).unwrap();
+        assert_eq!(archive.len(), 6);
+        assert_eq!(
+            archive.get(0).unwrap().raw_string,
+            log().lines().next().unwrap()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::detection::is_receiver;
+use crate::message::Message;
+use crate::output::packet_timestamp;
+use crate::pipeline::error_kind;
+use crate::time;
+
+// Counters of a parsed batch, collected while parsing so ETL jobs need no second pass
+// over the messages
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize)]
+pub struct BatchSummary {
+    pub messages: u64,
+    pub receiver_beacons: u64,
+    pub aircraft_beacons: u64,
+    pub server_comments: u64,
+    // Per beacon flavor, e.g. "Flarm"
+    pub flavors: BTreeMap<String, u64>,
+    // Per error kind, e.g. "InvalidPosition"
+    pub errors: BTreeMap<String, u64>,
+    // The resolved packet timestamps in seconds since 1970-01-01
+    pub earliest: Option<i64>,
+    pub latest: Option<i64>,
+}
+
+impl BatchSummary {
+    // reference_time: seconds since 1970-01-01 used to resolve the APRS timestamps
+    pub fn add(&mut self, message: &Message, reference_time: i64) {
+        self.messages += 1;
+        if message.server_comment.is_some() {
+            self.server_comments += 1;
+            return;
+        }
+        let packet = match &message.aprs_packet {
+            Ok(packet) => packet,
+            Err(error) => {
+                *self.errors.entry(error_kind(error)).or_default() += 1;
+                return;
+            }
+        };
+        if let Some(flavor) = message.flavor {
+            *self.flavors.entry(format!("{:?}", flavor)).or_default() += 1;
+        }
+        if is_receiver(packet) {
+            self.receiver_beacons += 1;
+        } else if message.flavor.is_some_and(|flavor| flavor.is_aircraft())
+            || message
+                .position_comment
+                .as_ref()
+                .is_some_and(|comment| comment.id.is_some())
+        {
+            self.aircraft_beacons += 1;
+        }
+        if let Some(time) =
+            packet_timestamp(message).and_then(|timestamp| time::resolve(timestamp, reference_time))
+        {
+            self.earliest = Some(self.earliest.map_or(time, |earliest| earliest.min(time)));
+            self.latest = Some(self.latest.map_or(time, |latest| latest.max(time)));
+        }
+    }
+}
+
+pub fn parse_batch<'a, I: IntoIterator<Item = &'a str>>(lines: I) -> Vec<Message> {
+    lines
+        .into_iter()
+        .map(|line| line.parse::<Message>().unwrap())
+        .collect()
+}
+
+pub fn parse_batch_with_summary<'a, I: IntoIterator<Item = &'a str>>(
+    lines: I,
+    reference_time: i64,
+) -> (Vec<Message>, BatchSummary) {
+    let mut summary = BatchSummary::default();
+    let messages = lines
+        .into_iter()
+        .map(|line| {
+            let message = line.parse::<Message>().unwrap();
+            summary.add(&message, reference_time);
+            message
+        })
+        .collect();
+    (messages, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_summary() {
+        let lines = [
+            r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074610h4714.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+            r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765",
+            "not an aprs packet",
+            "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152",
+        ];
+        // 2024-08-01 08:00:00
+        let (messages, summary) = parse_batch_with_summary(lines, 1722499200);
+        assert_eq!(messages, parse_batch(lines));
+        assert_eq!(summary.messages, 5);
+        assert_eq!((summary.receiver_beacons, summary.aircraft_beacons), (1, 2));
+        assert_eq!(summary.server_comments, 1);
+        assert_eq!(summary.flavors["Flarm"], 1);
+        assert_eq!(summary.flavors["Receiver"], 1);
+        assert_eq!(summary.errors.values().sum::<u64>(), 1);
+        assert_eq!(summary.earliest, Some(1722498348));
+        assert_eq!(summary.latest, Some(1722498370));
+    }
+}
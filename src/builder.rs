@@ -0,0 +1,369 @@
+use crate::callsign::Callsign;
+use crate::error::AprsError;
+use crate::packet::{AprsData, AprsPacket};
+use crate::position::AprsPosition;
+use crate::status::AprsStatus;
+use crate::status_comment::StatusComment;
+use crate::timestamp::Timestamp;
+
+// The altitude "/A=" has 6 digits, negative altitudes are sent as "-12345"
+const MAX_ALTITUDE: i32 = 999_999;
+const MIN_ALTITUDE: i32 = -99_999;
+
+// Builds the position of a beacon the way OGN devices send it:
+//     AprsPositionBuilder::new(47.148, 6.894)
+//         .with_timestamp(Timestamp::HHMMSS(7, 45, 48))
+//         .with_course_speed(86, 7)
+//         .with_altitude(607)
+//         .with_comment("id06DDFAA3")
+//         .build_packet("FLRDDFAA3", "OGFLR", &[])
+// FLRDDFAA3>OGFLR:/074548h4708.88N/00653.64E'086/007/A=000607 id06DDFAA3
+#[derive(Debug, PartialEq, Clone)]
+pub struct AprsPositionBuilder {
+    latitude: f64,
+    longitude: f64,
+    timestamp: Option<Timestamp>,
+    symbol_table: char,
+    symbol_code: char,
+    course_speed: Option<(u16, u16)>,
+    altitude: Option<i32>,
+    comment: Option<String>,
+}
+
+impl AprsPositionBuilder {
+    // The symbol defaults to the glider "/'"
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        AprsPositionBuilder {
+            latitude,
+            longitude,
+            timestamp: None,
+            symbol_table: '/',
+            symbol_code: '\'',
+            course_speed: None,
+            altitude: None,
+            comment: None,
+        }
+    }
+
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_symbol(mut self, symbol_table: char, symbol_code: char) -> Self {
+        self.symbol_table = symbol_table;
+        self.symbol_code = symbol_code;
+        self
+    }
+
+    // course in degrees 1-360 (0: unknown), speed in knots
+    pub fn with_course_speed(mut self, course: u16, speed: u16) -> Self {
+        self.course_speed = Some((course, speed));
+        self
+    }
+
+    // in feet
+    pub fn with_altitude(mut self, altitude: i32) -> Self {
+        self.altitude = Some(altitude);
+        self
+    }
+
+    // The OGN tokens after the altitude, e.g. "id06DDFAA3 -019fpm +0.0rot"
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<AprsPosition, AprsError> {
+        let invalid = |reason: String| Err(AprsError::InvalidPosition(reason));
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(AprsError::InvalidLatitude(self.latitude.to_string()));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(AprsError::InvalidLongitude(self.longitude.to_string()));
+        }
+        if let Some(timestamp) = &self.timestamp {
+            validate_timestamp(timestamp)?;
+        }
+        if !(matches!(self.symbol_table, '/' | '\\') || self.symbol_table.is_ascii_alphanumeric())
+            || !self.symbol_code.is_ascii_graphic()
+        {
+            return invalid(format!("symbol {}{}", self.symbol_table, self.symbol_code));
+        }
+
+        let mut comment = String::new();
+        if let Some((course, speed)) = self.course_speed {
+            if course > 360 || speed > 999 {
+                return invalid(format!("course/speed {}/{}", course, speed));
+            }
+            comment.push_str(&format!("{:03}/{:03}", course, speed));
+        }
+        if let Some(altitude) = self.altitude {
+            if !(MIN_ALTITUDE..=MAX_ALTITUDE).contains(&altitude) {
+                return invalid(format!("altitude {}", altitude));
+            }
+            comment.push_str(&format!("/A={:06}", altitude));
+        }
+        if let Some(text) = &self.comment {
+            // Directly after the symbol "ddd/ddd" would be parsed as course and speed
+            if text.contains(['\r', '\n']) || (comment.is_empty() && is_course_speed(text)) {
+                return invalid(format!("comment {:?}", text));
+            }
+            if !comment.is_empty() {
+                comment.push(' ');
+            }
+            comment.push_str(text);
+        }
+        Ok(AprsPosition {
+            timestamp: self.timestamp,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            symbol_table: self.symbol_table,
+            symbol_code: self.symbol_code,
+            compression: None,
+            mic_e: None,
+            ambiguity: 0,
+            comment,
+        })
+    }
+
+    pub fn build_packet(self, from: &str, to: &str, via: &[&str]) -> Result<AprsPacket, AprsError> {
+        let data = AprsData::Position(self.build()?);
+        build_packet(from, to, via, data)
+    }
+}
+
+// Builds the status of a receiver, e.g. from a StatusComment:
+//     AprsStatusBuilder::new()
+//         .with_timestamp(Timestamp::HHMMSS(7, 46, 0))
+//         .with_status_comment(&status_comment)
+//         .build_packet("Letzi", "OGNSDR", &["TCPIP*"])
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct AprsStatusBuilder {
+    timestamp: Option<Timestamp>,
+    comment: String,
+}
+
+impl AprsStatusBuilder {
+    pub fn new() -> Self {
+        AprsStatusBuilder::default()
+    }
+
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = comment.to_string();
+        self
+    }
+
+    pub fn with_status_comment(mut self, status_comment: &StatusComment) -> Self {
+        self.comment = status_comment.to_string();
+        self
+    }
+
+    pub fn build(self) -> Result<AprsStatus, AprsError> {
+        if let Some(timestamp) = &self.timestamp {
+            validate_timestamp(timestamp)?;
+        }
+        // Without a timestamp a comment starting like one would be parsed as one
+        if self.comment.contains(['\r', '\n'])
+            || (self.timestamp.is_none() && starts_with_timestamp(&self.comment))
+        {
+            return Err(AprsError::InvalidPacket(self.comment));
+        }
+        // Without the separating space a comment starting with 7 characters like
+        // a timestamp would be mistaken for one
+        let comment = match (&self.timestamp, self.comment.is_empty()) {
+            (Some(_), false) => format!(" {}", self.comment),
+            _ => self.comment,
+        };
+        Ok(AprsStatus {
+            timestamp: self.timestamp,
+            comment,
+        })
+    }
+
+    pub fn build_packet(self, from: &str, to: &str, via: &[&str]) -> Result<AprsPacket, AprsError> {
+        let data = AprsData::Status(self.build()?);
+        build_packet(from, to, via, data)
+    }
+}
+
+fn is_course_speed(s: &str) -> bool {
+    let digits = |range: std::ops::Range<usize>| {
+        s.get(range)
+            .is_some_and(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+    };
+    digits(0..3) && s.get(3..4) == Some("/") && digits(4..7)
+}
+
+fn starts_with_timestamp(s: &str) -> bool {
+    s.get(..7)
+        .is_some_and(|timestamp| timestamp.parse::<Timestamp>().is_ok())
+}
+
+fn validate_timestamp(timestamp: &Timestamp) -> Result<(), AprsError> {
+    let valid = match *timestamp {
+        Timestamp::DDHHMM(day, hours, minutes) => {
+            (1..=31).contains(&day) && hours < 24 && minutes < 60
+        }
+        Timestamp::HHMMSS(hours, minutes, seconds) => hours < 24 && minutes < 60 && seconds < 60,
+        // We don't write local time
+        Timestamp::Unsupported(_) => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AprsError::InvalidTimestamp(timestamp.to_string()))
+    }
+}
+
+fn build_packet(
+    from: &str,
+    to: &str,
+    via: &[&str],
+    data: AprsData,
+) -> Result<AprsPacket, AprsError> {
+    Ok(AprsPacket {
+        from: callsign(from)?,
+        to: callsign(to)?,
+        via: via
+            .iter()
+            .map(|via| callsign(via))
+            .collect::<Result<Vec<_>, _>>()?,
+        data,
+    })
+}
+
+// The parser takes anything up to the separators, a written header must not contain them
+fn callsign(s: &str) -> Result<Callsign, AprsError> {
+    if s.contains(|c: char| matches!(c, '>' | ',' | ':') || c.is_whitespace()) {
+        return Err(AprsError::InvalidPacket(s.to_string()));
+    }
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+
+    #[test]
+    fn test_position_builder() {
+        let packet = AprsPositionBuilder::new(47.148666666666664, 6.8945)
+            .with_timestamp(Timestamp::HHMMSS(7, 45, 48))
+            .with_course_speed(86, 7)
+            .with_altitude(607)
+            .with_comment("id06DDFAA3 -019fpm")
+            .build_packet("FLRDDFAA3", "OGFLR", &[])
+            .unwrap();
+        assert_eq!(
+            packet.to_string(),
+            r"FLRDDFAA3>OGFLR:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3 -019fpm"
+        );
+        let message = packet.to_string().parse::<Message>().unwrap();
+        let position_comment = message.position_comment.unwrap();
+        assert_eq!(position_comment.altitude, Some(607));
+        assert_eq!(position_comment.climb_rate, Some(-19));
+
+        let position = AprsPositionBuilder::new(-33.5, -70.25)
+            .with_symbol('\\', '^')
+            .with_altitude(-120)
+            .build()
+            .unwrap();
+        assert_eq!(position.to_string(), r"!3330.00S\07015.00W^/A=-00120");
+    }
+
+    #[test]
+    fn test_position_validation() {
+        let build = |builder: AprsPositionBuilder| builder.build().unwrap_err();
+        assert_eq!(
+            build(AprsPositionBuilder::new(90.5, 0.0)),
+            AprsError::InvalidLatitude("90.5".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, -181.0)),
+            AprsError::InvalidLongitude("-181".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, 0.0).with_altitude(1_000_000)),
+            AprsError::InvalidPosition("altitude 1000000".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, 0.0).with_course_speed(361, 0)),
+            AprsError::InvalidPosition("course/speed 361/0".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, 0.0).with_timestamp(Timestamp::HHMMSS(24, 0, 0))),
+            AprsError::InvalidTimestamp("240000h".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, 0.0).with_symbol('#', '\'')),
+            AprsError::InvalidPosition("symbol #'".to_string())
+        );
+        assert_eq!(
+            build(AprsPositionBuilder::new(0.0, 0.0).with_comment("086/007 id06DDFAA3")),
+            AprsError::InvalidPosition("comment \"086/007 id06DDFAA3\"".to_string())
+        );
+        // After the altitude it is no course and speed
+        let position = AprsPositionBuilder::new(0.0, 0.0)
+            .with_altitude(607)
+            .with_comment("086/007")
+            .build()
+            .unwrap();
+        assert_eq!(position.comment, "/A=000607 086/007");
+        assert_eq!(
+            AprsPositionBuilder::new(0.0, 0.0).build_packet("FLR DDFAA3", "OGFLR", &[]),
+            Err(AprsError::InvalidPacket("FLR DDFAA3".to_string()))
+        );
+        assert_eq!(
+            AprsPositionBuilder::new(0.0, 0.0).build_packet("FLRDDFAA3", "OGFLR", &[""]),
+            Err(AprsError::EmptyCallsign("".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_status_builder() {
+        let status_comment = "v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB"
+            .parse::<StatusComment>()
+            .unwrap();
+        let packet = AprsStatusBuilder::new()
+            .with_timestamp(Timestamp::HHMMSS(7, 46, 0))
+            .with_status_comment(&status_comment)
+            .build_packet("Letzi", "OGNSDR", &["TCPIP*"])
+            .unwrap();
+        assert_eq!(
+            packet.to_string(),
+            "Letzi>OGNSDR,TCPIP*:>074600h v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB"
+        );
+        let message = packet.to_string().parse::<Message>().unwrap();
+        assert_eq!(message.status_comment, Some(status_comment));
+
+        assert_eq!(
+            AprsStatusBuilder::new()
+                .with_timestamp(Timestamp::DDHHMM(0, 12, 0))
+                .build(),
+            Err(AprsError::InvalidTimestamp("001200z".to_string()))
+        );
+        assert!(AprsStatusBuilder::new()
+            .with_comment("two\nlines")
+            .build()
+            .is_err());
+
+        // Without a timestamp the comment must not start with one
+        assert_eq!(
+            AprsStatusBuilder::new()
+                .with_comment("074600h v0.2.7")
+                .build(),
+            Err(AprsError::InvalidPacket("074600h v0.2.7".to_string()))
+        );
+        let status = AprsStatusBuilder::new()
+            .with_comment("v0.2.7 074600h")
+            .build()
+            .unwrap();
+        assert_eq!(status.comment.parse::<AprsStatus>().unwrap(), status);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::error::AprsError;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct Callsign {
+    pub call: String,
+    pub ssid: Option<String>,
+}
+
+impl Callsign {
+    pub fn new<T: Into<String>>(call: T, ssid: Option<T>) -> Callsign {
+        Callsign {
+            call: call.into(),
+            ssid: ssid.map(Into::into),
+        }
+    }
+
+    // The 3 letter prefix of OGN device callsigns, e.g. "FLR" of FLRDDFAA3
+    pub fn device_prefix(&self) -> Option<&str> {
+        split_device_callsign(&self.call).map(|(prefix, _)| prefix)
+    }
+
+    // The 24 bit address of OGN device callsigns, e.g. 0xDDFAA3 of FLRDDFAA3
+    pub fn device_address(&self) -> Option<u32> {
+        split_device_callsign(&self.call)
+            .and_then(|(_, address)| u32::from_str_radix(address, 16).ok())
+    }
+}
+
+// OGN devices send with a callsign of 3 uppercase letters and 6 hex digits
+pub(crate) fn split_device_callsign(call: &str) -> Option<(&str, &str)> {
+    let valid = call.len() == 9
+        && call.is_ascii()
+        && call[..3].chars().all(|c| c.is_ascii_uppercase())
+        && call[3..].chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| call.split_at(3))
+}
+
+impl FromStr for Callsign {
+    type Err = AprsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (call, ssid) = match s.split_once('-') {
+            Some((call, ssid)) => (call, Some(ssid)),
+            None => (s, None),
+        };
+        if call.is_empty() {
+            return Err(AprsError::EmptyCallsign(s.to_string()));
+        }
+        if ssid == Some("") {
+            return Err(AprsError::EmptyCallsignSSID(s.to_string()));
+        }
+        Ok(Callsign::new(call, ssid))
+    }
+}
+
+impl Display for Callsign {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.call)?;
+        if let Some(ssid) = &self.ssid {
+            write!(f, "-{}", ssid)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_callsign() {
+    assert_eq!("Letzi".parse(), Ok(Callsign::new("Letzi", None)));
+    assert_eq!("DL4MEA-8".parse(), Ok(Callsign::new("DL4MEA", Some("8"))));
+    assert_eq!(Callsign::new("DL4MEA", Some("8")).to_string(), "DL4MEA-8");
+    assert_eq!(
+        "-8".parse::<Callsign>(),
+        Err(AprsError::EmptyCallsign("-8".to_string()))
+    );
+    assert_eq!(
+        "DL4MEA-".parse::<Callsign>(),
+        Err(AprsError::EmptyCallsignSSID("DL4MEA-".to_string()))
+    );
+}
+
+#[test]
+fn test_device_callsign() {
+    let callsign = "FLRDDFAA3".parse::<Callsign>().unwrap();
+    assert_eq!(callsign.device_prefix(), Some("FLR"));
+    assert_eq!(callsign.device_address(), Some(0xDDFAA3));
+    let callsign = "ICA3d17f2-1".parse::<Callsign>().unwrap();
+    assert_eq!(callsign.device_prefix(), Some("ICA"));
+    assert_eq!(callsign.device_address(), Some(0x3D17F2));
+
+    for call in [
+        "Letzi",
+        "DL4MEA-8",
+        "FLRDDFAAX",
+        "flrDDFAA3",
+        "FLRDDFAA30",
+        "FLRDDF\u{e4}A",
+    ] {
+        let callsign = call.parse::<Callsign>().unwrap();
+        assert_eq!(callsign.device_prefix(), None);
+        assert_eq!(callsign.device_address(), None);
+    }
+}
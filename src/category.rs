@@ -0,0 +1,340 @@
+// Mapping between the OGN/FLARM aircraft types (the tttt bits of the id) and the
+// ADS-B emitter categories (DO-260B, as used by GDL90), so mixed sources share one taxonomy
+
+use serde::Serialize;
+
+use crate::message::Message;
+use crate::packet::AprsData;
+
+// OGN/FLARM aircraft types
+pub const UNKNOWN: u8 = 0;
+pub const GLIDER: u8 = 1;
+pub const TOW_PLANE: u8 = 2;
+pub const HELICOPTER: u8 = 3;
+pub const SKYDIVER: u8 = 4;
+pub const DROP_PLANE: u8 = 5;
+pub const HANG_GLIDER: u8 = 6;
+pub const PARAGLIDER: u8 = 7;
+pub const POWERED_AIRCRAFT: u8 = 8;
+pub const JET_AIRCRAFT: u8 = 9;
+pub const UFO: u8 = 10;
+pub const BALLOON: u8 = 11;
+pub const AIRSHIP: u8 = 12;
+pub const UAV: u8 = 13;
+pub const GROUND_SUPPORT: u8 = 14;
+pub const STATIC_OBSTACLE: u8 = 15;
+
+// ADS-B emitter categories
+pub const EMITTER_NO_INFORMATION: u8 = 0;
+pub const EMITTER_LIGHT: u8 = 1;
+pub const EMITTER_SMALL: u8 = 2;
+pub const EMITTER_LARGE: u8 = 3;
+pub const EMITTER_HIGH_VORTEX_LARGE: u8 = 4;
+pub const EMITTER_HEAVY: u8 = 5;
+pub const EMITTER_HIGH_PERFORMANCE: u8 = 6;
+pub const EMITTER_ROTORCRAFT: u8 = 7;
+pub const EMITTER_GLIDER: u8 = 9;
+pub const EMITTER_LIGHTER_THAN_AIR: u8 = 10;
+pub const EMITTER_PARACHUTIST: u8 = 11;
+pub const EMITTER_ULTRALIGHT: u8 = 12;
+pub const EMITTER_UAV: u8 = 14;
+pub const EMITTER_SPACE: u8 = 15;
+pub const EMITTER_SURFACE_EMERGENCY: u8 = 17;
+pub const EMITTER_SURFACE_SERVICE: u8 = 18;
+pub const EMITTER_POINT_OBSTACLE: u8 = 19;
+pub const EMITTER_CLUSTER_OBSTACLE: u8 = 20;
+pub const EMITTER_LINE_OBSTACLE: u8 = 21;
+
+pub fn emitter_category(aircraft_type: u8) -> u8 {
+    match aircraft_type {
+        GLIDER => EMITTER_GLIDER,
+        TOW_PLANE | DROP_PLANE | POWERED_AIRCRAFT => EMITTER_LIGHT,
+        HELICOPTER => EMITTER_ROTORCRAFT,
+        SKYDIVER => EMITTER_PARACHUTIST,
+        HANG_GLIDER | PARAGLIDER => EMITTER_ULTRALIGHT,
+        JET_AIRCRAFT => EMITTER_LARGE,
+        BALLOON | AIRSHIP => EMITTER_LIGHTER_THAN_AIR,
+        UAV => EMITTER_UAV,
+        GROUND_SUPPORT => EMITTER_SURFACE_SERVICE,
+        STATIC_OBSTACLE => EMITTER_POINT_OBSTACLE,
+        _ => EMITTER_NO_INFORMATION,
+    }
+}
+
+// Categories covering several aircraft types map to the most common one in OGN,
+// e.g. "ultralight / hang glider / paraglider" to paraglider
+pub fn aircraft_type(emitter_category: u8) -> u8 {
+    match emitter_category {
+        EMITTER_LIGHT | EMITTER_SMALL => POWERED_AIRCRAFT,
+        EMITTER_LARGE | EMITTER_HIGH_VORTEX_LARGE | EMITTER_HEAVY | EMITTER_HIGH_PERFORMANCE => {
+            JET_AIRCRAFT
+        }
+        EMITTER_ROTORCRAFT => HELICOPTER,
+        EMITTER_GLIDER => GLIDER,
+        EMITTER_LIGHTER_THAN_AIR => BALLOON,
+        EMITTER_PARACHUTIST => SKYDIVER,
+        EMITTER_ULTRALIGHT => PARAGLIDER,
+        EMITTER_UAV => UAV,
+        EMITTER_SURFACE_EMERGENCY | EMITTER_SURFACE_SERVICE => GROUND_SUPPORT,
+        EMITTER_POINT_OBSTACLE | EMITTER_CLUSTER_OBSTACLE | EMITTER_LINE_OBSTACLE => {
+            STATIC_OBSTACLE
+        }
+        _ => UNKNOWN,
+    }
+}
+
+pub fn aircraft_type_name(aircraft_type: u8) -> &'static str {
+    match aircraft_type {
+        GLIDER => "glider",
+        TOW_PLANE => "tow plane",
+        HELICOPTER => "helicopter",
+        SKYDIVER => "skydiver",
+        DROP_PLANE => "drop plane",
+        HANG_GLIDER => "hang glider",
+        PARAGLIDER => "paraglider",
+        POWERED_AIRCRAFT => "powered aircraft",
+        JET_AIRCRAFT => "jet aircraft",
+        UFO => "ufo",
+        BALLOON => "balloon",
+        AIRSHIP => "airship",
+        UAV => "uav",
+        GROUND_SUPPORT => "ground support",
+        STATIC_OBSTACLE => "static obstacle",
+        _ => "unknown",
+    }
+}
+
+// The APRS symbols the OGN devices send for the aircraft types. Symbols shared by
+// several types map to the most common one, "/z" (unknown, UFO, ground support) to none.
+pub fn aircraft_type_from_symbol(symbol_table: char, symbol_code: char) -> Option<u8> {
+    match (symbol_table, symbol_code) {
+        ('/', '\'') => Some(GLIDER),
+        ('/', 'X') => Some(HELICOPTER),
+        ('/', 'g') => Some(PARAGLIDER),
+        ('\\', '^') => Some(POWERED_AIRCRAFT),
+        ('/', '^') => Some(JET_AIRCRAFT),
+        ('/', 'O') => Some(BALLOON),
+        ('\\', 'n') => Some(STATIC_OBSTACLE),
+        _ => None,
+    }
+}
+
+// What the APRS symbol of a station stands for
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize)]
+pub enum SymbolClass {
+    Glider,
+    Paraglider,
+    PoweredAircraft,
+    JetAircraft,
+    Helicopter,
+    Receiver,
+    WeatherStation,
+    Uav,
+    Balloon,
+}
+
+impl SymbolClass {
+    // The aircraft symbols are those of aircraft_type_from_symbol, the other symbols are
+    // the common APRS ones, overlays included: "I&" is an igate, "D^" a drone.
+    pub fn from_symbol(symbol_table: char, symbol_code: char) -> Option<SymbolClass> {
+        if let Some(aircraft_type) = aircraft_type_from_symbol(symbol_table, symbol_code) {
+            return SymbolClass::from_aircraft_type(aircraft_type);
+        }
+        let overlay = symbol_table.is_ascii_alphanumeric();
+        match (symbol_table, symbol_code) {
+            ('D' | 'R' | 'A', '^') => Some(SymbolClass::Uav),
+            (_, '^') if overlay => Some(SymbolClass::PoweredAircraft),
+            ('/', 'X') => Some(SymbolClass::Helicopter),
+            ('/' | '\\', '&') => Some(SymbolClass::Receiver),
+            (_, '&') if overlay => Some(SymbolClass::Receiver),
+            ('/' | '\\', '_') => Some(SymbolClass::WeatherStation),
+            (_, '_') if overlay => Some(SymbolClass::WeatherStation),
+            _ => None,
+        }
+    }
+
+    pub fn from_aircraft_type(aircraft_type: u8) -> Option<SymbolClass> {
+        match aircraft_type {
+            GLIDER => Some(SymbolClass::Glider),
+            HANG_GLIDER | PARAGLIDER => Some(SymbolClass::Paraglider),
+            TOW_PLANE | DROP_PLANE | POWERED_AIRCRAFT => Some(SymbolClass::PoweredAircraft),
+            JET_AIRCRAFT => Some(SymbolClass::JetAircraft),
+            HELICOPTER => Some(SymbolClass::Helicopter),
+            BALLOON | AIRSHIP => Some(SymbolClass::Balloon),
+            UAV => Some(SymbolClass::Uav),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolClass::Glider => "glider",
+            SymbolClass::Paraglider => "paraglider",
+            SymbolClass::PoweredAircraft => "powered aircraft",
+            SymbolClass::JetAircraft => "jet aircraft",
+            SymbolClass::Helicopter => "helicopter",
+            SymbolClass::Receiver => "receiver",
+            SymbolClass::WeatherStation => "weather station",
+            SymbolClass::Uav => "uav",
+            SymbolClass::Balloon => "balloon",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct AircraftClassification {
+    pub aircraft_type: u8,
+    // Not sent in the id but derived from the symbol, e.g. for FANET or SafeSky traffic
+    pub inferred: bool,
+}
+
+pub fn classify(message: &Message) -> Option<AircraftClassification> {
+    let packet = message.aprs_packet.as_ref().ok()?;
+    let AprsData::Position(position) = &packet.data else {
+        return None;
+    };
+    if let Some(id) = message
+        .position_comment
+        .as_ref()
+        .and_then(|comment| comment.id.as_ref())
+    {
+        return Some(AircraftClassification {
+            aircraft_type: id.aircraft_type,
+            inferred: false,
+        });
+    }
+    aircraft_type_from_symbol(position.symbol_table, position.symbol_code).map(|aircraft_type| {
+        AircraftClassification {
+            aircraft_type,
+            inferred: true,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emitter_category() {
+        assert_eq!(emitter_category(GLIDER), 9);
+        assert_eq!(emitter_category(POWERED_AIRCRAFT), 1);
+        assert_eq!(emitter_category(UFO), 0);
+        assert_eq!(emitter_category(16), 0);
+        assert_eq!(aircraft_type(EMITTER_HEAVY), JET_AIRCRAFT);
+        assert_eq!(aircraft_type(EMITTER_SPACE), UNKNOWN);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        // Every category we produce maps back to an aircraft type of the same category
+        for aircraft_type in 0..16 {
+            let category = emitter_category(aircraft_type);
+            assert_eq!(emitter_category(super::aircraft_type(category)), category);
+        }
+        for category in 0..40 {
+            let aircraft_type = aircraft_type(category);
+            assert_eq!(
+                super::aircraft_type(emitter_category(aircraft_type)),
+                aircraft_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_symbol_class() {
+        assert_eq!(
+            SymbolClass::from_symbol('/', '\''),
+            Some(SymbolClass::Glider)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('\\', '^'),
+            Some(SymbolClass::PoweredAircraft)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('J', '^'),
+            Some(SymbolClass::PoweredAircraft)
+        );
+        assert_eq!(SymbolClass::from_symbol('D', '^'), Some(SymbolClass::Uav));
+        assert_eq!(
+            SymbolClass::from_symbol('/', 'X'),
+            Some(SymbolClass::Helicopter)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('/', '&'),
+            Some(SymbolClass::Receiver)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('I', '&'),
+            Some(SymbolClass::Receiver)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('/', '_'),
+            Some(SymbolClass::WeatherStation)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('/', 'O'),
+            Some(SymbolClass::Balloon)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('/', 'g'),
+            Some(SymbolClass::Paraglider)
+        );
+        assert_eq!(
+            SymbolClass::from_symbol('/', '^'),
+            Some(SymbolClass::JetAircraft)
+        );
+        assert_eq!(SymbolClass::from_symbol('/', 'z'), None);
+        assert_eq!(SymbolClass::from_symbol('\\', 'O'), None);
+        assert_eq!(SymbolClass::WeatherStation.name(), "weather station");
+
+        // Both agree on the aircraft symbols
+        for symbol_table in ['/', '\\'] {
+            for symbol_code in '!'..='~' {
+                if let Some(aircraft_type) = aircraft_type_from_symbol(symbol_table, symbol_code) {
+                    assert_eq!(
+                        SymbolClass::from_symbol(symbol_table, symbol_code),
+                        SymbolClass::from_aircraft_type(aircraft_type)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        // The id wins over the symbol
+        let message =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id2CDDFAA3"
+                .parse::<Message>()
+                .unwrap();
+        assert_eq!(
+            classify(&message),
+            Some(AircraftClassification {
+                aircraft_type: BALLOON,
+                inferred: false,
+            })
+        );
+
+        let message = r"FNT1103CE>OGNFNT,qAS,FNB1103CE:/183734h5057.94N/00801.00Eg355/002/A=001042 !W10! +000fpm 12.3dB"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(
+            classify(&message),
+            Some(AircraftClassification {
+                aircraft_type: PARAGLIDER,
+                inferred: true,
+            })
+        );
+
+        let message =
+            r"SKY3E5906>OGNSKY,qAS,SafeSky:/072553h5103.47N/00524.54E/z/A=001260 !W48! 5.0dB"
+                .parse::<Message>()
+                .unwrap();
+        assert_eq!(classify(&message), None);
+        let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(classify(&message), None);
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::message::Message;
+use crate::packet::AprsData;
+
+// python-ogn-client converts to metric units
+const FEET_TO_METERS: f64 = 0.3048;
+const KNOTS_TO_KMH: f64 = 1.852;
+const FPM_TO_MS: f64 = 0.00508;
+// rot is half turns per minute
+const ROT_TO_DEGS: f64 = 3.0;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Disagreement {
+    pub field: String,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+// Compares our parse result to the dict returned by python-ogn-client's `ogn.parser.parse`.
+// Only fields which both parsers know are compared.
+pub fn compare(message: &Message, theirs: &Value) -> Vec<Disagreement> {
+    let ours = to_ogn_python(message);
+    let mut disagreements = ours
+        .iter()
+        .filter_map(|(field, value)| {
+            let other = theirs.get(field.as_str()).unwrap_or(&Value::Null);
+            (!equivalent(value, other)).then(|| Disagreement {
+                field: field.clone(),
+                ours: value.clone(),
+                theirs: other.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    disagreements.sort_by(|a, b| a.field.cmp(&b.field));
+    disagreements
+}
+
+// Our result with the field names and units of python-ogn-client
+pub fn to_ogn_python(message: &Message) -> Map<String, Value> {
+    let mut result = Map::new();
+    let mut insert = |field: &str, value: Value| {
+        result.insert(field.to_string(), value);
+    };
+
+    let packet = match &message.aprs_packet {
+        Ok(packet) => packet,
+        Err(_) => return result,
+    };
+    insert("name", Value::from(packet.from.to_string()));
+    insert("dstcall", Value::from(packet.to.to_string()));
+    if let Some(receiver) = packet.via.last() {
+        insert("receiver_name", Value::from(receiver.to_string()));
+    }
+    if let AprsData::Position(position) = &packet.data {
+        insert("latitude", Value::from(position.latitude));
+        insert("longitude", Value::from(position.longitude));
+    }
+
+    if let Some(comment) = &message.position_comment {
+        let scaled = |value: Option<f64>, factor: f64| value.map(|value| value * factor).into();
+        insert("track", comment.course.into());
+        insert(
+            "ground_speed",
+            scaled(comment.speed.map(f64::from), KNOTS_TO_KMH),
+        );
+        insert(
+            "altitude",
+            scaled(comment.altitude.map(f64::from), FEET_TO_METERS),
+        );
+        insert(
+            "climb_rate",
+            scaled(comment.climb_rate.map(f64::from), FPM_TO_MS),
+        );
+        insert(
+            "turn_rate",
+            scaled(comment.turn_rate.map(f64::from), ROT_TO_DEGS),
+        );
+        insert("signal_quality", comment.signal_quality.into());
+        insert("error_count", comment.error.into());
+        insert("frequency_offset", comment.frequency_offset.into());
+        insert("signal_power", comment.signal_power.into());
+        insert("software_version", comment.software_version.into());
+        insert("hardware_version", comment.hardware_version.into());
+        insert(
+            "real_address",
+            comment
+                .original_address
+                .map(|address| format!("{:06X}", address))
+                .into(),
+        );
+        if let Some(id) = &comment.id {
+            insert("address_type", u8::from(id.address_type).into());
+            insert("aircraft_type", id.aircraft_type.into());
+            insert("stealth", id.is_stealth.into());
+            insert("no-tracking", id.is_notrack.into());
+            insert("address", format!("{:06X}", id.address).into());
+        }
+    }
+
+    if let Some(comment) = &message.status_comment {
+        insert("version", comment.version.clone().into());
+        insert("platform", comment.platform.clone().into());
+        insert("cpu_load", comment.cpu_load.into());
+        insert("free_ram", comment.ram_free.into());
+        insert("total_ram", comment.ram_total.into());
+        insert("voltage", comment.voltage.into());
+        insert("amperage", comment.amperage.into());
+        insert("cpu_temp", comment.cpu_temperature.into());
+        insert("senders_visible", comment.visible_senders.into());
+        insert("senders_total", comment.senders.into());
+        insert("rec_input_noise", comment.noise.into());
+        insert("senders_messages", comment.senders_messages.into());
+        insert("good_senders", comment.good_senders.into());
+        insert("good_and_bad_senders", comment.good_and_bad_senders.into());
+    }
+    result
+}
+
+// Numbers are compared with a tolerance because of the unit conversions and f32 values
+fn equivalent(ours: &Value, theirs: &Value) -> bool {
+    match (ours.as_f64(), theirs.as_f64()) {
+        (Some(ours), Some(theirs)) => (ours - theirs).abs() <= 0.01_f64.max(theirs.abs() * 0.001),
+        _ => ours == theirs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare() {
+        let message = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm +0.0rot 5.5dB 3e -4.3kHz"
+            .parse::<Message>()
+            .unwrap();
+        let mut theirs = json!({
+            "name": "FLRDDFAA3",
+            "dstcall": "APRS",
+            "receiver_name": "Letzi",
+            "latitude": 51.18866666666667,
+            "longitude": -1.034,
+            "track": 86,
+            "ground_speed": 12.964,
+            "altitude": 185.0136,
+            "climb_rate": -0.09652,
+            "turn_rate": 0.0,
+            "signal_quality": 5.5,
+            "error_count": 3,
+            "frequency_offset": -4.3,
+            "address_type": 2,
+            "aircraft_type": 2,
+            "stealth": false,
+            "no-tracking": false,
+            "address": "DDFAA3",
+        });
+        assert_eq!(compare(&message, &theirs), vec![]);
+
+        theirs["altitude"] = json!(607);
+        theirs.as_object_mut().unwrap().remove("address");
+        let disagreements = compare(&message, &theirs);
+        assert_eq!(
+            disagreements
+                .iter()
+                .map(|disagreement| disagreement.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["address", "altitude"]
+        );
+        assert_eq!(disagreements[0].theirs, Value::Null);
+        assert_eq!(disagreements[1].theirs, json!(607));
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+use crate::callsign::split_device_callsign;
+use crate::inspect::inspect;
+use crate::message::Message;
+use crate::packet::AprsHeader;
+
+// Collects anonymized test fixtures, one per packet layout, grouped by tocall
+pub struct CorpusBuilder {
+    salt: u64,
+    max_per_group: usize,
+    seen: HashSet<String>,
+    groups: BTreeMap<String, Vec<String>>,
+}
+
+impl CorpusBuilder {
+    pub fn new(salt: u64, max_per_group: usize) -> Self {
+        CorpusBuilder {
+            salt,
+            max_per_group,
+            seen: HashSet::new(),
+            groups: BTreeMap::new(),
+        }
+    }
+
+    // Returns true if the line has a new layout and was added to the corpus
+    pub fn add(&mut self, line: &str) -> bool {
+        let report = inspect(line);
+        let tocall = report
+            .tocall
+            .clone()
+            .unwrap_or_else(|| "invalid".to_string());
+        let group = self.groups.entry(tocall.clone()).or_default();
+        if group.len() >= self.max_per_group {
+            return false;
+        }
+
+        let layout = if report.parser_error.is_some() {
+            shape(line.split_once(':').map_or(line, |(_, body)| body))
+        } else {
+            report
+                .tokens
+                .iter()
+                .map(|token| match token.fields.is_empty() {
+                    true => shape(&token.token),
+                    false => token.fields.join("+"),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let key = format!("{} {} {}", tocall, report.packet_type, layout);
+        if !self.seen.insert(key) {
+            return false;
+        }
+
+        group.push(anonymize(line, self.salt));
+        true
+    }
+
+    pub fn groups(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.groups
+    }
+
+    // Writes one file per tocall, e.g. APRS.txt
+    pub fn write_to_dir(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (tocall, lines) in &self.groups {
+            let filename = tocall
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>();
+            fs::write(
+                dir.join(format!("{}.txt", filename)),
+                lines.join("\n") + "\n",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Replaces the 24 bit device addresses (e.g. FLRDDFAA3) with salted pseudonyms,
+// so the fixture still parses the same way
+pub fn anonymize(line: &str, salt: u64) -> String {
+    let message = line.parse::<Message>().unwrap();
+    replace_addresses(&message, |address| {
+        let mut hasher = DefaultHasher::new();
+        (salt, format!("{:06X}", address)).hash(&mut hasher);
+        (hasher.finish() & 0xFFFFFF) as u32
+    })
+}
+
+// The raw string of the message with the pseudonyms of the device addresses, only in the
+// fields the parser takes them from: the device callsigns of the sender and the path,
+// and the id and r tokens of the position comment. The rest is kept as sent.
+pub(crate) fn replace_addresses(message: &Message, pseudonym: impl Fn(u32) -> u32) -> String {
+    let line = message.raw_string.as_str();
+    let Ok(header) = AprsHeader::parse(line) else {
+        return line.to_string();
+    };
+    // The callsigns of the path can have a SSID and the used flag, e.g. OGN123456*
+    let callsign = |call: &str| {
+        let (base, suffix) = call.split_at(call.find(['-', '*']).unwrap_or(call.len()));
+        let Some((prefix, address)) = split_device_callsign(base) else {
+            return call.to_string();
+        };
+        let address = u32::from_str_radix(address, 16).unwrap();
+        format!("{}{:06X}{}", prefix, pseudonym(address), suffix)
+    };
+    let path = [header.to]
+        .into_iter()
+        .chain(header.via.iter().copied())
+        .map(callsign)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    // Only the tokens with the parsed addresses are replaced
+    let comment = message.position_comment.as_ref();
+    let id = comment
+        .and_then(|comment| comment.id.as_ref())
+        .map(|id| id.address);
+    let original_address = comment.and_then(|comment| comment.original_address);
+    let token = |token: &str| {
+        let (offset, parsed) = match token.len() {
+            10 | 12 if token.starts_with("id") => (token.len() - 6, id),
+            7 if token.starts_with('r') => (1, original_address),
+            _ => return token.to_string(),
+        };
+        match u32::from_str_radix(&token[offset..], 16).ok() {
+            Some(address) if Some(address) == parsed => {
+                format!("{}{:06X}", &token[..offset], pseudonym(address))
+            }
+            _ => token.to_string(),
+        }
+    };
+    let information = header
+        .information
+        .split(' ')
+        .map(token)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!("{}>{}:{}", callsign(header.from), path, information)
+}
+
+// Replaces digits with 9 and letters with A, e.g. "gps2xFLRD0" becomes "aaa9aAAAA9"
+fn shape(token: &str) -> String {
+    token
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => '9',
+            'a'..='z' => 'a',
+            'A'..='Z' => 'A',
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+
+    #[test]
+    fn test_anonymize() {
+        let line = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm +0.0rot 5.5dB 3e -4.3kHz";
+        let anonymized = anonymize(line, 42);
+        assert_ne!(anonymized, line);
+        assert!(!anonymized.contains("DDFAA3"));
+        assert_eq!(anonymized, anonymize(line, 42));
+        assert_ne!(anonymized, anonymize(line, 43));
+
+        let original = line.parse::<Message>().unwrap();
+        let message = anonymized.parse::<Message>().unwrap();
+        let (original, comment) = (
+            original.position_comment.unwrap(),
+            message.position_comment.unwrap(),
+        );
+        assert_eq!(comment.altitude, original.altitude);
+        assert_ne!(comment.id.unwrap().address, original.id.unwrap().address);
+
+        // Receivers are public stations
+        let line = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7.RPI-GPU";
+        assert_eq!(anonymize(line, 42), line);
+    }
+
+    #[test]
+    fn test_replace_addresses() {
+        let pseudonym = |address: u32| address ^ 0xFFFFFF;
+
+        // Only the parsed fields: the id, the original address and the relay of the path
+        let line = r"FLRddfaa3>OGFLR,OGN123456*,qAS,DDFAA3:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 rDDFAA3 -019fpm DDFAA3";
+        let message = line.parse::<Message>().unwrap();
+        assert_eq!(
+            replace_addresses(&message, pseudonym),
+            r"FLR22055C>OGFLR,OGNEDCBA9*,qAS,DDFAA3:/074548h5111.32N/00102.04W'086/007/A=000607 id0622055C r22055C -019fpm DDFAA3"
+        );
+
+        // Tokens with other addresses are left unparsed and aren't replaced
+        let line = r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 id06123456";
+        let message = line.parse::<Message>().unwrap();
+        assert!(replace_addresses(&message, pseudonym).ends_with(" id0622055C id06123456"));
+
+        let line = "not an aprs packet";
+        let message = line.parse::<Message>().unwrap();
+        assert_eq!(replace_addresses(&message, pseudonym), line);
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let mut corpus = CorpusBuilder::new(0, 10);
+        assert!(corpus.add(r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm"));
+        assert!(!corpus.add(r"FLRDDFAA4>APRS,qAS,Letzi:/074549h5111.33N/00102.05W'087/008/A=000608 id0ADDFAA4 -020fpm"));
+        assert!(corpus.add(
+            r"FLRDDFAA4>APRS,qAS,Letzi:/074549h5111.33N/00102.05W'087/008/A=000608 id0ADDFAA4"
+        ));
+        assert!(corpus.add(r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7.RPI-GPU"));
+        assert!(corpus.add("not an aprs packet"));
+        assert!(!corpus.add("not an aprs racket"));
+        assert!(corpus.add("still not an aprs packet"));
+
+        let groups = corpus.groups();
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            vec!["APRS", "OGNSDR", "invalid"]
+        );
+        assert_eq!(groups["APRS"].len(), 2);
+    }
+}
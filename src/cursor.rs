@@ -0,0 +1,70 @@
+// Reads a string field by field. All methods return None instead of panicking when the
+// string is too short or a field would end inside a multibyte character, and only advance
+// on success, so the caller can try alternatives.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FieldCursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> FieldCursor<'a> {
+    pub(crate) fn new(s: &'a str) -> Self {
+        FieldCursor { rest: s }
+    }
+
+    // The next n bytes
+    pub(crate) fn take_n(&mut self, n: usize) -> Option<&'a str> {
+        let field = self.rest.get(..n)?;
+        self.rest = &self.rest[n..];
+        Some(field)
+    }
+
+    // Everything before the delimiter, the delimiter is skipped
+    pub(crate) fn take_until(&mut self, delimiter: &str) -> Option<&'a str> {
+        let (field, rest) = self.rest.split_once(delimiter)?;
+        self.rest = rest;
+        Some(field)
+    }
+
+    // Skips the prefix, None if the rest doesn't start with it
+    pub(crate) fn expect(&mut self, prefix: &str) -> Option<()> {
+        self.rest = self.rest.strip_prefix(prefix)?;
+        Some(())
+    }
+
+    pub(crate) fn rest(&self) -> &'a str {
+        self.rest
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = FieldCursor::new("074849h4821.61N/rest");
+        assert_eq!(cursor.take_n(7), Some("074849h"));
+        assert_eq!(cursor.take_until("/"), Some("4821.61N"));
+        assert_eq!(cursor.expect("x"), None);
+        assert_eq!(cursor.expect("re"), Some(()));
+        assert_eq!(cursor.take_n(3), None);
+        assert_eq!(cursor.take_until("/"), None);
+        assert_eq!(cursor.rest(), "st");
+        assert_eq!(cursor.take_n(2), Some("st"));
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn test_multibyte() {
+        let mut cursor = FieldCursor::new("a°b");
+        assert_eq!(cursor.take_n(2), None);
+        assert_eq!(cursor.rest(), "a°b");
+        assert_eq!(cursor.take_n(3), Some("a°"));
+        assert_eq!(cursor.take_until("°"), None);
+        assert_eq!(cursor.rest(), "b");
+    }
+}
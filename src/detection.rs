@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::category::SymbolClass;
+use crate::flavor::BeaconFlavor;
+use crate::geo::distance;
+use crate::message::Message;
+use crate::packet::{AprsData, AprsPacket};
+use crate::position_comment::AddressType;
+use crate::time::seconds_of_day;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ReceiverMoved {
+    pub receiver: String,
+    pub previous_latitude: f64,
+    pub previous_longitude: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+    // in meters
+    pub distance: f64,
+}
+
+// Remembers the last known position of each receiver and reports relocations
+pub struct ReceiverPositionTracker {
+    threshold: f64,
+    positions: HashMap<String, (f64, f64)>,
+}
+
+impl ReceiverPositionTracker {
+    // threshold: distance in meters which counts as a relocation
+    pub fn new(threshold: f64) -> Self {
+        ReceiverPositionTracker {
+            threshold,
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) -> Option<ReceiverMoved> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) if is_receiver(packet) => position,
+            _ => return None,
+        };
+        let receiver = packet.from.to_string();
+        let current = (position.latitude, position.longitude);
+        let previous = self.positions.insert(receiver.clone(), current)?;
+
+        let distance = distance(previous.0, previous.1, current.0, current.1);
+        if distance > self.threshold {
+            Some(ReceiverMoved {
+                receiver,
+                previous_latitude: previous.0,
+                previous_longitude: previous.1,
+                latitude: current.0,
+                longitude: current.1,
+                distance,
+            })
+        } else {
+            // Keep the reference position, so a slow drift is detected too
+            self.positions.insert(receiver, previous);
+            None
+        }
+    }
+
+    pub fn position(&self, receiver: &str) -> Option<(f64, f64)> {
+        self.positions.get(receiver).copied()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct LocationMismatch {
+    pub receiver: String,
+    pub registered_latitude: f64,
+    pub registered_longitude: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+    // in meters
+    pub distance: f64,
+}
+
+// Compares the positions the receivers report with their registered locations
+// (e.g. from a receiver registry), mismatches are often copy-pasted configurations
+pub struct ReceiverLocationChecker {
+    threshold: f64,
+    locations: HashMap<String, (f64, f64)>,
+}
+
+impl ReceiverLocationChecker {
+    // threshold: distance in meters which counts as a mismatch
+    pub fn new(threshold: f64) -> Self {
+        ReceiverLocationChecker {
+            threshold,
+            locations: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, receiver: &str, latitude: f64, longitude: f64) {
+        self.locations
+            .insert(receiver.to_string(), (latitude, longitude));
+    }
+
+    // Only receivers with a registered location are checked
+    pub fn check(&self, message: &Message) -> Option<LocationMismatch> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) if is_receiver(packet) => position,
+            _ => return None,
+        };
+        let receiver = packet.from.to_string();
+        let registered = *self.locations.get(&receiver)?;
+
+        let distance = distance(
+            registered.0,
+            registered.1,
+            position.latitude,
+            position.longitude,
+        );
+        (distance > self.threshold).then_some(LocationMismatch {
+            receiver,
+            registered_latitude: registered.0,
+            registered_longitude: registered.1,
+            latitude: position.latitude,
+            longitude: position.longitude,
+            distance,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AddressConflict {
+    pub address_type: AddressType,
+    pub address: u32,
+    pub senders: (String, String),
+    pub positions: ((f64, f64), (f64, f64)),
+    // in seconds
+    pub time_difference: i64,
+    // in meters
+    pub distance: f64,
+}
+
+#[derive(Clone)]
+struct Fix {
+    sender: String,
+    seconds_of_day: i64,
+    latitude: f64,
+    longitude: f64,
+}
+
+// Detects two aircraft transmitting the same address: positions which are
+// too far apart for the time between them
+pub struct AddressConflictDetector {
+    max_speed: f64,
+    window: i64,
+    fixes: HashMap<(AddressType, u32), Fix>,
+}
+
+impl AddressConflictDetector {
+    // max_speed: the highest plausible ground speed in m/s
+    // window: only fixes at most this many seconds apart are compared
+    pub fn new(max_speed: f64, window: i64) -> Self {
+        AddressConflictDetector {
+            max_speed,
+            window,
+            fixes: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) -> Option<AddressConflict> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) => position,
+            _ => return None,
+        };
+        let id = message.position_comment.as_ref()?.id.as_ref()?;
+        let fix = Fix {
+            sender: packet.from.to_string(),
+            seconds_of_day: seconds_of_day(position.timestamp.as_ref()?)?,
+            latitude: position.latitude,
+            longitude: position.longitude,
+        };
+        let previous = self
+            .fixes
+            .insert((id.address_type, id.address), fix.clone())?;
+
+        // The timestamps wrap around at midnight
+        let difference = (fix.seconds_of_day - previous.seconds_of_day).rem_euclid(86400);
+        let time_difference = difference.min(86400 - difference);
+        if time_difference > self.window {
+            return None;
+        }
+        let distance = distance(
+            previous.latitude,
+            previous.longitude,
+            fix.latitude,
+            fix.longitude,
+        );
+        // One second of tolerance for the timestamp resolution
+        if distance <= self.max_speed * (time_difference + 1) as f64 {
+            return None;
+        }
+        Some(AddressConflict {
+            address_type: id.address_type,
+            address: id.address,
+            senders: (previous.sender, fix.sender),
+            positions: (
+                (previous.latitude, previous.longitude),
+                (fix.latitude, fix.longitude),
+            ),
+            time_difference,
+            distance,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AddressLink {
+    pub previous_address: u32,
+    pub address: u32,
+    // The first address seen of the device
+    pub device: u32,
+    // in seconds
+    pub time_difference: i64,
+    // in meters
+    pub distance: f64,
+}
+
+struct RandomTrack {
+    fix: Fix,
+    device: u32,
+    continued: bool,
+}
+
+// Heuristic: links the consecutive random addresses of a device by the continuity of the track.
+// A new address continues the closest track of another random address which ended shortly
+// before and is reachable with max_speed. Aircraft flying close together can be mixed up.
+pub struct RandomAddressCorrelator {
+    max_speed: f64,
+    window: i64,
+    tracks: HashMap<u32, RandomTrack>,
+}
+
+impl RandomAddressCorrelator {
+    // max_speed: the highest plausible ground speed in m/s
+    // window: the longest gap in seconds between the last fix of the old and the first fix of the new address
+    pub fn new(max_speed: f64, window: i64) -> Self {
+        RandomAddressCorrelator {
+            max_speed,
+            window,
+            tracks: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) -> Option<AddressLink> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) => position,
+            _ => return None,
+        };
+        let id = message.position_comment.as_ref()?.id.as_ref()?;
+        // Devices with random addresses change them periodically
+        if id.address_type != AddressType::Random {
+            return None;
+        }
+        let fix = Fix {
+            sender: packet.from.to_string(),
+            seconds_of_day: seconds_of_day(position.timestamp.as_ref()?)?,
+            latitude: position.latitude,
+            longitude: position.longitude,
+        };
+        // Seconds from the last fix of a track to this fix, the timestamps wrap around at midnight
+        let gap =
+            |track: &RandomTrack| (fix.seconds_of_day - track.fix.seconds_of_day).rem_euclid(86400);
+        // Keep the tracks with slightly newer fixes, beacons can arrive out of order
+        self.tracks
+            .retain(|_, track| gap(track) <= self.window || gap(track) >= 86400 - self.window);
+
+        if let Some(track) = self.tracks.get_mut(&id.address) {
+            track.fix = fix;
+            return None;
+        }
+        let link = self
+            .tracks
+            .iter()
+            .filter(|(_, track)| !track.continued && (1..=self.window).contains(&gap(track)))
+            .map(|(address, track)| {
+                let distance = distance(
+                    track.fix.latitude,
+                    track.fix.longitude,
+                    fix.latitude,
+                    fix.longitude,
+                );
+                (*address, track.device, gap(track), distance)
+            })
+            // One second of tolerance for the timestamp resolution
+            .filter(|(_, _, gap, distance)| *distance <= self.max_speed * (gap + 1) as f64)
+            .min_by(|a, b| a.3.total_cmp(&b.3))
+            .map(
+                |(previous_address, device, time_difference, distance)| AddressLink {
+                    previous_address,
+                    address: id.address,
+                    device,
+                    time_difference,
+                    distance,
+                },
+            );
+
+        if let Some(link) = &link {
+            if let Some(previous) = self.tracks.get_mut(&link.previous_address) {
+                previous.continued = true;
+            }
+        }
+        let device = link.as_ref().map_or(id.address, |link| link.device);
+        self.tracks.insert(
+            id.address,
+            RandomTrack {
+                fix,
+                device,
+                continued: false,
+            },
+        );
+        link
+    }
+
+    // The first address of the device with this random address, while it is tracked
+    pub fn device(&self, address: u32) -> Option<u32> {
+        self.tracks.get(&address).map(|track| track.device)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize)]
+pub enum BeaconKind {
+    AircraftPosition,
+    ReceiverPosition,
+    ReceiverStatus,
+    WeatherStation,
+    Unknown,
+}
+
+impl Message {
+    // Derived from the tocall, the symbol and the comment
+    pub fn beacon_kind(&self) -> BeaconKind {
+        let Ok(packet) = &self.aprs_packet else {
+            return BeaconKind::Unknown;
+        };
+        let position = match &packet.data {
+            AprsData::Position(position) => position,
+            AprsData::Weather(_) => return BeaconKind::WeatherStation,
+            AprsData::Status(_) if is_receiver(packet) => return BeaconKind::ReceiverStatus,
+            _ => return BeaconKind::Unknown,
+        };
+        let symbol_class = SymbolClass::from_symbol(position.symbol_table, position.symbol_code);
+        if self.weather_comment.is_some() || symbol_class == Some(SymbolClass::WeatherStation) {
+            BeaconKind::WeatherStation
+        } else if is_receiver(packet) || symbol_class == Some(SymbolClass::Receiver) {
+            BeaconKind::ReceiverPosition
+        } else if self
+            .position_comment
+            .as_ref()
+            .is_some_and(|comment| comment.id.is_some())
+            || symbol_class.is_some()
+            || !matches!(
+                self.flavor,
+                None | Some(BeaconFlavor::Aprs | BeaconFlavor::Unknown)
+            )
+        {
+            BeaconKind::AircraftPosition
+        } else {
+            BeaconKind::Unknown
+        }
+    }
+}
+
+// Receivers log in directly to the APRS servers (qAC),
+// aircraft beacons are forwarded by the receivers (qAS)
+pub(crate) fn is_receiver(packet: &AprsPacket) -> bool {
+    packet.to.to_string() == "OGNSDR"
+        || packet
+            .via
+            .iter()
+            .any(|callsign| callsign.to_string() == "qAC")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receiver_beacon(latitude: &str) -> Message {
+        format!(
+            "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/085623h{}I00653.67E&/A=001765",
+            latitude
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_receiver_moved() {
+        let mut tracker = ReceiverPositionTracker::new(500.0);
+        assert_eq!(tracker.update(&receiver_beacon("4708.92N")), None);
+        // 0.01' of latitude are about 18.5 m
+        assert_eq!(tracker.update(&receiver_beacon("4708.93N")), None);
+        assert_eq!(tracker.update(&receiver_beacon("4708.94N")), None);
+
+        let event = tracker.update(&receiver_beacon("4710.92N")).unwrap();
+        assert_eq!(event.receiver, "Letzi");
+        assert!((event.distance - 3706.0).abs() < 10.0);
+        assert_eq!(
+            tracker.position("Letzi"),
+            Some((event.latitude, event.longitude))
+        );
+        assert_eq!(tracker.update(&receiver_beacon("4710.92N")), None);
+    }
+
+    #[test]
+    fn test_receiver_location() {
+        let mut checker = ReceiverLocationChecker::new(1000.0);
+        assert_eq!(checker.check(&receiver_beacon("4708.92N")), None);
+
+        checker.register("Letzi", 47.1487, 6.8945);
+        assert_eq!(checker.check(&receiver_beacon("4708.92N")), None);
+        let mismatch = checker.check(&receiver_beacon("4808.92N")).unwrap();
+        assert_eq!(mismatch.receiver, "Letzi");
+        assert_eq!(mismatch.registered_latitude, 47.1487);
+        assert!((mismatch.distance - 111195.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_aircraft_are_ignored() {
+        let mut tracker = ReceiverPositionTracker::new(500.0);
+        for latitude in ["5111.32N", "5211.32N"] {
+            let message = format!(
+                "FLRDDFAA3>APRS,qAS,Letzi:/074548h{}/00102.04W'086/007/A=000607 id0ADDFAA3",
+                latitude
+            )
+            .parse::<Message>()
+            .unwrap();
+            assert_eq!(tracker.update(&message), None);
+        }
+        assert_eq!(tracker.position("FLRDDFAA3"), None);
+    }
+
+    fn aircraft_beacon(timestamp: &str, latitude: &str) -> Message {
+        format!(
+            "FLRDDFAA3>APRS,qAS,Letzi:/{}{}/00102.04W'086/007/A=000607 id0ADDFAA3",
+            timestamp, latitude
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_address_conflict() {
+        let mut detector = AddressConflictDetector::new(150.0, 60);
+        assert_eq!(
+            detector.update(&aircraft_beacon("074548h", "5111.32N")),
+            None
+        );
+        // 0.5' of latitude in 10 seconds are about 93 m/s
+        assert_eq!(
+            detector.update(&aircraft_beacon("074558h", "5111.82N")),
+            None
+        );
+
+        let conflict = detector
+            .update(&aircraft_beacon("074600h", "5211.82N"))
+            .unwrap();
+        assert_eq!(conflict.address, 0xDDFAA3);
+        assert_eq!(conflict.time_difference, 2);
+        assert!(conflict.distance > 100_000.0);
+
+        // Too far apart in time to decide
+        assert_eq!(
+            detector.update(&aircraft_beacon("080000h", "5111.32N")),
+            None
+        );
+    }
+
+    fn random_beacon(timestamp: &str, latitude: &str, address: &str) -> Message {
+        format!(
+            "RND{address}>OGFLR,qAS,Letzi:/{timestamp}{latitude}/00102.04W'086/007/A=000607 id08{address}"
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_random_addresses() {
+        let mut correlator = RandomAddressCorrelator::new(150.0, 60);
+        assert_eq!(
+            correlator.update(&random_beacon("074548h", "5111.32N", "1A2B3C")),
+            None
+        );
+        // Not random
+        assert_eq!(
+            correlator.update(&aircraft_beacon("074549h", "5111.33N")),
+            None
+        );
+        // Another device far away
+        assert_eq!(
+            correlator.update(&random_beacon("074550h", "5211.32N", "5F0A11")),
+            None
+        );
+        assert_eq!(
+            correlator.update(&random_beacon("074552h", "5111.34N", "1A2B3C")),
+            None
+        );
+
+        let link = correlator
+            .update(&random_beacon("074600h", "5111.40N", "7C8D9E"))
+            .unwrap();
+        assert_eq!(link.previous_address, 0x1A2B3C);
+        assert_eq!(link.device, 0x1A2B3C);
+        assert_eq!(link.time_difference, 8);
+        let link = correlator
+            .update(&random_beacon("074630h", "5111.70N", "0D0E0F"))
+            .unwrap();
+        assert_eq!(link.previous_address, 0x7C8D9E);
+        assert_eq!(link.device, 0x1A2B3C);
+        assert_eq!(correlator.device(0x0D0E0F), Some(0x1A2B3C));
+
+        // The track of the far away device ended too long ago
+        assert_eq!(
+            correlator.update(&random_beacon("074700h", "5211.32N", "2B2B2B")),
+            None
+        );
+        assert_eq!(correlator.device(0x5F0A11), None);
+    }
+
+    #[test]
+    fn test_conflict_around_midnight() {
+        let mut detector = AddressConflictDetector::new(150.0, 60);
+        assert_eq!(
+            detector.update(&aircraft_beacon("235959h", "5111.32N")),
+            None
+        );
+        let conflict = detector
+            .update(&aircraft_beacon("000001h", "5311.32N"))
+            .unwrap();
+        assert_eq!(conflict.time_difference, 2);
+    }
+
+    #[test]
+    fn test_beacon_kind() {
+        let kind = |line: &str| line.parse::<Message>().unwrap().beacon_kind();
+        assert_eq!(
+            kind(
+                r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3"
+            ),
+            BeaconKind::AircraftPosition
+        );
+        // Old receivers use the tocall APRS for all beacons
+        assert_eq!(
+            kind(r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054"),
+            BeaconKind::AircraftPosition
+        );
+        assert_eq!(
+            kind(r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765"),
+            BeaconKind::ReceiverPosition
+        );
+        assert_eq!(
+            kind(r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>074600h v0.2.8.RPI-GPU CPU:0.5"),
+            BeaconKind::ReceiverStatus
+        );
+        assert_eq!(
+            kind(
+                r"FNT08F298>OGNFNT,qAS,DREIFBERG:/082654h4804.90N/00845.74E_273/005g008t057 sF1 cr4 DF0"
+            ),
+            BeaconKind::WeatherStation
+        );
+        assert_eq!(
+            kind(r"N0CALL>APRS,TCPIP*:_10090556c220s004g005t077r000p000P000h50b09900wRSW"),
+            BeaconKind::WeatherStation
+        );
+        assert_eq!(
+            kind(r"N0CALL>APRS,TCPIP*:/074600h4708.92N/00653.67E[hiking"),
+            BeaconKind::Unknown
+        );
+        assert_eq!(kind("# aprsc 2.1.15-gc67551b"), BeaconKind::Unknown);
+    }
+}
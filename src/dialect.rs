@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::message::Message;
+
+// How the comments of a sender are best parsed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Dialect {
+    // Lenient parsing doesn't parse more, so it isn't tried any more
+    Standard,
+    // Miscased or split token prefixes, the lenient parser is used right away
+    Lenient,
+}
+
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, Serialize)]
+pub struct DialectStatistics {
+    pub messages: u64,
+    // Parsed with the dialect of the sender
+    pub hinted: u64,
+    // Parsed a second time to learn the dialect
+    pub retried: u64,
+    // Dialects dropped because the sender changed its tocall or software version
+    pub invalidated: u64,
+    // Dialects dropped to keep the cache below max_senders
+    pub evicted: u64,
+}
+
+// About the number of senders on the OGN feed within a day
+pub const DEFAULT_MAX_SENDERS: usize = 100_000;
+
+struct Hint {
+    tocall: String,
+    software_version: Option<f32>,
+    dialect: Dialect,
+    // The message count when the hint was used last
+    last_used: u64,
+}
+
+// Selects parse() or parse_lenient() for each sender of a feed. Comments with unparsed
+// tokens are parsed again leniently until the dialect of the sender is known, afterwards
+// only the matching parser runs. This saves the second parse of the senders that need
+// the lenient parser, but it is no faster than parse() alone: both parsers run the full
+// chain of token matchers and every sender costs a lookup. A new tocall or software
+// version means new firmware,
+// then the dialect is learned again. Beacons without a software version, like the
+// statuses, keep the dialect. A new sender on a full cache drops the half of the
+// senders seen least recently.
+pub struct DialectCache {
+    hints: HashMap<String, Hint>,
+    max_senders: usize,
+    pub statistics: DialectStatistics,
+}
+
+impl Default for DialectCache {
+    fn default() -> Self {
+        DialectCache::with_max_senders(DEFAULT_MAX_SENDERS)
+    }
+}
+
+impl DialectCache {
+    pub fn new() -> Self {
+        DialectCache::default()
+    }
+
+    pub fn with_max_senders(max_senders: usize) -> Self {
+        DialectCache {
+            hints: HashMap::new(),
+            max_senders: max_senders.max(1),
+            statistics: DialectStatistics::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
+    pub fn dialect(&self, sender: &str) -> Option<Dialect> {
+        self.hints.get(sender).map(|hint| hint.dialect)
+    }
+
+    pub fn parse(&mut self, line: &str) -> Message {
+        self.statistics.messages += 1;
+        let Some((sender, tocall)) = sender_and_tocall(line) else {
+            return line.parse().unwrap();
+        };
+        if let Some(hint) = self.hints.get_mut(sender) {
+            if hint.tocall == tocall {
+                let message = match hint.dialect {
+                    Dialect::Standard => line.parse().unwrap(),
+                    Dialect::Lenient => Message::parse_lenient(line),
+                };
+                let version = software_version(&message);
+                if version.is_none() || version == hint.software_version {
+                    hint.last_used = self.statistics.messages;
+                    self.statistics.hinted += 1;
+                    return message;
+                }
+            }
+            self.hints.remove(sender);
+            self.statistics.invalidated += 1;
+        }
+
+        let message = line.parse::<Message>().unwrap();
+        if unparsed_tokens(&message) == 0 {
+            return message;
+        }
+        self.statistics.retried += 1;
+        let lenient = Message::parse_lenient(line);
+        let (message, dialect) = if unparsed_tokens(&lenient) < unparsed_tokens(&message) {
+            (lenient, Dialect::Lenient)
+        } else {
+            (message, Dialect::Standard)
+        };
+        if self.hints.len() >= self.max_senders {
+            self.evict();
+        }
+        self.hints.insert(
+            sender.to_string(),
+            Hint {
+                tocall: tocall.to_string(),
+                software_version: software_version(&message),
+                dialect,
+                last_used: self.statistics.messages,
+            },
+        );
+        message
+    }
+
+    // Halving the cache instead of dropping a single hint keeps the cost per new sender
+    // constant on average
+    fn evict(&mut self) {
+        let mut last_used = self
+            .hints
+            .values()
+            .map(|hint| hint.last_used)
+            .collect::<Vec<_>>();
+        let middle = (last_used.len() - 1) / 2;
+        let median = *last_used.select_nth_unstable(middle).1;
+        let before = self.hints.len();
+        self.hints.retain(|_, hint| hint.last_used > median);
+        self.statistics.evicted += (before - self.hints.len()) as u64;
+    }
+}
+
+// "FLRDDFAA3>OGFLR,qAS,Letzi:..."
+fn sender_and_tocall(line: &str) -> Option<(&str, &str)> {
+    let (sender, rest) = line.split_once('>')?;
+    let end = rest.find([',', ':'])?;
+    Some((sender, &rest[..end]))
+}
+
+fn software_version(message: &Message) -> Option<f32> {
+    message
+        .position_comment
+        .as_ref()
+        .and_then(|comment| comment.software_version)
+}
+
+fn unparsed_tokens(message: &Message) -> usize {
+    message
+        .position_comment
+        .as_ref()
+        .and_then(|comment| comment.unparsed.as_ref())
+        .map_or(0, |unparsed| unparsed.split(' ').count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION: &str =
+        r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3";
+
+    #[test]
+    fn test_dialect_cache() {
+        let mut cache = DialectCache::new();
+        // Clean comments don't need a dialect
+        cache.parse(POSITION);
+        assert_eq!(cache.dialect("FLRDDFAA3"), None);
+
+        let message = cache.parse(&format!("{} GPS3x5", POSITION));
+        assert_eq!(
+            message.position_comment.unwrap().gps_quality,
+            Some("3x5".into())
+        );
+        assert_eq!(cache.dialect("FLRDDFAA3"), Some(Dialect::Lenient));
+        let message = cache.parse(&format!("{} GPS2x3", POSITION));
+        assert_eq!(
+            message.position_comment.unwrap().gps_quality,
+            Some("2x3".into())
+        );
+
+        // Tokens the lenient parser can't fix either
+        cache.parse(r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 hello");
+        assert_eq!(cache.dialect("ICA3D17F2"), Some(Dialect::Standard));
+        cache.parse(r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 hello");
+        assert_eq!(
+            cache.statistics,
+            DialectStatistics {
+                messages: 5,
+                hinted: 2,
+                retried: 2,
+                invalidated: 0,
+                evicted: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_firmware_change() {
+        let mut cache = DialectCache::new();
+        cache.parse(&format!("{} GPS3x5 s7.07", POSITION));
+        assert_eq!(cache.dialect("FLRDDFAA3"), Some(Dialect::Lenient));
+
+        // The new firmware sends the standard tokens
+        let message = cache.parse(&format!("{} hello s7.08", POSITION));
+        assert_eq!(
+            message.position_comment.unwrap().unparsed,
+            Some("hello".into())
+        );
+        assert_eq!(cache.dialect("FLRDDFAA3"), Some(Dialect::Standard));
+
+        let message = cache.parse(&POSITION.replace("OGFLR", "OGNTRK"));
+        assert_eq!(
+            message.flavor,
+            Some(crate::flavor::BeaconFlavor::OgnTracker)
+        );
+        assert_eq!(cache.dialect("FLRDDFAA3"), None);
+        assert_eq!(cache.statistics.invalidated, 2);
+
+        assert!(cache.parse("invalid").aprs_packet.is_err());
+    }
+
+    #[test]
+    fn test_missing_version() {
+        let mut cache = DialectCache::new();
+        cache.parse(&format!("{} GPS3x5 s7.07", POSITION));
+        // Neither a position without the version nor a status is a firmware change
+        cache.parse(&format!("{} GPS3x5", POSITION));
+        cache.parse(r"FLRDDFAA3>OGFLR,qAS,Letzi:>074600h h00 v00");
+        assert_eq!(cache.dialect("FLRDDFAA3"), Some(Dialect::Lenient));
+        assert_eq!(cache.statistics.hinted, 2);
+        assert_eq!(cache.statistics.invalidated, 0);
+    }
+
+    #[test]
+    fn test_max_senders() {
+        let mut cache = DialectCache::with_max_senders(4);
+        for sender in ["FLR000001", "FLR000002", "FLR000003", "FLR000004"] {
+            cache.parse(&format!("{} GPS3x5", POSITION.replace("FLRDDFAA3", sender)));
+        }
+        assert_eq!(cache.len(), 4);
+        // Seen again, so it is kept
+        cache.parse(&format!(
+            "{} GPS3x5",
+            POSITION.replace("FLRDDFAA3", "FLR000001")
+        ));
+
+        cache.parse(&format!("{} GPS3x5", POSITION));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.statistics.evicted, 2);
+        assert_eq!(cache.dialect("FLR000001"), Some(Dialect::Lenient));
+        assert_eq!(cache.dialect("FLRDDFAA3"), Some(Dialect::Lenient));
+        assert_eq!(cache.dialect("FLR000002"), None);
+    }
+}
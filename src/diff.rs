@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::message::Message;
+
+// A field that differs between two messages, missing fields are null
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FieldChange {
+    // The path in the JSON export, e.g. "position_comment.climb_rate"
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl Message {
+    // The differences of the parsed fields, sorted by field. The raw string is the input
+    // and not compared, arrays are compared as a whole.
+    pub fn diff(&self, other: &Message) -> Vec<FieldChange> {
+        let (old_value, new_value) = (
+            serde_json::to_value(self).unwrap(),
+            serde_json::to_value(other).unwrap(),
+        );
+        let (mut old, mut new) = (BTreeMap::new(), BTreeMap::new());
+        flatten("", &old_value, &mut old);
+        flatten("", &new_value, &mut new);
+        old.remove("raw_string");
+        new.remove("raw_string");
+
+        let mut fields = old.keys().chain(new.keys()).collect::<Vec<_>>();
+        fields.sort();
+        fields.dedup();
+        fields
+            .into_iter()
+            .filter_map(|field| {
+                let (old, new) = (
+                    old.get(field).copied().unwrap_or(&Value::Null),
+                    new.get(field).copied().unwrap_or(&Value::Null),
+                );
+                (old != new).then(|| FieldChange {
+                    field: field.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+// The leaves of the objects by their dotted path, null values are skipped
+fn flatten<'a>(prefix: &str, value: &'a Value, leaves: &mut BTreeMap<String, &'a Value>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = match prefix {
+                    "" => key.clone(),
+                    prefix => format!("{}.{}", prefix, key),
+                };
+                flatten(&path, value, leaves);
+            }
+        }
+        Value::Null => {}
+        leaf => {
+            leaves.insert(prefix.to_string(), leaf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff() {
+        let old = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3 -019fpm 10.0dB"
+            .parse::<Message>()
+            .unwrap();
+        let new = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3 +020fpm 3e"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(old.diff(&old), vec![]);
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                FieldChange {
+                    field: "aprs_packet.data.Position.comment".to_string(),
+                    old: json!("086/007/A=000607 id06DDFAA3 -019fpm 10.0dB"),
+                    new: json!("086/007/A=000607 id06DDFAA3 +020fpm 3e"),
+                },
+                FieldChange {
+                    field: "position_comment.climb_rate".to_string(),
+                    old: json!(-19),
+                    new: json!(20),
+                },
+                FieldChange {
+                    field: "position_comment.error".to_string(),
+                    old: Value::Null,
+                    new: json!(3),
+                },
+                FieldChange {
+                    field: "position_comment.signal_quality".to_string(),
+                    old: json!(10.0),
+                    new: Value::Null,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_error() {
+        let old = "FLRDDFAA3>APRS,qAS,Letzi:/0745".parse::<Message>().unwrap();
+        let new = "FLRDDFAA3>APRS,qAS,Letzi:/07451"
+            .parse::<Message>()
+            .unwrap();
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes,
+            vec![FieldChange {
+                field: "parser_error".to_string(),
+                old: json!("Invalid timestamp: 0745"),
+                new: json!("Invalid timestamp: 07451"),
+            }]
+        );
+    }
+}
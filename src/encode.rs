@@ -0,0 +1,128 @@
+use crate::error::AprsError;
+use crate::packet::{AprsData, AprsPacket};
+
+// What to do with comments longer than the cap
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CommentPolicy {
+    Error,
+    Truncate,
+}
+
+// APRS itself allows 43 characters for position comments (36 with a data extension)
+// and 62 for status texts, but OGN comments are usually longer, so the cap is configurable
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EncodeOptions {
+    // In bytes
+    pub max_comment_length: usize,
+    pub policy: CommentPolicy,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Encoded {
+    pub packet: String,
+    // The end of the comment that was cut off
+    pub dropped: Option<String>,
+}
+
+impl AprsPacket {
+    // Like to_string(), but the comment is checked against the cap
+    pub fn encode(&self, options: &EncodeOptions) -> Result<Encoded, AprsError> {
+        let mut packet = self.clone();
+        let mut dropped = None;
+        if let Some(comment) = comment_mut(&mut packet.data) {
+            if comment.len() > options.max_comment_length {
+                if options.policy == CommentPolicy::Error {
+                    return Err(AprsError::LimitExceeded(format!(
+                        "comment length {} > {}",
+                        comment.len(),
+                        options.max_comment_length
+                    )));
+                }
+                let mut end = options.max_comment_length;
+                while !comment.is_char_boundary(end) {
+                    end -= 1;
+                }
+                dropped = Some(comment.split_off(end));
+            }
+        }
+        Ok(Encoded {
+            packet: packet.to_string(),
+            dropped,
+        })
+    }
+}
+
+fn comment_mut(data: &mut AprsData) -> Option<&mut String> {
+    match data {
+        AprsData::Position(position) => Some(&mut position.comment),
+        AprsData::Item(item) => Some(&mut item.position.comment),
+        AprsData::Status(status) => Some(&mut status.comment),
+        AprsData::Telemetry(telemetry) => Some(&mut telemetry.comment),
+        AprsData::Weather(weather) => Some(&mut weather.comment),
+        AprsData::ThirdParty(packet) => comment_mut(&mut packet.data),
+        AprsData::Message(_) | AprsData::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str =
+        "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623hv0.2.7 CPU:0.7 RAM:777.7/972.2MB";
+
+    #[test]
+    fn test_encode_within_cap() {
+        let packet = STATUS.parse::<AprsPacket>().unwrap();
+        let options = EncodeOptions {
+            max_comment_length: 62,
+            policy: CommentPolicy::Error,
+        };
+        assert_eq!(
+            packet.encode(&options),
+            Ok(Encoded {
+                packet: STATUS.to_string(),
+                dropped: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_encode_over_cap() {
+        let packet = STATUS.parse::<AprsPacket>().unwrap();
+        let options = EncodeOptions {
+            max_comment_length: 14,
+            policy: CommentPolicy::Truncate,
+        };
+        assert_eq!(
+            packet.encode(&options),
+            Ok(Encoded {
+                packet: "Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623hv0.2.7 CPU:0.7".to_string(),
+                dropped: Some(" RAM:777.7/972.2MB".to_string()),
+            })
+        );
+
+        let options = EncodeOptions {
+            policy: CommentPolicy::Error,
+            ..options
+        };
+        assert_eq!(
+            packet.encode(&options),
+            Err(AprsError::LimitExceeded(
+                "comment length 32 > 14".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_encode_char_boundary() {
+        let packet = "Letzi>OGNSDR:>Grüezi".parse::<AprsPacket>().unwrap();
+        let options = EncodeOptions {
+            max_comment_length: 3,
+            policy: CommentPolicy::Truncate,
+        };
+        let encoded = packet.encode(&options).unwrap();
+        assert_eq!(encoded.packet, "Letzi>OGNSDR:>Gr");
+        assert_eq!(encoded.dropped, Some("üezi".to_string()));
+    }
+}
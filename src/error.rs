@@ -0,0 +1,45 @@
+use std::fmt::{Display, Formatter};
+
+// All errors of the APRS layer, each with the offending part of the packet
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AprsError {
+    InvalidPacket(String),
+    EmptyCallsign(String),
+    EmptyCallsignSSID(String),
+    InvalidTimestamp(String),
+    UnsupportedPositionFormat(String),
+    InvalidPosition(String),
+    InvalidLatitude(String),
+    InvalidLongitude(String),
+    InvalidMessageDestination(String),
+    InvalidMessageId(String),
+    InvalidTelemetry(String),
+    InvalidItem(String),
+    LimitExceeded(String),
+}
+
+impl Display for AprsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AprsError::InvalidPacket(s) => write!(f, "Invalid packet: {}", s),
+            AprsError::EmptyCallsign(s) => write!(f, "Callsign is empty: {}", s),
+            AprsError::EmptyCallsignSSID(s) => write!(f, "Callsign SSID is empty: {}", s),
+            AprsError::InvalidTimestamp(s) => write!(f, "Invalid timestamp: {}", s),
+            AprsError::UnsupportedPositionFormat(s) => {
+                write!(f, "Unsupported position format: {}", s)
+            }
+            AprsError::InvalidPosition(s) => write!(f, "Invalid position: {}", s),
+            AprsError::InvalidLatitude(s) => write!(f, "Invalid latitude: {}", s),
+            AprsError::InvalidLongitude(s) => write!(f, "Invalid longitude: {}", s),
+            AprsError::InvalidMessageDestination(s) => {
+                write!(f, "Invalid message destination: {}", s)
+            }
+            AprsError::InvalidMessageId(s) => write!(f, "Invalid message id: {}", s),
+            AprsError::InvalidTelemetry(s) => write!(f, "Invalid telemetry: {}", s),
+            AprsError::InvalidItem(s) => write!(f, "Invalid item: {}", s),
+            AprsError::LimitExceeded(s) => write!(f, "Limit exceeded: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for AprsError {}
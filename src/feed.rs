@@ -0,0 +1,154 @@
+use crate::error::AprsError;
+use crate::limits::Limits;
+use crate::message::Message;
+
+// A push parser for byte streams without BufRead, e.g. the UART of a gateway:
+//     parser.feed(&bytes);
+//     while let Some(message) = parser.poll() { ... }
+// The buffer is allocated once for the longest allowed line and never grows, feed()
+// takes only the bytes that fit and the rest has to be fed again after polling.
+pub struct FeedParser {
+    limits: Limits,
+    buffer: Vec<u8>,
+    // The rest of a line that didn't fit into the buffer is dropped up to its end
+    discarding: bool,
+}
+
+impl FeedParser {
+    pub fn new(limits: Limits) -> Self {
+        FeedParser {
+            limits,
+            buffer: Vec::with_capacity(limits.max_line_length + 2),
+            discarding: false,
+        }
+    }
+
+    // Returns the number of bytes taken
+    pub fn feed(&mut self, bytes: &[u8]) -> usize {
+        let mut consumed = 0;
+        if self.discarding {
+            match bytes.iter().position(|byte| *byte == b'\n') {
+                Some(idx) => {
+                    self.discarding = false;
+                    consumed = idx + 1;
+                }
+                None => return bytes.len(),
+            }
+        }
+        let taken = (self.capacity() - self.buffer.len()).min(bytes.len() - consumed);
+        self.buffer
+            .extend_from_slice(&bytes[consumed..consumed + taken]);
+        consumed + taken
+    }
+
+    // The next complete line, lines too long for the buffer are rejected with
+    // AprsError::LimitExceeded and their beginning as raw string
+    pub fn poll(&mut self) -> Option<Message> {
+        loop {
+            let Some(idx) = self.buffer.iter().position(|byte| *byte == b'\n') else {
+                if self.buffer.len() < self.capacity() {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&self.buffer).into_owned();
+                self.buffer.clear();
+                self.discarding = true;
+                return Some(Message::rejected(
+                    &line,
+                    AprsError::LimitExceeded(format!(
+                        "line length > {}",
+                        self.limits.max_line_length
+                    )),
+                ));
+            };
+            let line = String::from_utf8_lossy(&self.buffer[..idx])
+                .trim_end_matches('\r')
+                .to_string();
+            self.buffer.drain(..=idx);
+            if !line.trim().is_empty() {
+                return Some(Message::parse_with_limits(&line, &self.limits));
+            }
+        }
+    }
+
+    // The longest line and its "\r\n"
+    fn capacity(&self) -> usize {
+        self.limits.max_line_length + 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEACON: &str =
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm";
+    const STATUS: &str = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!";
+
+    #[test]
+    fn test_feed_chunks() {
+        let stream = format!("{}\r\n\r\n{}\n", BEACON, STATUS);
+        let mut parser = FeedParser::new(Limits::default());
+        let mut messages = vec![];
+        for chunk in stream.as_bytes().chunks(7) {
+            assert_eq!(parser.feed(chunk), chunk.len());
+            while let Some(message) = parser.poll() {
+                messages.push(message);
+            }
+        }
+        assert_eq!(
+            messages,
+            vec![
+                BEACON.parse::<Message>().unwrap(),
+                STATUS.parse::<Message>().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_feed_full_buffer() {
+        let limits = Limits {
+            max_line_length: 100,
+            ..Limits::default()
+        };
+        let stream = format!("{}\n{}\n", BEACON, STATUS);
+        let mut bytes = stream.as_bytes();
+        let mut parser = FeedParser::new(limits);
+        let mut messages = vec![];
+        while !bytes.is_empty() {
+            let consumed = parser.feed(bytes);
+            assert!(consumed <= 102);
+            bytes = &bytes[consumed..];
+            while let Some(message) = parser.poll() {
+                messages.push(message.raw_string);
+            }
+        }
+        assert_eq!(messages, vec![BEACON, STATUS]);
+        assert_eq!(parser.buffer.capacity(), 102);
+    }
+
+    #[test]
+    fn test_feed_line_too_long() {
+        let limits = Limits {
+            max_line_length: 40,
+            ..Limits::default()
+        };
+        let stream = format!("{}\n{}\n", BEACON, &STATUS[..40]);
+        let mut parser = FeedParser::new(limits);
+        let mut bytes = stream.as_bytes();
+        let mut messages = vec![];
+        while !bytes.is_empty() {
+            bytes = &bytes[parser.feed(bytes)..];
+            while let Some(message) = parser.poll() {
+                messages.push(message);
+            }
+        }
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].raw_string, BEACON[..42]);
+        assert_eq!(
+            messages[0].aprs_packet,
+            Err(AprsError::LimitExceeded("line length > 40".to_string()))
+        );
+        assert_eq!(messages[1].raw_string, STATUS[..40]);
+        assert!(messages[1].aprs_packet.is_ok());
+    }
+}
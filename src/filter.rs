@@ -0,0 +1,159 @@
+use std::{convert::Infallible, str::FromStr};
+
+use crate::geo::distance;
+use crate::message::Message;
+use crate::packet::AprsData;
+
+// The APRS-IS server side filters OGN clients use:
+//     r/lat/lon/dist  positions within dist km
+//     p/aa/bb         callsigns starting with one of the prefixes
+//     b/call1/call2   the callsigns, '*' matches any ending
+#[derive(Debug, PartialEq, Clone)]
+pub enum Filter {
+    Range {
+        latitude: f64,
+        longitude: f64,
+        // in km
+        distance: f64,
+    },
+    Prefix(Vec<String>),
+    Budlist(Vec<String>),
+}
+
+impl Filter {
+    // None for unknown or invalid filters, the servers ignore them as well
+    pub fn parse(s: &str) -> Option<Filter> {
+        let mut parts = s.split('/');
+        let kind = parts.next()?;
+        let values = parts
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        match (kind, &values[..]) {
+            ("r", [latitude, longitude, distance]) => Some(Filter::Range {
+                latitude: latitude.parse().ok()?,
+                longitude: longitude.parse().ok()?,
+                distance: distance.parse().ok()?,
+            }),
+            ("p", [_, ..]) => Some(Filter::Prefix(values)),
+            ("b", [_, ..]) => Some(Filter::Budlist(values)),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, message: &Message) -> bool {
+        let Ok(packet) = &message.aprs_packet else {
+            return false;
+        };
+        let from = packet.from.to_string();
+        match self {
+            Filter::Range {
+                latitude,
+                longitude,
+                distance: range,
+            } => match &packet.data {
+                AprsData::Position(position) => {
+                    distance(*latitude, *longitude, position.latitude, position.longitude)
+                        <= range * 1000.0
+                }
+                _ => false,
+            },
+            Filter::Prefix(prefixes) => prefixes.iter().any(|prefix| from.starts_with(prefix)),
+            Filter::Budlist(callsigns) => {
+                callsigns
+                    .iter()
+                    .any(|callsign| match callsign.strip_suffix('*') {
+                        Some(prefix) => from.starts_with(prefix),
+                        None => from == *callsign,
+                    })
+            }
+        }
+    }
+}
+
+// The filters of a login, separated by spaces. A message passes if it matches one of
+// the filters and none of the excluding ones, which are prefixed with '-'.
+// Without filters everything passes.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct FilterSet {
+    pub include: Vec<Filter>,
+    pub exclude: Vec<Filter>,
+}
+
+impl FilterSet {
+    pub fn matches(&self, message: &Message) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|filter| filter.matches(message)))
+            && !self.exclude.iter().any(|filter| filter.matches(message))
+    }
+}
+
+impl FromStr for FilterSet {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter_set = FilterSet::default();
+        for part in s.split_whitespace() {
+            match part.strip_prefix('-') {
+                Some(part) => filter_set.exclude.extend(Filter::parse(part)),
+                None => filter_set.include.extend(Filter::parse(part)),
+            }
+        }
+        Ok(filter_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(line: &str) -> Message {
+        line.parse().unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(
+            Filter::parse("r/47.1/6.9/50"),
+            Some(Filter::Range {
+                latitude: 47.1,
+                longitude: 6.9,
+                distance: 50.0,
+            })
+        );
+        assert_eq!(
+            Filter::parse("p/FLR/ICA"),
+            Some(Filter::Prefix(vec!["FLR".to_string(), "ICA".to_string()]))
+        );
+        assert_eq!(Filter::parse("r/47.1/6.9"), None);
+        assert_eq!(Filter::parse("p/"), None);
+        assert_eq!(Filter::parse("t/p"), None);
+
+        let flarm = message(
+            r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+        );
+        let icao =
+            message(r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054");
+        let range = Filter::parse("r/47.1/6.9/50").unwrap();
+        assert!(range.matches(&flarm));
+        assert!(!range.matches(&icao));
+        assert!(Filter::parse("b/ICA3D17F2").unwrap().matches(&icao));
+        assert!(!Filter::parse("b/ICA3D17F").unwrap().matches(&icao));
+        assert!(Filter::parse("b/ICA3D*").unwrap().matches(&icao));
+        assert!(!range.matches(&message("invalid")));
+    }
+
+    #[test]
+    fn test_filter_set() {
+        let flarm = message(
+            r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+        );
+        let receiver =
+            message(r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765");
+        assert!(FilterSet::default().matches(&flarm));
+
+        let filter_set = "r/47.1/6.9/50 -p/Letzi t/x".parse::<FilterSet>().unwrap();
+        assert_eq!(filter_set.include.len(), 1);
+        assert!(filter_set.matches(&flarm));
+        assert!(!filter_set.matches(&receiver));
+        assert!(!"p/ICA".parse::<FilterSet>().unwrap().matches(&flarm));
+    }
+}
@@ -0,0 +1,87 @@
+use serde::Serialize;
+
+// The kind of beacon, derived from the destination callsign (tocall). It selects the
+// comment parsers: the receiver status is only parsed for receivers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum BeaconFlavor {
+    // OGNSDR
+    Receiver,
+    // OGFLR
+    Flarm,
+    // OGNTRK
+    OgnTracker,
+    // OGNFNT
+    Fanet,
+    // OGADSB
+    AdsB,
+    // OGPAW
+    PilotAware,
+    // OGNSKY
+    SafeSky,
+    // OGNDVS
+    RemoteId,
+    // OGNAVI
+    Naviter,
+    // OGSPOT, OGNINRE, OGLT24, OGSKYL, OGCAPT: satellite and phone trackers
+    Spot,
+    InReach,
+    Lt24,
+    Skylines,
+    Capturs,
+    // OGFLYM
+    FlyMaster,
+    // APRS, the tocall of old receiver software for all beacons
+    Aprs,
+    Unknown,
+}
+
+impl BeaconFlavor {
+    pub fn from_tocall(tocall: &str) -> BeaconFlavor {
+        match tocall {
+            "OGNSDR" => BeaconFlavor::Receiver,
+            "OGFLR" => BeaconFlavor::Flarm,
+            "OGNTRK" => BeaconFlavor::OgnTracker,
+            "OGNFNT" => BeaconFlavor::Fanet,
+            "OGADSB" => BeaconFlavor::AdsB,
+            "OGPAW" => BeaconFlavor::PilotAware,
+            "OGNSKY" => BeaconFlavor::SafeSky,
+            "OGNDVS" => BeaconFlavor::RemoteId,
+            "OGNAVI" => BeaconFlavor::Naviter,
+            "OGSPOT" => BeaconFlavor::Spot,
+            "OGNINRE" => BeaconFlavor::InReach,
+            "OGLT24" => BeaconFlavor::Lt24,
+            "OGSKYL" => BeaconFlavor::Skylines,
+            "OGCAPT" => BeaconFlavor::Capturs,
+            "OGFLYM" => BeaconFlavor::FlyMaster,
+            "APRS" => BeaconFlavor::Aprs,
+            _ => BeaconFlavor::Unknown,
+        }
+    }
+
+    // Beacons of aircraft and other tracked objects, their status is no receiver status
+    pub fn is_aircraft(&self) -> bool {
+        !matches!(
+            self,
+            BeaconFlavor::Receiver | BeaconFlavor::Aprs | BeaconFlavor::Unknown
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tocall() {
+        assert_eq!(BeaconFlavor::from_tocall("OGNSDR"), BeaconFlavor::Receiver);
+        assert_eq!(BeaconFlavor::from_tocall("OGFLR"), BeaconFlavor::Flarm);
+        assert_eq!(BeaconFlavor::from_tocall("OGNFNT"), BeaconFlavor::Fanet);
+        assert_eq!(BeaconFlavor::from_tocall("OGNAVI"), BeaconFlavor::Naviter);
+        assert_eq!(BeaconFlavor::from_tocall("OGNINRE"), BeaconFlavor::InReach);
+        assert_eq!(BeaconFlavor::from_tocall("APRS"), BeaconFlavor::Aprs);
+        assert_eq!(BeaconFlavor::from_tocall("OGFLR7"), BeaconFlavor::Unknown);
+        assert!(BeaconFlavor::OgnTracker.is_aircraft());
+        assert!(!BeaconFlavor::Receiver.is_aircraft());
+        assert!(!BeaconFlavor::Aprs.is_aircraft());
+    }
+}
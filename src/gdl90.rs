@@ -0,0 +1,281 @@
+use crate::callsign::split_device_callsign;
+use crate::category::emitter_category;
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::position_comment::AddressType;
+
+const FLAG_BYTE: u8 = 0x7E;
+const CONTROL_ESCAPE: u8 = 0x7D;
+
+const MESSAGE_ID_HEARTBEAT: u8 = 0x00;
+const MESSAGE_ID_TRAFFIC_REPORT: u8 = 0x14;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrafficReport {
+    pub address_type: u8,
+    pub address: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<i32>,
+    pub airborne: bool,
+    pub nic: u8,
+    pub nacp: u8,
+    pub horizontal_velocity: Option<u16>,
+    pub vertical_velocity: Option<i32>,
+    pub track: Option<u16>,
+    pub emitter_category: u8,
+    pub callsign: String,
+}
+
+impl TrafficReport {
+    pub fn from_message(message: &Message) -> Option<TrafficReport> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) => position,
+            _ => return None,
+        };
+        let comment = message.position_comment.as_ref()?;
+        let id = comment.id.as_ref()?;
+
+        // GDL90 wants pressure altitude, so the flight level is preferred over the GNSS altitude
+        let altitude = comment
+            .flight_level
+            .map(|flight_level| (flight_level * 100.0).round() as i32)
+            .or(comment.altitude.map(|altitude| altitude as i32));
+
+        // The first number of gpsAxB is the horizontal accuracy in meters
+        let nacp = comment
+            .gps_quality
+            .as_ref()
+            .and_then(|gps_quality| gps_quality.split_once('x'))
+            .and_then(|(horizontal, _)| horizontal.parse::<u16>().ok())
+            .map(nacp_from_accuracy)
+            .unwrap_or(0);
+
+        Some(TrafficReport {
+            // OGN address type 1 is ICAO, everything else is a self-assigned address
+            address_type: if id.address_type == AddressType::Icao {
+                0
+            } else {
+                1
+            },
+            address: id.address,
+            latitude: position.latitude,
+            longitude: position.longitude,
+            altitude,
+            airborne: comment.speed.map(|speed| speed > 0).unwrap_or(true),
+            nic: nacp,
+            nacp,
+            horizontal_velocity: comment.speed,
+            vertical_velocity: comment.climb_rate.map(i32::from),
+            track: comment.course,
+            emitter_category: emitter_category(id.aircraft_type),
+            callsign: callsign(&packet.from.to_string()),
+        })
+    }
+
+    // Traffic report (message id 20) layout:
+    // st aa aa aa ll ll ll nn nn nn dd dm ia hh hv vv tt ee cc cc cc cc cc cc cc cc px
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(28);
+        payload.push(MESSAGE_ID_TRAFFIC_REPORT);
+        payload.push(self.address_type & 0x0F);
+        payload.extend_from_slice(&self.address.to_be_bytes()[1..]);
+        payload.extend_from_slice(&semicircles(self.latitude));
+        payload.extend_from_slice(&semicircles(self.longitude));
+
+        // Altitude in 25 ft steps with an offset of -1000 ft, 0xFFF means invalid
+        let altitude = self
+            .altitude
+            .map(|altitude| ((altitude + 1000) / 25).clamp(0, 0xFFE) as u16)
+            .unwrap_or(0xFFF);
+        // Miscellaneous indicators: airborne flag and "true track angle" as track type
+        let misc = if self.airborne { 0b1001 } else { 0b0001 };
+        payload.push((altitude >> 4) as u8);
+        payload.push(((altitude & 0x0F) << 4) as u8 | misc);
+        payload.push((self.nic & 0x0F) << 4 | (self.nacp & 0x0F));
+
+        // Horizontal velocity in knots (0xFFF: unknown), vertical velocity in 64 fpm steps (0x800: unknown)
+        let horizontal_velocity = self
+            .horizontal_velocity
+            .map(|speed| speed.min(0xFFE))
+            .unwrap_or(0xFFF);
+        let vertical_velocity = self
+            .vertical_velocity
+            .map(|climb_rate| (climb_rate / 64).clamp(-510, 510) as u16 & 0xFFF)
+            .unwrap_or(0x800);
+        payload.push((horizontal_velocity >> 4) as u8);
+        payload.push(((horizontal_velocity & 0x0F) << 4) as u8 | (vertical_velocity >> 8) as u8);
+        payload.push((vertical_velocity & 0xFF) as u8);
+
+        payload.push(
+            self.track
+                .map(|track| ((track % 360) as u32 * 256 / 360) as u8)
+                .unwrap_or(0),
+        );
+        payload.push(self.emitter_category);
+        payload.extend_from_slice(format!("{:<8.8}", self.callsign).as_bytes());
+        payload.push(0x00);
+
+        frame(&payload)
+    }
+}
+
+// Heartbeat (message id 0) with "GPS position valid" and "UAT initialized" set
+pub fn heartbeat(seconds_since_midnight: u32) -> Vec<u8> {
+    let timestamp = seconds_since_midnight % 86400;
+    let status_2 = if timestamp & 0x10000 != 0 { 0x80 } else { 0x00 };
+    frame(&[
+        MESSAGE_ID_HEARTBEAT,
+        0x81,
+        status_2,
+        (timestamp & 0xFF) as u8,
+        ((timestamp >> 8) & 0xFF) as u8,
+        0x00,
+        0x00,
+    ])
+}
+
+fn frame(payload: &[u8]) -> Vec<u8> {
+    let crc = crc16(payload);
+    let mut result = Vec::with_capacity(payload.len() + 6);
+    result.push(FLAG_BYTE);
+    for &byte in payload.iter().chain(crc.to_le_bytes().iter()) {
+        if byte == FLAG_BYTE || byte == CONTROL_ESCAPE {
+            result.push(CONTROL_ESCAPE);
+            result.push(byte ^ 0x20);
+        } else {
+            result.push(byte);
+        }
+    }
+    result.push(FLAG_BYTE);
+    result
+}
+
+// CRC-CCITT as defined in the GDL90 ICD
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        let mut entry = crc & 0xFF00;
+        for _ in 0..8 {
+            entry = (entry << 1) ^ if entry & 0x8000 != 0 { 0x1021 } else { 0 };
+        }
+        entry ^ (crc << 8) ^ byte as u16
+    })
+}
+
+// 24 bit signed binary fraction with a resolution of 180/2^23 degrees
+fn semicircles(degrees: f64) -> [u8; 3] {
+    let value = (degrees * f64::from(1 << 23) / 180.0).round() as i32;
+    let bytes = value.to_be_bytes();
+    [bytes[1], bytes[2], bytes[3]]
+}
+
+fn nacp_from_accuracy(meters: u16) -> u8 {
+    match meters {
+        0..=2 => 11,
+        3..=9 => 10,
+        10..=29 => 9,
+        30..=92 => 8,
+        93..=184 => 7,
+        185..=555 => 6,
+        _ => 0,
+    }
+}
+
+// OGN device callsigns (e.g. FLRDDFAA3) are too long, so we use the address part
+fn callsign(from: &str) -> String {
+    let callsign = match split_device_callsign(from) {
+        Some((_, address)) => address,
+        None => from,
+    };
+    callsign
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(8)
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unframe(frame: &[u8]) -> Vec<u8> {
+        assert_eq!(frame.first(), Some(&FLAG_BYTE));
+        assert_eq!(frame.last(), Some(&FLAG_BYTE));
+        let mut result = vec![];
+        let mut escaped = false;
+        for &byte in &frame[1..frame.len() - 1] {
+            if byte == CONTROL_ESCAPE {
+                escaped = true;
+            } else if escaped {
+                result.push(byte ^ 0x20);
+                escaped = false;
+            } else {
+                result.push(byte);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        // Example from the GDL90 ICD
+        assert_eq!(
+            frame(&[0x00, 0x81, 0x41, 0xDB, 0xD0, 0x08, 0x02]),
+            vec![0x7E, 0x00, 0x81, 0x41, 0xDB, 0xD0, 0x08, 0x02, 0xB3, 0x8B, 0x7E]
+        );
+        assert_eq!(
+            unframe(&heartbeat(3600))[..5],
+            [0x00, 0x81, 0x00, 0x10, 0x0E]
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let result = frame(&[0x7E, 0x7D]);
+        assert_eq!(&result[..5], &[0x7E, 0x7D, 0x5E, 0x7D, 0x5D]);
+        assert_eq!(unframe(&result)[..2], [0x7E, 0x7D]);
+    }
+
+    #[test]
+    fn test_traffic_report() {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 !W09! id213D17F2 -039fpm +0.0rot 2.5dB 3e -0.0kHz gps1x1".parse::<Message>().unwrap();
+        let report = TrafficReport::from_message(&message).unwrap();
+        assert_eq!(report.address_type, 0);
+        assert_eq!(report.address, 0x3D17F2);
+        assert_eq!(report.emitter_category, 1);
+        assert_eq!(report.callsign, "3D17F2");
+        assert_eq!(report.nacp, 11);
+
+        let payload = unframe(&report.encode());
+        assert_eq!(payload.len(), 28 + 2);
+        assert_eq!(payload[0], MESSAGE_ID_TRAFFIC_REPORT);
+        assert_eq!(&payload[2..5], &[0x3D, 0x17, 0xF2]);
+
+        let latitude = i32::from_be_bytes([0, payload[5], payload[6], payload[7]]);
+        assert!((latitude as f64 * 180.0 / f64::from(1 << 23) - 48.36016).abs() < 0.0001);
+
+        // (3054 ft + 1000 ft) / 25 ft = 162
+        let altitude = (payload[11] as u16) << 4 | (payload[12] >> 4) as u16;
+        assert_eq!(altitude, 162);
+
+        // 103 kt and -39 fpm / 64 = 0
+        let horizontal_velocity = (payload[14] as u16) << 4 | (payload[15] >> 4) as u16;
+        assert_eq!(horizontal_velocity, 103);
+        assert_eq!(payload[17], (322 * 256 / 360) as u8);
+        assert_eq!(&payload[19..27], b"3D17F2  ");
+        assert_eq!(
+            crc16(&payload[..28]).to_le_bytes(),
+            [payload[28], payload[29]]
+        );
+    }
+
+    #[test]
+    fn test_no_traffic_report_without_id() {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(TrafficReport::from_message(&message), None);
+    }
+}
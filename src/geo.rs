@@ -0,0 +1,46 @@
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+// Great circle distance in meters (haversine formula)
+pub fn distance(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (phi1, phi2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_phi = (latitude2 - latitude1).to_radians();
+    let delta_lambda = (longitude2 - longitude1).to_radians();
+    let a = (delta_phi / 2.0).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+// The point reached from a start point on a great circle with the given bearing in degrees
+pub fn destination(latitude: f64, longitude: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let (phi1, lambda1) = (latitude.to_radians(), longitude.to_radians());
+    let theta = bearing.to_radians();
+    let delta = distance / EARTH_RADIUS;
+    let phi2 = (phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos()).asin();
+    let lambda2 = lambda1
+        + (theta.sin() * delta.sin() * phi1.cos()).atan2(delta.cos() - phi1.sin() * phi2.sin());
+    let longitude = (lambda2.to_degrees() + 540.0) % 360.0 - 180.0;
+    (phi2.to_degrees(), longitude)
+}
+
+#[test]
+fn test_distance() {
+    assert_eq!(distance(48.0, 11.0, 48.0, 11.0), 0.0);
+    // One degree of latitude is about 111.2 km
+    assert!((distance(48.0, 11.0, 49.0, 11.0) - 111_195.0).abs() < 1.0);
+    // Munich to Berlin
+    assert!((distance(48.1374, 11.5755, 52.5200, 13.4050) - 504_000.0).abs() < 2_000.0);
+}
+
+#[test]
+fn test_destination() {
+    let (latitude, longitude) = destination(48.0, 11.0, 0.0, 111_195.0);
+    assert!((latitude - 49.0).abs() < 0.001);
+    assert!((longitude - 11.0).abs() < 0.001);
+
+    let (latitude, longitude) = destination(48.0, 11.0, 90.0, 10_000.0);
+    assert!((distance(48.0, 11.0, latitude, longitude) - 10_000.0).abs() < 1.0);
+    assert!(longitude > 11.0);
+
+    let (_, longitude) = destination(0.0, 179.99, 90.0, 10_000.0);
+    assert!(longitude < -179.9);
+}
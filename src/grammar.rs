@@ -0,0 +1,130 @@
+// The simple tokens of the comments are declared as a table of rules
+//     ("prefix", "suffix", value length, value type[, check]) => destination,
+// and comment_grammar! generates the matcher function for them. The destination is a
+// field of the comment or a field of an optional group like uas.ua_type. A rule only
+// applies while its destination is unset, the first applicable rule wins.
+// The matcher returns None if no rule applies, Some(false) if the value is invalid.
+macro_rules! comment_grammar {
+    (
+        $vis:vis fn $name:ident($comment:ty) {
+            $(
+                ($prefix:literal, $suffix:literal, $len:expr, $kind:path $(, $check:expr)?)
+                    => $field:ident $(. $inner:ident)?
+            ),* $(,)?
+        }
+    ) => {
+        $vis fn $name(comment: &mut $comment, part: &str) -> Option<bool> {
+            $(
+                if let Some(raw) = part
+                    .strip_prefix($prefix)
+                    .and_then(|rest| rest.strip_suffix($suffix))
+                {
+                    if ($len).contains(&raw.len())
+                        && comment_grammar!(@get comment $field $($inner)?).is_none()
+                    {
+                        let value = $kind(raw) $(.filter($check))?;
+                        return Some(match value {
+                            Some(value) => {
+                                comment_grammar!(@set comment value $field $($inner)?);
+                                true
+                            }
+                            None => false,
+                        });
+                    }
+                }
+            )*
+            None
+        }
+    };
+    (@get $comment:ident $field:ident) => {
+        $comment.$field
+    };
+    (@get $comment:ident $group:ident $field:ident) => {
+        $comment.$group.as_ref().and_then(|group| group.$field.as_ref())
+    };
+    (@set $comment:ident $value:ident $field:ident) => {
+        $comment.$field = Some($value)
+    };
+    (@set $comment:ident $value:ident $group:ident $field:ident) => {
+        $comment.$group.get_or_insert_with(Default::default).$field = Some($value)
+    };
+}
+
+pub(crate) use comment_grammar;
+
+// The value types of the rules
+
+pub(crate) fn digits<T: std::str::FromStr>(raw: &str) -> Option<T> {
+    crate::utils::parse_digits(raw)
+}
+
+pub(crate) fn decimal(raw: &str) -> Option<f32> {
+    crate::utils::parse_decimal(raw, false)
+}
+
+pub(crate) fn signed_decimal(raw: &str) -> Option<f32> {
+    crate::utils::parse_decimal(raw, true)
+}
+
+// Anything str::parse::<f32> accepts, for the receiver status
+pub(crate) fn float(raw: &str) -> Option<f32> {
+    raw.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Group {
+        count: Option<u8>,
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Comment {
+        level: Option<f32>,
+        name: Option<String>,
+        group: Option<Group>,
+    }
+
+    fn text(raw: &str) -> Option<String> {
+        Some(raw.to_string())
+    }
+
+    comment_grammar! {
+        fn match_token(Comment) {
+            ("L", "m", 1.., signed_decimal) => level,
+            ("N:", "", 1..=4, text, |name: &String| name != "none") => name,
+            ("C=", "", 1.., digits, |count: &u8| *count <= 9) => group.count,
+        }
+    }
+
+    #[test]
+    fn test_comment_grammar() {
+        let mut comment = Comment::default();
+        assert_eq!(match_token(&mut comment, "L-12.5m"), Some(true));
+        assert_eq!(match_token(&mut comment, "N:abcd"), Some(true));
+        assert_eq!(match_token(&mut comment, "C=7"), Some(true));
+        assert_eq!(
+            comment,
+            Comment {
+                level: Some(-12.5),
+                name: Some("abcd".to_string()),
+                group: Some(Group { count: Some(7) }),
+            }
+        );
+
+        // The destinations are set
+        assert_eq!(match_token(&mut comment, "L3m"), None);
+        assert_eq!(match_token(&mut comment, "C=1"), None);
+
+        let mut comment = Comment::default();
+        assert_eq!(match_token(&mut comment, "Lm"), None);
+        assert_eq!(match_token(&mut comment, "L12"), None);
+        assert_eq!(match_token(&mut comment, "N:abcde"), None);
+        assert_eq!(match_token(&mut comment, "Lxm"), Some(false));
+        assert_eq!(match_token(&mut comment, "N:none"), Some(false));
+        assert_eq!(match_token(&mut comment, "C=12"), Some(false));
+        assert_eq!(comment, Comment::default());
+    }
+}
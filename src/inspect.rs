@@ -0,0 +1,172 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::position_comment::PositionComment;
+use crate::status_comment::StatusComment;
+use crate::utils::split_whitespace_with_offsets;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FormatReport {
+    // "position", "status", "message", "unknown" or "invalid"
+    pub packet_type: String,
+    pub parser_error: Option<String>,
+    pub tocall: Option<String>,
+    // "position_comment" or "status_comment"
+    pub comment_dialect: Option<String>,
+    pub tokens: Vec<TokenReport>,
+    pub unrecognized: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TokenReport {
+    pub token: String,
+    // Byte offset in the raw string
+    pub start: usize,
+    // The fields set by this token, empty if the token was not recognized
+    pub fields: Vec<String>,
+}
+
+pub fn inspect(line: &str) -> FormatReport {
+    let message = line.parse::<Message>().unwrap();
+    let packet = match &message.aprs_packet {
+        Ok(packet) => packet,
+        Err(error) => {
+            return FormatReport {
+                packet_type: "invalid".to_string(),
+                parser_error: Some(error.to_string()),
+                tocall: None,
+                comment_dialect: None,
+                tokens: vec![],
+                unrecognized: vec![],
+            }
+        }
+    };
+
+    let (packet_type, comment, dialect) = match &packet.data {
+        AprsData::Position(position) => {
+            ("position", Some(&position.comment), Some(Dialect::Position))
+        }
+        AprsData::Item(item) => (
+            "item",
+            Some(&item.position.comment),
+            Some(Dialect::Position),
+        ),
+        AprsData::Status(status) => ("status", Some(&status.comment), Some(Dialect::Status)),
+        AprsData::Message(_) => ("message", None, None),
+        AprsData::Telemetry(_) => ("telemetry", None, None),
+        AprsData::Weather(_) => ("weather", None, None),
+        AprsData::ThirdParty(_) => ("third_party", None, None),
+        AprsData::Unknown => ("unknown", None, None),
+    };
+
+    // Parse the comment token by token and look which fields each token adds
+    let mut tokens = vec![];
+    if let (Some(comment), Some(dialect)) = (comment, dialect) {
+        let offset = line.rfind(comment.as_str()).unwrap_or(0);
+        let parts = split_whitespace_with_offsets(comment).collect::<Vec<_>>();
+        let mut previous = dialect.parse("");
+        for (idx, (start, part)) in parts.iter().enumerate() {
+            let prefix = parts[..=idx]
+                .iter()
+                .map(|(_, part)| *part)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let current = dialect.parse(&prefix);
+            tokens.push(TokenReport {
+                token: part.to_string(),
+                start: offset + start,
+                fields: changed_fields(&previous, &current),
+            });
+            previous = current;
+        }
+    }
+
+    FormatReport {
+        packet_type: packet_type.to_string(),
+        parser_error: None,
+        tocall: Some(packet.to.to_string()),
+        comment_dialect: dialect.map(|dialect| dialect.name().to_string()),
+        unrecognized: tokens
+            .iter()
+            .filter(|token| token.fields.is_empty())
+            .map(|token| token.token.clone())
+            .collect(),
+        tokens,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Dialect {
+    Position,
+    Status,
+}
+
+impl Dialect {
+    fn name(self) -> &'static str {
+        match self {
+            Dialect::Position => "position_comment",
+            Dialect::Status => "status_comment",
+        }
+    }
+
+    fn parse(self, s: &str) -> Value {
+        match self {
+            Dialect::Position => serde_json::to_value(s.parse::<PositionComment>().unwrap()),
+            Dialect::Status => serde_json::to_value(s.parse::<StatusComment>().unwrap()),
+        }
+        .unwrap()
+    }
+}
+
+fn changed_fields(previous: &Value, current: &Value) -> Vec<String> {
+    current
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, value)| *key != "unparsed" && previous.get(key.as_str()) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_position() {
+        let report = inspect(
+            r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 !W09! -039fpm foo",
+        );
+        assert_eq!(report.packet_type, "position");
+        assert_eq!(report.tocall, Some("APRS".to_string()));
+        assert_eq!(report.comment_dialect, Some("position_comment".to_string()));
+        assert_eq!(report.tokens.len(), 4);
+        assert_eq!(report.tokens[0].token, "322/103/A=003054");
+        assert_eq!(report.tokens[0].start, 53);
+        assert_eq!(report.tokens[0].fields.len(), 3);
+        assert!(report.tokens[0].fields.contains(&"altitude".to_string()));
+        assert_eq!(report.tokens[1].fields, vec!["additional_precision"]);
+        assert_eq!(report.tokens[2].fields, vec!["climb_rate"]);
+        assert_eq!(report.unrecognized, vec!["foo"]);
+    }
+
+    #[test]
+    fn test_inspect_status() {
+        let report = inspect("ICA3D17F2>APRS,qAS,dl4mea:>312359zv0.2.7.RPI-GPU CPU:0.7 bar");
+        assert_eq!(report.packet_type, "status");
+        // version, platform and platform_kind
+        assert_eq!(report.tokens[0].fields.len(), 3);
+        assert_eq!(report.tokens[1].fields, vec!["cpu_load"]);
+        assert_eq!(report.unrecognized, vec!["bar"]);
+    }
+
+    #[test]
+    fn test_inspect_invalid() {
+        let report = inspect("not an aprs packet");
+        assert_eq!(report.packet_type, "invalid");
+        assert!(report.parser_error.is_some());
+        assert!(report.tokens.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::geo::destination;
+use crate::track::Fix;
+
+const KNOTS_TO_MS: f64 = 0.514444;
+
+// Fills the gaps between sparse beacons with synthetic fixes, e.g. for smooth map animations
+pub struct Interpolator<K> {
+    interval: i64,
+    max_gap: i64,
+    last: HashMap<K, Fix>,
+}
+
+impl<K: Eq + Hash> Interpolator<K> {
+    // interval: seconds between the synthetic fixes
+    // max_gap: longer gaps are not filled, the aircraft may have landed or left the coverage
+    pub fn new(interval: i64, max_gap: i64) -> Self {
+        Interpolator {
+            interval: interval.max(1),
+            max_gap,
+            last: HashMap::new(),
+        }
+    }
+
+    // Returns the synthetic fixes since the previous fix of this aircraft, followed by the fix itself
+    pub fn push(&mut self, key: K, fix: Fix) -> Vec<Fix> {
+        let mut result = match self.last.get(&key) {
+            Some(previous)
+                if previous.time < fix.time && fix.time - previous.time <= self.max_gap =>
+            {
+                (1..)
+                    .map(|step| previous.time + step * self.interval)
+                    .take_while(|time| *time < fix.time)
+                    .map(|time| interpolate(previous, &fix, time))
+                    .collect()
+            }
+            _ => vec![],
+        };
+        // Out of order fixes are passed on, but don't replace the newer fix
+        match self.last.get(&key) {
+            Some(previous) if previous.time >= fix.time => {}
+            _ => {
+                self.last.insert(key, fix.clone());
+            }
+        }
+        result.push(fix);
+        result
+    }
+}
+
+// Dead reckoning forward from the previous fix and backward from the next fix,
+// blended by time so the synthetic track ends exactly at the next fix
+pub fn interpolate(previous: &Fix, next: &Fix, time: i64) -> Fix {
+    let duration = (next.time - previous.time) as f64;
+    let weight = (time - previous.time) as f64 / duration;
+
+    let forward = dead_reckoning(previous, (time - previous.time) as f64);
+    let backward = dead_reckoning(next, (time - next.time) as f64);
+    let blend = |a: f64, b: f64| a * (1.0 - weight) + b * weight;
+    let blend_option = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => Some(blend(a, b)),
+        (a, b) => a.or(b),
+    };
+
+    let altitude = match (previous.altitude, next.altitude) {
+        (Some(a), Some(b)) => {
+            let climb = |fix: &Fix, a: f64, dt: f64| a + fix.climb_rate.unwrap_or(0.0) * dt / 60.0;
+            Some(blend(
+                climb(previous, a, (time - previous.time) as f64),
+                climb(next, b, (time - next.time) as f64),
+            ))
+        }
+        (a, b) => a.or(b),
+    };
+
+    Fix {
+        time,
+        latitude: blend(forward.0, backward.0),
+        longitude: blend_longitude(forward.1, backward.1, weight),
+        altitude,
+        course: blend_course(previous.course, next.course, weight),
+        speed: blend_option(previous.speed, next.speed),
+        climb_rate: blend_option(previous.climb_rate, next.climb_rate),
+        synthetic: true,
+    }
+}
+
+// seconds can be negative to go backwards in time
+fn dead_reckoning(fix: &Fix, seconds: f64) -> (f64, f64) {
+    match (fix.course, fix.speed) {
+        (Some(course), Some(speed)) if speed > 0.0 => destination(
+            fix.latitude,
+            fix.longitude,
+            course,
+            speed * KNOTS_TO_MS * seconds,
+        ),
+        _ => (fix.latitude, fix.longitude),
+    }
+}
+
+fn blend_longitude(a: f64, b: f64, weight: f64) -> f64 {
+    // Take the short way around the antimeridian
+    let delta = (b - a + 540.0) % 360.0 - 180.0;
+    (a + delta * weight + 540.0) % 360.0 - 180.0
+}
+
+fn blend_course(a: Option<f64>, b: Option<f64>, weight: f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let delta = (b - a + 540.0) % 360.0 - 180.0;
+            Some((a + delta * weight).rem_euclid(360.0))
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::distance;
+
+    fn fix(time: i64, latitude: f64, course: f64, speed: f64, altitude: f64) -> Fix {
+        Fix {
+            time,
+            latitude,
+            longitude: 11.0,
+            altitude: Some(altitude),
+            course: Some(course),
+            speed: Some(speed),
+            climb_rate: Some(0.0),
+            synthetic: false,
+        }
+    }
+
+    #[test]
+    fn test_straight_flight() {
+        // 100 kt to the north for 20 s are about 1029 m
+        let first = fix(0, 48.0, 0.0, 100.0, 1000.0);
+        let (latitude, _) = destination(48.0, 11.0, 0.0, 100.0 * KNOTS_TO_MS * 20.0);
+        let last = fix(20, latitude, 0.0, 100.0, 2000.0);
+
+        let mut interpolator = Interpolator::new(5, 60);
+        assert_eq!(interpolator.push("FLRDDFAA3", first.clone()), vec![first]);
+        let result = interpolator.push("FLRDDFAA3", last.clone());
+        assert_eq!(result.len(), 4);
+        assert!(result[..3].iter().all(|fix| fix.synthetic));
+        assert_eq!(result[3], last);
+
+        let middle = &result[1];
+        assert_eq!(middle.time, 10);
+        let expected = destination(48.0, 11.0, 0.0, 100.0 * KNOTS_TO_MS * 10.0);
+        assert!(distance(middle.latitude, middle.longitude, expected.0, expected.1) < 1.0);
+        assert_eq!(middle.altitude, Some(1500.0));
+    }
+
+    #[test]
+    fn test_gaps_and_order() {
+        let mut interpolator = Interpolator::new(5, 60);
+        let mut push = |key, time| interpolator.push(key, fix(time, 48.1, 0.0, 100.0, 1000.0));
+        assert_eq!(push(1, 0).len(), 1);
+        // Gap too long
+        assert_eq!(push(1, 100).len(), 1);
+        // Out of order
+        assert_eq!(push(1, 90).len(), 1);
+        // Other aircraft
+        assert_eq!(push(2, 110).len(), 1);
+        assert_eq!(push(1, 110).len(), 2);
+    }
+
+    #[test]
+    fn test_blend_angles() {
+        assert_eq!(blend_course(Some(350.0), Some(10.0), 0.5), Some(0.0));
+        assert!((blend_longitude(179.0, -179.0, 0.25) - 179.5).abs() < 1e-9);
+    }
+}
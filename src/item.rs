@@ -0,0 +1,101 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::error::AprsError;
+use crate::position::AprsPosition;
+
+// An item report ")NAME!4903.50N/07201.75W>comment", a position without timestamp of
+// something that is not a station, e.g. a landing field or a thermal hotspot
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AprsItem {
+    // 3-9 characters
+    pub name: String,
+    // '!': live, '_': killed
+    pub live: bool,
+    // The position and the comment of the item, without timestamp
+    pub position: AprsPosition,
+}
+
+impl FromStr for AprsItem {
+    type Err = AprsError;
+
+    // Parses the information field without the data type identifier ')'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The name ends with the first '!' or '_' of the first 10 characters
+        let (idx, live) = s
+            .char_indices()
+            .take(10)
+            .find_map(|(idx, c)| match c {
+                '!' => Some((idx, true)),
+                '_' => Some((idx, false)),
+                _ => None,
+            })
+            .filter(|(idx, _)| *idx >= 3)
+            .ok_or_else(|| AprsError::InvalidItem(s.to_string()))?;
+        let name = &s[..idx];
+        // The position is the one of a position report without timestamp
+        let position = format!("!{}", &s[idx + 1..]).parse::<AprsPosition>()?;
+        Ok(AprsItem {
+            name: name.to_string(),
+            live,
+            position,
+        })
+    }
+}
+
+impl Display for AprsItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let position = self.position.to_string();
+        write!(
+            f,
+            "){}{}{}",
+            self.name,
+            if self.live { '!' } else { '_' },
+            position.strip_prefix('!').unwrap_or(&position)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item() {
+        let item = "AID #2!4903.50N/07201.75WA Aid station"
+            .parse::<AprsItem>()
+            .unwrap();
+        assert_eq!(item.name, "AID #2");
+        assert!(item.live);
+        assert_eq!(item.position.latitude, 49.05833333333333);
+        assert_eq!(item.position.longitude, -72.02916666666667);
+        assert_eq!(item.position.symbol_code, 'A');
+        assert_eq!(item.position.comment, " Aid station");
+        assert_eq!(item.to_string(), ")AID #2!4903.50N/07201.75WA Aid station");
+
+        let item = "MOBIL_/5L!!<*e7>7P[".parse::<AprsItem>().unwrap();
+        assert!(!item.live);
+        assert!(item.position.compression.is_some());
+        assert_eq!(item.to_string(), ")MOBIL_/5L!!<*e7>7P[");
+    }
+
+    #[test]
+    fn test_item_errors() {
+        for invalid in [
+            "AB!4903.50N/07201.75WA",
+            "ABCDEFGHIJ!4903.50N/07201.75WA",
+            "ABCD",
+        ] {
+            assert_eq!(
+                invalid.parse::<AprsItem>(),
+                Err(AprsError::InvalidItem(invalid.to_string()))
+            );
+        }
+        assert!(matches!(
+            "ABC!4903.50X/07201.75WA".parse::<AprsItem>(),
+            Err(AprsError::InvalidLatitude(_))
+        ));
+    }
+}
@@ -1,18 +1,97 @@
+pub mod aggregation;
+mod aprs_message;
+pub mod archive;
+pub mod batch;
+pub mod builder;
+mod callsign;
+pub mod category;
+pub mod compare;
+pub mod corpus;
+mod cursor;
+pub mod detection;
+pub mod dialect;
+pub mod diff;
+pub mod encode;
+mod error;
+pub mod feed;
+pub mod filter;
+pub mod flavor;
+pub mod gdl90;
+pub mod geo;
+mod grammar;
+pub mod icao;
+mod inspect;
+pub mod interpolation;
+mod item;
+mod limits;
+pub mod login;
 mod message;
+#[cfg(feature = "test-support")]
+pub mod mock_server;
+pub mod ndjson;
+pub mod order;
+pub mod output;
+mod packet;
+mod path;
+pub mod pipeline;
+mod position;
 mod position_comment;
+pub mod pretty;
+pub mod profiling;
+pub mod provenance;
+pub mod pseudonym;
 mod python_functions;
+pub mod quality;
+pub mod reception;
+#[cfg(feature = "relay")]
+pub mod relay;
+pub mod rollup;
+pub mod schema;
+mod server_comment;
+pub mod stealth;
+mod status;
 mod status_comment;
-mod utils;
+pub mod storage;
+mod telemetry;
+pub mod time;
+mod timestamp;
+pub mod track;
+pub mod utils;
+pub mod vendor;
+mod weather;
 
-use crate::python_functions::{parse, parse_to_json};
+use crate::python_functions::{features, inspect as py_inspect, parse, parse_to_json};
 use pyo3::prelude::*;
 
-pub use message::Message;
+pub use aprs_message::{AprsMessage, MessageKind};
+pub use callsign::Callsign;
+pub use error::AprsError;
+pub use inspect::{inspect, FormatReport, TokenReport};
+pub use item::AprsItem;
+pub use limits::Limits;
+pub use message::{EmbeddedMessage, Message, RawData};
+pub use packet::{AprsData, AprsHeader, AprsPacket};
+pub use path::{PathEntry, QConstruct};
+pub use position::{AprsPosition, CompressedExtension, Compression, MicE};
+pub use position_comment::{
+    AdditionalPrecision, AddressType, AdsB, Balloon, BearingReport, DerivedMotion, ExternalTracker,
+    Fanet, FlyMaster, PilotAware, PositionComment, Uas, ID,
+};
+pub use server_comment::ServerComment;
+pub use status::AprsStatus;
+pub use status_comment::{PlatformKind, StatusComment, TrackerStatus, MAINTENANCE_KEYWORDS};
+pub use telemetry::{AprsTelemetry, TelemetryDefinition};
+pub use timestamp::Timestamp;
+pub use weather::{AprsWeather, WeatherComment};
 
 #[pymodule]
 fn ognparser(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.add_function(wrap_pyfunction!(parse, m)?)?;
     m.add_function(wrap_pyfunction!(parse_to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(py_inspect, m)?)?;
+    m.add_function(wrap_pyfunction!(features, m)?)?;
+    #[cfg(feature = "mmap")]
+    m.add_function(wrap_pyfunction!(python_functions::read_archive, m)?)?;
     Ok(())
 }
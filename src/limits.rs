@@ -0,0 +1,120 @@
+use crate::error::AprsError;
+
+// Upper bounds for a single line, so pathological or malicious feed lines can't
+// make a long running service spend unbounded time or memory on them
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    // In bytes, APRS-IS itself doesn't forward lines longer than 512 bytes
+    pub max_line_length: usize,
+    // In bytes, for the unparsed rest of position and status comments
+    pub max_unparsed_length: usize,
+    // Whitespace separated tokens in the whole line
+    pub max_tokens: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_line_length: 512,
+            max_unparsed_length: 256,
+            max_tokens: 64,
+        }
+    }
+}
+
+impl Limits {
+    // Checks everything that can be checked before parsing
+    pub(crate) fn check_line(&self, line: &str) -> Result<(), AprsError> {
+        if line.len() > self.max_line_length {
+            return Err(AprsError::LimitExceeded(format!(
+                "line length {} > {}",
+                line.len(),
+                self.max_line_length
+            )));
+        }
+        // Stops counting at the limit, the line can still be huge if max_line_length is
+        if line.split_whitespace().nth(self.max_tokens).is_some() {
+            return Err(AprsError::LimitExceeded(format!(
+                "more than {} tokens",
+                self.max_tokens
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_unparsed(&self, unparsed: Option<&str>) -> Result<(), AprsError> {
+        match unparsed {
+            Some(unparsed) if unparsed.len() > self.max_unparsed_length => {
+                Err(AprsError::LimitExceeded(format!(
+                    "unparsed length {} > {}",
+                    unparsed.len(),
+                    self.max_unparsed_length
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+
+    const BEACON: &str =
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm";
+
+    #[test]
+    fn test_within_limits() {
+        let message = Message::parse_with_limits(BEACON, &Limits::default());
+        assert_eq!(message, BEACON.parse::<Message>().unwrap());
+    }
+
+    #[test]
+    fn test_line_length() {
+        let limits = Limits {
+            max_line_length: 40,
+            ..Limits::default()
+        };
+        let message = Message::parse_with_limits(BEACON, &limits);
+        assert!(matches!(
+            message.aprs_packet,
+            Err(AprsError::LimitExceeded(_))
+        ));
+        assert_eq!(message.position_comment, None);
+        assert_eq!(message.raw_string, BEACON);
+    }
+
+    #[test]
+    fn test_tokens() {
+        let limits = Limits {
+            max_tokens: 2,
+            ..Limits::default()
+        };
+        assert!(Message::parse_with_limits(BEACON, &limits)
+            .aprs_packet
+            .is_err());
+        let limits = Limits {
+            max_tokens: 3,
+            ..Limits::default()
+        };
+        assert!(Message::parse_with_limits(BEACON, &limits)
+            .aprs_packet
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unparsed_length() {
+        let line = format!("{} {}", BEACON, "x".repeat(20));
+        let limits = Limits {
+            max_unparsed_length: 10,
+            ..Limits::default()
+        };
+        let message = Message::parse_with_limits(&line, &limits);
+        assert_eq!(
+            message.aprs_packet.unwrap_err().to_string(),
+            "Limit exceeded: unparsed length 20 > 10"
+        );
+        assert_eq!(message.position_comment, None);
+    }
+}
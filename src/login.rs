@@ -0,0 +1,76 @@
+// The login line of an APRS-IS client
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Login {
+    pub user: String,
+    pub pass: String,
+    // Software and version of the client
+    pub vers: Option<String>,
+    pub filter: Option<String>,
+    pub verified: bool,
+}
+
+impl Login {
+    // "user N0CALL pass -1 vers test 1.0 filter r/47/7/100"
+    pub fn parse(line: &str) -> Option<Login> {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let ["user", user, "pass", pass, rest @ ..] = &parts[..] else {
+            return None;
+        };
+        let mut vers = None;
+        let mut filter = None;
+        match rest {
+            ["vers", software, version, "filter", filters @ ..] => {
+                vers = Some(format!("{} {}", software, version));
+                filter = Some(filters.join(" "));
+            }
+            ["vers", software, version] => vers = Some(format!("{} {}", software, version)),
+            ["filter", filters @ ..] => filter = Some(filters.join(" ")),
+            _ => {}
+        }
+        Some(Login {
+            user: user.to_string(),
+            pass: pass.to_string(),
+            vers,
+            filter: filter.filter(|filter| !filter.is_empty()),
+            verified: pass.parse::<i32>().ok() == Some(passcode(user)),
+        })
+    }
+}
+
+// The APRS-IS passcode of a callsign, the SSID is ignored
+pub fn passcode(callsign: &str) -> i32 {
+    let callsign = callsign.split('-').next().unwrap_or_default();
+    let mut hash: u16 = 0x73e2;
+    for pair in callsign.to_ascii_uppercase().as_bytes().chunks(2) {
+        hash ^= u16::from(pair[0]) << 8;
+        if let Some(low) = pair.get(1) {
+            hash ^= u16::from(*low);
+        }
+    }
+    i32::from(hash & 0x7fff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login() {
+        assert_eq!(passcode("N0CALL"), 13023);
+        assert_eq!(passcode("n0call-10"), 13023);
+        assert_eq!(
+            Login::parse("user N0CALL pass 13023 vers test 1.0 filter r/47/7/100 t/p"),
+            Some(Login {
+                user: "N0CALL".to_string(),
+                pass: "13023".to_string(),
+                vers: Some("test 1.0".to_string()),
+                filter: Some("r/47/7/100 t/p".to_string()),
+                verified: true,
+            })
+        );
+        let login = Login::parse("user N0CALL pass -1").unwrap();
+        assert_eq!(login.vers, None);
+        assert!(!login.verified);
+        assert_eq!(Login::parse("hello"), None);
+    }
+}
@@ -1,43 +1,344 @@
+use crate::error::AprsError;
+use crate::flavor::BeaconFlavor;
+use crate::limits::Limits;
+use crate::packet::{AprsData, AprsHeader, AprsPacket};
 use crate::position_comment::*;
+use crate::provenance::Provenance;
+use crate::schema::SCHEMA_VERSION;
+use crate::server_comment::ServerComment;
 use crate::status_comment::*;
-use aprs_parser::AprsError;
-use aprs_parser::{AprsData, AprsPacket};
+use crate::telemetry::TelemetryDefinition;
+use crate::weather::WeatherComment;
 use serde::ser::SerializeStruct;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ParseMode {
+    Default,
+    Lenient,
+    Strict,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Message {
     pub raw_string: String,
     pub aprs_packet: Result<AprsPacket, AprsError>,
+    // Derived from the tocall of the packet
+    pub flavor: Option<BeaconFlavor>,
     pub position_comment: Option<PositionComment>,
+    pub weather_comment: Option<WeatherComment>,
     pub status_comment: Option<StatusComment>,
+    pub embedded_message: Option<EmbeddedMessage>,
+    pub telemetry_definition: Option<TelemetryDefinition>,
+    pub raw_data: Option<RawData>,
+    // Comment lines of the APRS server, they are no APRS packets
+    pub server_comment: Option<ServerComment>,
+    // Only set by the pipeline, parsing a line can't know its source
+    pub provenance: Option<Provenance>,
+}
+
+// The information field of packets with a data type we don't parse (yet)
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct RawData {
+    pub data_type: char,
+    pub body: String,
+}
+
+impl RawData {
+    fn from_header(header: &AprsHeader) -> Option<RawData> {
+        Some(RawData {
+            data_type: header.data_type()?,
+            body: header.body().to_string(),
+        })
+    }
+}
+
+// An APRS message appended to a position with messaging: ":ADDRESSEE:text{id"
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct EmbeddedMessage {
+    pub addressee: String,
+    pub text: String,
+    pub id: Option<String>,
+}
+
+impl EmbeddedMessage {
+    // Splits the comment into the position comment and the appended message
+    fn split_comment(comment: &str) -> Option<(&str, EmbeddedMessage)> {
+        comment.match_indices(':').find_map(|(idx, _)| {
+            let addressee = comment.get(idx + 1..idx + 10)?;
+            if comment.get(idx + 10..idx + 11) != Some(":")
+                || addressee.trim_end().is_empty()
+                || !addressee
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ' ')
+            {
+                return None;
+            }
+            let (text, id) = match comment[idx + 11..].rsplit_once('{') {
+                Some((text, id)) => (text, Some(id.to_string())),
+                None => (&comment[idx + 11..], None),
+            };
+            Some((
+                comment[..idx].trim_end(),
+                EmbeddedMessage {
+                    addressee: addressee.trim_end().to_string(),
+                    text: text.to_string(),
+                    id,
+                },
+            ))
+        })
+    }
 }
 
 impl FromStr for Message {
     type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Message::parse_comments(s, ParseMode::Default))
+    }
+}
+
+impl Message {
+    // Like parse(), but the position comment tolerates prefixes in the wrong case
+    // or separated from their value, e.g. "Gps3x5" or "FL 050.00"
+    pub fn parse_lenient(s: &str) -> Message {
+        Message::parse_comments(s, ParseMode::Lenient)
+    }
+
+    // Like parse(), but low confidence matches of the position comment are left unparsed
+    pub fn parse_strict(s: &str) -> Message {
+        Message::parse_comments(s, ParseMode::Strict)
+    }
+
+    fn parse_comments(s: &str, mode: ParseMode) -> Message {
         let aprs_packet = s.parse::<AprsPacket>();
+        let header = AprsHeader::parse(s).ok();
+
+        // The comments of third-party packets are the ones of the inner packet
+        if let (
+            Ok(AprsPacket {
+                data: AprsData::ThirdParty(_),
+                ..
+            }),
+            Some(header),
+        ) = (&aprs_packet, &header)
+        {
+            let inner = Message::parse_comments(header.body(), mode);
+            return Message {
+                raw_string: s.to_string(),
+                aprs_packet,
+                ..inner
+            };
+        }
+
+        let flavor = aprs_packet
+            .as_ref()
+            .ok()
+            .map(|packet| BeaconFlavor::from_tocall(&packet.to.call));
 
+        // Only positions with messaging ('=' or '@') can carry a message
+        let with_messaging = matches!(
+            header.as_ref().and_then(AprsHeader::data_type),
+            Some('=') | Some('@')
+        );
+
+        let mut embedded_message = None;
+        let mut weather_comment = None;
         let (position_comment, status_comment) = match &aprs_packet {
             Ok(packet) => match &packet.data {
                 AprsData::Position(position) => {
-                    (position.comment.parse::<PositionComment>().ok(), None)
+                    let mut comment = position.comment.as_str();
+                    if with_messaging {
+                        if let Some((rest, message)) = EmbeddedMessage::split_comment(comment) {
+                            comment = rest;
+                            embedded_message = Some(message);
+                        }
+                    }
+                    // Weather stations put the weather data before the position comment
+                    if position.symbol_code == '_' {
+                        if let Some((weather, rest)) = WeatherComment::split(comment) {
+                            comment = rest;
+                            weather_comment = Some(weather);
+                        }
+                    }
+                    let mut position_comment = Some(match mode {
+                        ParseMode::Default => comment.parse::<PositionComment>().unwrap(),
+                        ParseMode::Lenient => PositionComment::parse_lenient(comment),
+                        ParseMode::Strict => PositionComment::parse_strict(comment),
+                    });
+                    match (position_comment.as_mut(), flavor) {
+                        (Some(position_comment), Some(BeaconFlavor::Fanet)) => {
+                            position_comment.parse_fanet_tokens(packet.from.device_address())
+                        }
+                        (Some(position_comment), Some(BeaconFlavor::Naviter)) => {
+                            position_comment.parse_naviter_tokens()
+                        }
+                        (Some(position_comment), Some(BeaconFlavor::PilotAware)) => {
+                            position_comment.parse_pilot_aware_tokens()
+                        }
+                        (
+                            Some(position_comment),
+                            Some(BeaconFlavor::AdsB | BeaconFlavor::SafeSky),
+                        ) => position_comment.parse_adsb_tokens(),
+                        (
+                            Some(position_comment),
+                            Some(
+                                BeaconFlavor::Spot
+                                | BeaconFlavor::InReach
+                                | BeaconFlavor::Lt24
+                                | BeaconFlavor::Skylines
+                                | BeaconFlavor::Capturs,
+                            ),
+                        ) => position_comment.parse_external_tracker_tokens(),
+                        (Some(position_comment), Some(BeaconFlavor::FlyMaster)) => {
+                            position_comment.parse_flymaster_tokens()
+                        }
+                        _ => {}
+                    }
+                    (position_comment, None)
+                }
+                AprsData::Item(item) => {
+                    (item.position.comment.parse::<PositionComment>().ok(), None)
+                }
+                AprsData::Weather(weather) => {
+                    weather_comment = weather.comment.parse::<WeatherComment>().ok();
+                    (None, None)
+                }
+                AprsData::Status(status) => {
+                    let mut status_comment = status.comment.parse::<StatusComment>().ok();
+                    // Trackers send their own layout, the generic parser only takes the voltage
+                    if let (Some(status_comment), true) = (
+                        status_comment.as_mut(),
+                        flavor.is_some_and(|flavor| flavor.is_aircraft()),
+                    ) {
+                        status_comment.parse_tracker_tokens();
+                    }
+                    (None, status_comment)
                 }
-                AprsData::Status(status) => (None, status.comment.parse::<StatusComment>().ok()),
-                AprsData::Message(_) | AprsData::Unknown => (None, None),
+                AprsData::Message(_)
+                | AprsData::Telemetry(_)
+                | AprsData::ThirdParty(_)
+                | AprsData::Unknown => (None, None),
             },
             Err(_) => (None, None),
         };
 
-        Ok(Message {
+        let telemetry_definition = match &aprs_packet {
+            Ok(AprsPacket {
+                from,
+                data: AprsData::Message(message),
+                ..
+            }) => TelemetryDefinition::from_message(from, message),
+            _ => None,
+        };
+
+        let raw_data = match &aprs_packet {
+            Ok(AprsPacket {
+                data: AprsData::Unknown,
+                ..
+            }) => header.as_ref().and_then(RawData::from_header),
+            _ => None,
+        };
+
+        let server_comment = match &aprs_packet {
+            Err(_) if s.starts_with('#') => s.parse::<ServerComment>().ok(),
+            _ => None,
+        };
+
+        Message {
             raw_string: s.to_string(),
             aprs_packet,
+            flavor,
             position_comment,
+            weather_comment,
             status_comment,
-        })
+            embedded_message,
+            telemetry_definition,
+            raw_data,
+            server_comment,
+            provenance: None,
+        }
+    }
+}
+
+// Exported messages always contain the raw string, so we can reparse it to get the same types
+#[derive(Deserialize)]
+struct JsonRecord {
+    raw_string: String,
+}
+
+impl Message {
+    // Like parse(), but lines exceeding the limits are rejected with AprsError::LimitExceeded
+    // instead of being parsed: only the raw string is kept
+    pub fn parse_with_limits(s: &str, limits: &Limits) -> Message {
+        let rejected = |error| Message::rejected(s, error);
+        if let Err(error) = limits.check_line(s) {
+            return rejected(error);
+        }
+        let message = s.parse::<Message>().unwrap();
+        let unparsed = [
+            message
+                .position_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_deref()),
+            message
+                .status_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_deref()),
+        ];
+        let checked = unparsed
+            .into_iter()
+            .try_for_each(|unparsed| limits.check_unparsed(unparsed));
+        match checked {
+            Ok(()) => message,
+            Err(error) => rejected(error),
+        }
+    }
+
+    // Only the raw string is kept
+    pub(crate) fn rejected(s: &str, error: AprsError) -> Message {
+        Message {
+            raw_string: s.to_string(),
+            aprs_packet: Err(error),
+            flavor: None,
+            position_comment: None,
+            weather_comment: None,
+            status_comment: None,
+            embedded_message: None,
+            telemetry_definition: None,
+            raw_data: None,
+            server_comment: None,
+            provenance: None,
+        }
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let record = serde_json::from_str::<JsonRecord>(s)?;
+        Ok(record.raw_string.parse::<Message>().unwrap())
+    }
+
+    // The message of the encoded packet: its raw string is in our layout, the parsed
+    // structures are the same and normalizing it again changes nothing.
+    // Parse failures and server comments are kept as they are. The encoded packet is
+    // parsed with parse(), messages of parse_lenient() or parse_strict() are normalized
+    // with normalize_with() and the same function.
+    pub fn normalize(&self) -> Message {
+        self.normalize_with(|s| s.parse().unwrap())
+    }
+
+    pub fn normalize_with<F: Fn(&str) -> Message>(&self, parse: F) -> Message {
+        let Ok(packet) = &self.aprs_packet else {
+            return self.clone();
+        };
+        let mut raw_string = packet.to_string();
+        // The information field of unknown data types is only kept as raw data
+        if let (AprsData::Unknown, Some(raw_data)) = (&packet.data, &self.raw_data) {
+            raw_string.push(raw_data.data_type);
+            raw_string.push_str(&raw_data.body);
+        }
+        parse(&raw_string)
     }
 }
 
@@ -46,17 +347,469 @@ impl Serialize for Message {
     where
         S: serde::Serializer,
     {
-        // 4 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 4)?;
+        // 12 is the number of fields in the struct.
+        let mut state = serializer.serialize_struct("Message", 12)?;
+        state.serialize_field("schema_version", &SCHEMA_VERSION)?;
         state.serialize_field("raw_string", &self.raw_string)?;
 
-        match &self.aprs_packet {
-            Ok(packet) => state.serialize_field("aprs_packet", packet)?,
-            Err(e) => state.serialize_field("parser_error", &e.to_string())?,
+        // Server comments are no parse errors
+        match (&self.aprs_packet, &self.server_comment) {
+            (Ok(packet), _) => state.serialize_field("aprs_packet", packet)?,
+            (Err(_), Some(_)) => {}
+            (Err(e), None) => state.serialize_field("parser_error", &e.to_string())?,
         }
 
+        state.serialize_field("flavor", &self.flavor)?;
         state.serialize_field("position_comment", &self.position_comment)?;
+        state.serialize_field("weather_comment", &self.weather_comment)?;
         state.serialize_field("status_comment", &self.status_comment)?;
+        state.serialize_field("embedded_message", &self.embedded_message)?;
+        state.serialize_field("telemetry_definition", &self.telemetry_definition)?;
+        state.serialize_field("raw_data", &self.raw_data)?;
+        state.serialize_field("server_comment", &self.server_comment)?;
+        state.serialize_field("provenance", &self.provenance)?;
         state.end()
     }
 }
+
+#[test]
+fn test_from_json() {
+    let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 !W09! id213D17F2 -039fpm +0.0rot 2.5dB 3e -0.0kHz gps1x1"
+        .parse::<Message>()
+        .unwrap();
+    let json = serde_json::to_string(&message).unwrap();
+    assert_eq!(Message::from_json(&json).unwrap(), message);
+}
+
+#[test]
+fn test_from_json_without_raw_string() {
+    assert!(Message::from_json(r#"{"position_comment": null}"#).is_err());
+    assert!(Message::from_json("no json").is_err());
+}
+
+#[test]
+fn test_embedded_message() {
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi:=4821.61N\01224.49E^322/103/A=003054 id213D17F2 :OGNDDFAA3:Hello world{42"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(
+        message.embedded_message,
+        Some(EmbeddedMessage {
+            addressee: "OGNDDFAA3".to_string(),
+            text: "Hello world".to_string(),
+            id: Some("42".to_string()),
+        })
+    );
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.altitude, Some(3054));
+    assert_eq!(position_comment.unparsed, None);
+
+    let message =
+        r"FLRDDFAA3>APRS,qAS,Letzi:=4821.61N\01224.49E^322/103/A=003054 :BLN1     :Fly safe"
+            .parse::<Message>()
+            .unwrap();
+    let embedded_message = message.embedded_message.unwrap();
+    assert_eq!(embedded_message.addressee, "BLN1");
+    assert_eq!(embedded_message.id, None);
+
+    // Positions without messaging can't carry a message
+    let message =
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074849h4821.61N\01224.49E^322/103/A=003054 :OGNDDFAA3:Hello"
+            .parse::<Message>()
+            .unwrap();
+    assert_eq!(message.embedded_message, None);
+    assert!(message.position_comment.unwrap().unparsed.is_some());
+}
+
+#[test]
+fn test_raw_data() {
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi:{Q1qwerty"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(
+        message.raw_data,
+        Some(RawData {
+            data_type: '{',
+            body: "Q1qwerty".to_string(),
+        })
+    );
+
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi:T#005,199,000,255,073,123,01101001"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.raw_data, None);
+
+    let message = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.raw_data, None);
+}
+
+#[test]
+fn test_telemetry_definition() {
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi::FLRDDFAA3:UNIT.V,deg.C"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(
+        message.telemetry_definition,
+        Some(TelemetryDefinition::Units(vec![
+            "V".to_string(),
+            "deg.C".to_string()
+        ]))
+    );
+
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi::OGNDDFAA3:Hello"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.telemetry_definition, None);
+
+    let message = r"FLRDDFAA3>APRS,qAS,Letzi::OGNDDFAA3:UNIT.V,deg.C"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.telemetry_definition, None);
+}
+
+#[test]
+fn test_item() {
+    let message = r"Letzi>APRS,TCPIP*,qAC,GLIDERN2:)LSZK-RWY!4708.53N/00846.90E/ 22/04"
+        .parse::<Message>()
+        .unwrap();
+    match message.aprs_packet.as_ref().map(|packet| &packet.data) {
+        Ok(AprsData::Item(item)) => assert_eq!(item.name, "LSZK-RWY"),
+        _ => panic!("Unexpected packet: {:?}", message.aprs_packet),
+    }
+    assert_eq!(
+        message.position_comment.unwrap().unparsed,
+        Some("22/04".to_string())
+    );
+}
+
+#[test]
+fn test_weather_comment() {
+    let message = r"FNT11033F>OGNFNT,qAS,LSXI2:/163435h4629.61N/00829.14E_349/002g004t034h95b10152 29.0dB -8.8kHz"
+        .parse::<Message>()
+        .unwrap();
+    let weather_comment = message.weather_comment.unwrap();
+    assert_eq!(weather_comment.wind_direction, Some(349));
+    assert_eq!(weather_comment.barometric_pressure, Some(10152));
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.signal_quality, Some(29.0));
+    assert_eq!(position_comment.course, None);
+    assert_eq!(position_comment.unparsed, None);
+
+    let message = r"FNT11033F>APRS,qAS,LSXI2:_10090556c220s004g005t077r000p000P000h50b09900wRSW"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.weather_comment.unwrap().temperature, Some(77));
+    assert_eq!(message.raw_data, None);
+}
+
+#[test]
+fn test_server_comment() {
+    let message = "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152"
+        .parse::<Message>()
+        .unwrap();
+    assert!(message.aprs_packet.is_err());
+    assert_eq!(
+        message.server_comment.as_ref().unwrap().server,
+        Some("GLIDERN3".to_string())
+    );
+    let record = serde_json::to_value(&message).unwrap();
+    assert!(record.get("parser_error").is_none());
+    assert_eq!(record["server_comment"]["software"], "aprsc");
+
+    let message = "not an aprs packet".parse::<Message>().unwrap();
+    assert_eq!(message.server_comment, None);
+}
+
+#[test]
+fn test_third_party() {
+    let message = r"FNB1103CE>OGNFNT,qAS,Letzi:}FNT1103CE>OGNFNT,TCPIP,FNB1103CE*:/183734h5057.94N/00801.00Eg355/002/A=001042 !W10! +000fpm 12.3dB"
+        .parse::<Message>()
+        .unwrap();
+    assert!(matches!(
+        message.aprs_packet.unwrap().data,
+        AprsData::ThirdParty(_)
+    ));
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.climb_rate, Some(0));
+    assert_eq!(position_comment.signal_quality, Some(12.3));
+    assert!(message.raw_string.starts_with("FNB1103CE>"));
+}
+
+#[test]
+fn test_parse_lenient() {
+    let line =
+        r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3 GPS3x5";
+    assert_eq!(
+        line.parse::<Message>()
+            .unwrap()
+            .position_comment
+            .unwrap()
+            .unparsed,
+        Some("GPS3x5".into())
+    );
+    let message = Message::parse_lenient(line);
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.gps_quality, Some("3x5".into()));
+    assert_eq!(position_comment.unparsed, None);
+}
+
+#[test]
+fn test_normalize() {
+    let lines = [
+        r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 !W87! id06DDFAA3 -019fpm +0.0rot 5.5dB 3e -4.3kHz gps2x3",
+        r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765",
+        r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>074600h v0.2.8.RPI-GPU CPU:0.5 RAM:770.2/968.2MB",
+        r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.  N\01224.  E^322/103/A=003054",
+        r"FNT08F298>OGNFNT,qAS,DREIFBERG:/082654h4804.90N/00845.74E_273/005g008t057 sF1 cr4 DF0",
+        r"N0CALL>APRS,TCPIP*:!/5L!!<*e7>7P[",
+        r"N0CALL>APRS,TCPIP*:_10090556c220s004g005t077r000p000P000h50b09900wRSW",
+        r"N0CALL>APRS,TCPIP*::OGNFLR   :hello{42",
+        r"N0CALL>APRS,TCPIP*:)AID #2!4903.50N/07201.75WA Aid station",
+        r"N0CALL>APRS,TCPIP*:T#005,199,000,255,073,123,01101001",
+        r"N0CALL>APRS,TCPIP*:$GPRMC,074548,A",
+        r"N0CALL>APRS,TCPIP*:}FLRDDFAA3>OGFLR,TCPIP,N0CALL*:/074548h4708.92N/00653.67E'086/007/A=000607",
+        "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152",
+        "invalid",
+    ];
+    for line in lines {
+        let message = line.parse::<Message>().unwrap();
+        let normalized = message.normalize();
+        assert_eq!(
+            Message {
+                raw_string: line.to_string(),
+                ..normalized.clone()
+            },
+            message,
+            "{}",
+            line
+        );
+        assert_eq!(normalized.normalize(), normalized, "{}", line);
+    }
+
+    // The lenient tokens stay parsed
+    let line = r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 Gps3x5";
+    let message = Message::parse_lenient(line);
+    let normalized = message.normalize_with(Message::parse_lenient);
+    assert_eq!(normalized.position_comment, message.position_comment);
+    assert_eq!(
+        message.normalize().position_comment.unwrap().unparsed,
+        Some("Gps3x5".into())
+    );
+}
+
+#[test]
+fn test_parse_strict() {
+    let line = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 hello s1.0";
+    let message = line.parse::<Message>().unwrap();
+    assert_eq!(
+        message.position_comment.unwrap().software_version,
+        Some(1.0)
+    );
+    let message = Message::parse_strict(line);
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.software_version, None);
+    assert_eq!(position_comment.unparsed, Some("hello s1.0".into()));
+}
+
+#[test]
+fn test_flavor() {
+    let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7.RPI-GPU CPU:0.7"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.flavor, Some(BeaconFlavor::Receiver));
+    assert_eq!(message.status_comment.unwrap().cpu_load, Some(0.7));
+
+    // The status of a tracker has its own tokens besides the voltage
+    let message = r"OGN2FD00F>OGNTRK,qAS,LZHL:>093214h h00 v00 9sat/1 164m 1002.6hPa +20.2degC 0% 3.34V 14/-110.5dBm 1/min"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.flavor, Some(BeaconFlavor::OgnTracker));
+    let status_comment = message.status_comment.unwrap();
+    assert_eq!(status_comment.voltage, Some(3.34));
+    let tracker = status_comment.tracker.unwrap();
+    assert_eq!(tracker.satellites, Some(9));
+    assert_eq!(tracker.pressure, Some(1002.6));
+    assert_eq!(tracker.rx_noise, Some(-110.5));
+    assert_eq!(status_comment.unparsed, None);
+
+    // Receivers don't send the tracker tokens
+    let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7.RPI-GPU 9sat/1"
+        .parse::<Message>()
+        .unwrap();
+    let status_comment = message.status_comment.unwrap();
+    assert_eq!(status_comment.tracker, None);
+    assert_eq!(status_comment.unparsed, Some("9sat/1".into()));
+
+    let message = "not an aprs packet".parse::<Message>().unwrap();
+    assert_eq!(message.flavor, None);
+}
+
+#[test]
+fn test_naviter() {
+    let message = r"NAV07220E>OGNAVI,qAS,NAVITER:/140648h4550.36N/01314.85E'090/152/A=001086 !W81! id0440049C6B +198fpm +0.0rot"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.flavor, Some(BeaconFlavor::Naviter));
+    let position_comment = message.position_comment.unwrap();
+    let id = position_comment.id.unwrap();
+    assert_eq!(id.aircraft_type, 1);
+    assert_eq!(id.device_id(), "NAV049C6B");
+    assert_eq!(position_comment.unparsed, None);
+
+    // Only the Naviter beacons have the longer id
+    let message = r"FLRDDFAA3>OGFLR,qAS,Letzi:/140648h4550.36N/01314.85E'090/152/A=001086 id0440049C6B +198fpm"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.id, None);
+    assert_eq!(position_comment.unparsed, Some("id0440049C6B".into()));
+}
+
+#[test]
+fn test_fanet() {
+    let message = r"FNT1103CE>OGNFNT,qAS,FNB1103CE:/183734h5057.94N/00801.00Eg355/002/A=001042 !W10! id1E1103CE +03fpm FNT91 11.0dB"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    let fanet = position_comment.fanet.unwrap();
+    assert_eq!(fanet.aircraft_type, Some(1));
+    assert_eq!(fanet.online_tracking, Some(true));
+    assert_eq!(fanet.manufacturer, Some(0x11));
+    assert_eq!(position_comment.unparsed, None);
+
+    // The weather stations of FANET
+    let message = r"FNT11073A>OGNFNT,qAS,LSXI2:/180906h4632.54N/00900.07E_349/002g004t043h85b10152 FW:1.0 29.0dB"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.weather_comment.unwrap().wind_gust, Some(4));
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.fanet.unwrap().firmware, Some("1.0".into()));
+    assert_eq!(position_comment.unparsed, None);
+
+    // Other sources don't send FANET tokens
+    let message = r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 FNT11"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.fanet, None);
+    assert_eq!(position_comment.unparsed, Some("FNT11".into()));
+}
+
+#[test]
+fn test_pilot_aware() {
+    let message = r"PAW404705>OGPAW,qAS,UKSPID:/104809h5211.69N/00029.29W'204/057/A=001962 !W19! id21404705 12.5dB +4.9kHz sw:20231012 hw:Rosetta RSSI:-85.5 ch:3"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(
+        position_comment.pilot_aware,
+        Some(PilotAware {
+            software: Some("20231012".into()),
+            hardware: Some("Rosetta".into()),
+            rssi: Some(-85.5),
+            channel: Some(3),
+        })
+    );
+    assert_eq!(position_comment.signal_quality, Some(12.5));
+    assert_eq!(position_comment.unparsed, None);
+
+    let message = r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 ch:3"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.position_comment.unwrap().pilot_aware, None);
+}
+
+#[test]
+fn test_external_tracker() {
+    let message = r"SPO2860357>OGSPOT,qAS,SPOT:/135050h4614.97N/01347.62E'000/000/A=000000 id0-2860357 SPOT3 GOOD"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(
+        position_comment.external_tracker,
+        Some(ExternalTracker {
+            id: Some("0-2860357".into()),
+            sequence: None,
+            battery: None,
+            text: Some("SPOT3 GOOD".into()),
+        })
+    );
+    assert_eq!(position_comment.unparsed, None);
+
+    let message = r"LT24087925>OGLT24,qAS,LT24:/102606h4030.47N/00338.38W'000/018/A=002267 id25387 +000fpm seq:1042 bat:56% GPS"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.climb_rate, Some(0));
+    let tracker = position_comment.external_tracker.unwrap();
+    assert_eq!(tracker.id, Some("25387".into()));
+    assert_eq!(tracker.sequence, Some(1042));
+    assert_eq!(tracker.battery, Some(56));
+    assert_eq!(tracker.text, Some("GPS".into()));
+
+    let message = r"SKY2816>OGSKYL,qAS,SKYLINES:/134403h4225.90N/00144.83E'000/000/A=008438"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.position_comment.unwrap().external_tracker, None);
+}
+
+#[test]
+fn test_flymaster() {
+    let message = r"FMT1234567>OGFLYM,qAS,FLYMASTER:/093207h4553.41N/00622.90E'237/031/A=006204 id1234567 +217fpm Bat:85%"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(
+        position_comment.flymaster,
+        Some(FlyMaster {
+            serial: Some(1234567),
+            battery: Some(85),
+        })
+    );
+    assert_eq!(position_comment.climb_rate, Some(217));
+    assert_eq!(position_comment.unparsed, None);
+
+    let message =
+        r"FMT1234567>OGFLYM,qAS,FLYMASTER:/093207h4553.41N/00622.90E'237/031/A=006204 Bat:185%"
+            .parse::<Message>()
+            .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(position_comment.flymaster, None);
+    assert_eq!(position_comment.unparsed, Some("Bat:185%".into()));
+}
+
+#[test]
+fn test_adsb() {
+    let message = r"ICA4B43D0>OGADSB,qAS,HLST:/114151h4652.97N/00724.18E^227/383/A=017927 !W04! id254B43D0 -1024fpm FL179.25 fnA3:SWR1864 regHB-JVC Sq3456 air"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    assert_eq!(
+        position_comment.adsb,
+        Some(AdsB {
+            squawk: Some("3456".into()),
+            registration: Some("HB-JVC".into()),
+            category: Some("A3".into()),
+            flight: Some("SWR1864".into()),
+            on_ground: Some(false),
+        })
+    );
+    assert_eq!(position_comment.flight_level, Some(179.25));
+    assert_eq!(position_comment.unparsed, None);
+
+    let message = r"SKY3E5906>OGNSKY,qAS,SafeSky:/072553h5103.47N/00524.81E'000/000/A=000000 !W05! id1C3E5906 gnd Sq7800 fnE1:X"
+        .parse::<Message>()
+        .unwrap();
+    let position_comment = message.position_comment.unwrap();
+    let adsb = position_comment.adsb.unwrap();
+    assert_eq!(adsb.on_ground, Some(true));
+    assert_eq!(adsb.squawk, None);
+    assert_eq!(position_comment.unparsed, Some("Sq7800 fnE1:X".into()));
+
+    let message = r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 gnd"
+        .parse::<Message>()
+        .unwrap();
+    assert_eq!(message.position_comment.unwrap().adsb, None);
+}
@@ -0,0 +1,191 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::login::Login;
+use crate::server_comment::ServerComment;
+use crate::time::{Clock, SystemClock};
+
+// A local APRS-IS server for hermetic integration tests. Every client gets the banner,
+// a logresp for its login line and then the fixture lines, after which the connection
+// is closed:
+//     let server = MockAprsServer::start(lines)?;
+//     let mut stream = TcpStream::connect(server.address())?;
+//     writeln!(stream, "user N0CALL pass -1 vers test 1.0 filter r/47/7/100")?;
+//     pipeline.run(BufReader::new(stream), |message| ...)?;
+// The time in the banner comes from the clock, start_with_clock takes a SimulatedClock.
+pub struct MockAprsServer {
+    address: SocketAddr,
+    logins: Arc<Mutex<Vec<Login>>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockAprsServer {
+    // Listens on a free port of localhost
+    pub fn start<S: Into<String>>(lines: impl IntoIterator<Item = S>) -> io::Result<Self> {
+        MockAprsServer::start_with_clock(lines, SystemClock)
+    }
+
+    pub fn start_with_clock<S: Into<String>, C: Clock + Send + 'static>(
+        lines: impl IntoIterator<Item = S>,
+        clock: C,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let lines = lines.into_iter().map(Into::into).collect::<Vec<String>>();
+        let logins = Arc::new(Mutex::new(vec![]));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let logins = logins.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    // A client hanging up early is no error of the server
+                    if let Ok(stream) = stream {
+                        let _ = serve(stream, &lines, &logins, &clock);
+                    }
+                }
+            })
+        };
+        Ok(MockAprsServer {
+            address,
+            logins,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    // The logins of the clients so far
+    pub fn logins(&self) -> Vec<Login> {
+        self.logins.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockAprsServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes up the accept loop
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve<C: Clock>(
+    stream: TcpStream,
+    lines: &[String],
+    logins: &Mutex<Vec<Login>>,
+    clock: &C,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let keepalive = ServerComment::keepalive(
+        "aprsc",
+        "2.1.15-mock",
+        clock.now(),
+        "MOCK",
+        &writer.local_addr()?.to_string(),
+    );
+    write!(writer, "{}\r\n", keepalive)?;
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let Some(login) = Login::parse(&line) else {
+        write!(writer, "# invalid login\r\n")?;
+        return Ok(());
+    };
+    let logresp = ServerComment::logresp(&login.user, login.verified, "MOCK");
+    write!(writer, "{}\r\n", logresp)?;
+    logins.lock().unwrap().push(login);
+
+    for line in lines {
+        write!(writer, "{}\r\n", line)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::pipeline::Pipeline;
+    use crate::time::SimulatedClock;
+
+    #[test]
+    fn test_mock_server() {
+        let server = MockAprsServer::start([
+            r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+            r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765",
+            "invalid",
+        ])
+        .unwrap();
+
+        for _ in 0..2 {
+            let mut stream = TcpStream::connect(server.address()).unwrap();
+            writeln!(
+                stream,
+                "user N0CALL pass -1 vers test 1.0 filter r/47/7/100"
+            )
+            .unwrap();
+            let mut messages = vec![];
+            let mut quarantine = vec![];
+            let mut pipeline = Pipeline::new().with_quarantine(&mut quarantine);
+            pipeline
+                .run(BufReader::new(stream), |message: Message| {
+                    messages.push(message)
+                })
+                .unwrap();
+            assert_eq!(pipeline.rejected, 1);
+            drop(pipeline);
+
+            // The banner and the logresp are server comments
+            assert_eq!(messages.len(), 4);
+            assert_eq!(
+                messages[0]
+                    .server_comment
+                    .as_ref()
+                    .unwrap()
+                    .server
+                    .as_deref(),
+                Some("MOCK")
+            );
+            assert_eq!(
+                messages[1].server_comment.as_ref().unwrap().text,
+                "logresp N0CALL unverified, server MOCK"
+            );
+            assert!(messages[2].position_comment.is_some());
+            assert_eq!(String::from_utf8(quarantine).unwrap().lines().count(), 1);
+        }
+        assert_eq!(server.logins().len(), 2);
+        assert_eq!(server.logins()[0].filter.as_deref(), Some("r/47/7/100"));
+    }
+
+    #[test]
+    fn test_banner_time() {
+        let clock = Arc::new(SimulatedClock::new(1722498529));
+        let server = MockAprsServer::start_with_clock(Vec::<String>::new(), clock.clone()).unwrap();
+        clock.advance(60);
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        writeln!(stream, "user N0CALL pass -1 vers test 1.0").unwrap();
+        let mut messages = vec![];
+        Pipeline::new()
+            .run(BufReader::new(stream), |message: Message| {
+                messages.push(message)
+            })
+            .unwrap();
+        let banner = messages[0].server_comment.as_ref().unwrap();
+        assert_eq!(banner.timestamp, Some(1722498589));
+    }
+}
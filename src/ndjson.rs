@@ -0,0 +1,57 @@
+use std::io::{BufRead, Lines};
+
+use crate::message::Message;
+
+pub struct NdjsonReader<R> {
+    lines: Lines<R>,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        NdjsonReader {
+            lines: reader.lines(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<Message, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(serde_json::Error::io(e))),
+            };
+            if !line.trim().is_empty() {
+                return Some(Message::from_json(&line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ndjson() {
+        let lines = [
+            r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 !W09! id213D17F2",
+            r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!",
+        ];
+        let ndjson = lines
+            .iter()
+            .map(|line| serde_json::to_string(&line.parse::<Message>().unwrap()).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let messages = NdjsonReader::new(ndjson.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].raw_string, lines[0]);
+        assert_eq!(messages[1].raw_string, lines[1]);
+        assert!(messages[1].status_comment.is_some());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::message::Message;
+use crate::output::packet_timestamp;
+use crate::time;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SortField {
+    // The resolved APRS timestamp
+    Time,
+    // The address of the id, or of the callsign for beacons without id
+    Address,
+    Receiver,
+}
+
+// One part of a sort key, missing values sort first
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum SortValue {
+    Time(Option<i64>),
+    Address(Option<u32>),
+    Receiver(Option<String>),
+}
+
+// The raw string breaks ties, so the order doesn't depend on the input order
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct SortKey {
+    pub values: Vec<SortValue>,
+    pub raw_string: String,
+}
+
+// Builds sort keys from the fields in the given order, e.g. by receiver, then by time:
+// SortOrder::new(reference_time).by(SortField::Receiver).by(SortField::Time)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SortOrder {
+    // seconds since 1970-01-01 used to resolve the APRS timestamps
+    reference_time: i64,
+    fields: Vec<SortField>,
+}
+
+impl SortOrder {
+    pub fn new(reference_time: i64) -> Self {
+        SortOrder {
+            reference_time,
+            fields: vec![],
+        }
+    }
+
+    pub fn by(mut self, field: SortField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn key(&self, message: &Message) -> SortKey {
+        let values = self
+            .fields
+            .iter()
+            .map(|field| match field {
+                SortField::Time => SortValue::Time(
+                    packet_timestamp(message)
+                        .and_then(|timestamp| time::resolve(timestamp, self.reference_time)),
+                ),
+                SortField::Address => SortValue::Address(address(message)),
+                SortField::Receiver => {
+                    SortValue::Receiver(message.receiver().map(|receiver| receiver.to_string()))
+                }
+            })
+            .collect();
+        SortKey {
+            values,
+            raw_string: message.raw_string.clone(),
+        }
+    }
+
+    pub fn sort(&self, messages: &mut [Message]) {
+        messages.sort_by_cached_key(|message| self.key(message));
+    }
+}
+
+fn address(message: &Message) -> Option<u32> {
+    let id = message
+        .position_comment
+        .as_ref()
+        .and_then(|comment| comment.id.as_ref());
+    match id {
+        Some(id) => Some(id.address),
+        None => message.aprs_packet.as_ref().ok()?.from.device_address(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: [&str; 4] = [
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074610h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+        r"ICA3D17F2>APRS,qAS,dl4mea:/074548h4821.61N\01224.49E^322/103/A=003054 id213D17F2",
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+        r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7",
+    ];
+
+    fn sorted(order: &SortOrder, lines: &[&str]) -> Vec<String> {
+        let mut messages = lines
+            .iter()
+            .map(|line| line.parse::<Message>().unwrap())
+            .collect::<Vec<_>>();
+        order.sort(&mut messages);
+        messages
+            .into_iter()
+            .map(|message| message.raw_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        // 2024-08-01 09:00:00
+        let order = SortOrder::new(1722502800).by(SortField::Time);
+        assert_eq!(
+            sorted(&order, &LINES),
+            vec![LINES[2], LINES[1], LINES[0], LINES[3]]
+        );
+
+        let order = SortOrder::new(1722502800)
+            .by(SortField::Address)
+            .by(SortField::Time);
+        assert_eq!(
+            sorted(&order, &LINES),
+            vec![LINES[3], LINES[1], LINES[2], LINES[0]]
+        );
+
+        // The receiver beacon has no receiver, the rest is sorted by the raw string
+        let order = SortOrder::new(1722502800).by(SortField::Receiver);
+        assert_eq!(
+            sorted(&order, &LINES),
+            vec![LINES[3], LINES[2], LINES[0], LINES[1]]
+        );
+    }
+
+    #[test]
+    fn test_sort_is_deterministic() {
+        let order = SortOrder::new(1722502800).by(SortField::Receiver);
+        let mut reversed = LINES;
+        reversed.reverse();
+        assert_eq!(sorted(&order, &LINES), sorted(&order, &reversed));
+    }
+}
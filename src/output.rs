@@ -0,0 +1,611 @@
+use std::str::FromStr;
+
+use serde_json::{json, Map, Value};
+
+use crate::compare::to_ogn_python;
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::pseudonym::Pseudonymizer;
+use crate::time::{self, Clock, SystemClock};
+use crate::timestamp::Timestamp;
+use crate::utils::split_whitespace_with_offsets;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TimestampFormat {
+    // As transmitted, e.g. "074849h"
+    #[default]
+    Raw,
+    // Resolved against the reference time, e.g. "2024-08-01T07:48:49Z"
+    Iso8601,
+    // Resolved against the reference time, in seconds since 1970-01-01
+    EpochSeconds,
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(TimestampFormat::Raw),
+            "iso8601" => Ok(TimestampFormat::Iso8601),
+            "epoch" => Ok(TimestampFormat::EpochSeconds),
+            _ => Err(format!(
+                "Unknown timestamp format '{}', expected 'raw', 'iso8601' or 'epoch'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum UnitFormat {
+    // Plain numbers, e.g. "altitude": 3054
+    #[default]
+    None,
+    // Value and unit objects, e.g. "altitude": {"value": 3054, "unit": "ft"}
+    Inline,
+    // Plain numbers and a map of the units, e.g. "units": {"position_comment.altitude": "ft"}
+    Sidecar,
+}
+
+impl FromStr for UnitFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(UnitFormat::None),
+            "inline" => Ok(UnitFormat::Inline),
+            "sidecar" => Ok(UnitFormat::Sidecar),
+            _ => Err(format!(
+                "Unknown unit format '{}', expected 'none', 'inline' or 'sidecar'",
+                s
+            )),
+        }
+    }
+}
+
+// What to do with resolved timestamps which are more than the given seconds ahead of the
+// reference time, as sent by trackers with a bad clock
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FutureTimestamps {
+    #[default]
+    Keep,
+    // Replaced by the reference time, raw timestamps are kept
+    Clamp(i64),
+    // Kept, but the message gets "future_timestamp": true
+    Flag(i64),
+}
+
+impl FromStr for FutureTimestamps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tolerance = |seconds: &str| {
+            seconds
+                .parse::<i64>()
+                .ok()
+                .filter(|seconds| *seconds >= 0)
+                .ok_or_else(|| format!("Invalid tolerance '{}', expected seconds", seconds))
+        };
+        match s.split_once(':') {
+            None if s == "keep" => Ok(FutureTimestamps::Keep),
+            Some(("clamp", seconds)) => tolerance(seconds).map(FutureTimestamps::Clamp),
+            Some(("flag", seconds)) => tolerance(seconds).map(FutureTimestamps::Flag),
+            _ => Err(format!(
+                "Unknown future timestamp handling '{}', expected 'keep', 'clamp:<seconds>' or 'flag:<seconds>'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FieldNames {
+    // The structure of Message
+    #[default]
+    Native,
+    // The flat dict of python-ogn-client with its field names and metric units, e.g.
+    // "aircraft_type", "address_type", "reference_timestamp", for existing ogn-python databases
+    OgnPython,
+}
+
+impl FromStr for FieldNames {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(FieldNames::Native),
+            "ogn-python" => Ok(FieldNames::OgnPython),
+            _ => Err(format!(
+                "Unknown field names '{}', expected 'native' or 'ogn-python'",
+                s
+            )),
+        }
+    }
+}
+
+// Physical quantities as (section, field, unit)
+const UNITS: [(&str, &str, &str); 26] = [
+    ("position_comment", "course", "deg"),
+    ("position_comment", "speed", "kt"),
+    ("position_comment", "altitude", "ft"),
+    ("position_comment", "climb_rate", "fpm"),
+    ("position_comment", "turn_rate", "rot"),
+    ("position_comment", "signal_quality", "dB"),
+    ("position_comment", "frequency_offset", "kHz"),
+    ("position_comment", "flight_level", "FL"),
+    ("position_comment", "signal_power", "dBm"),
+    ("status_comment", "ram_free", "MB"),
+    ("status_comment", "ram_total", "MB"),
+    ("status_comment", "ntp_offset", "ms"),
+    ("status_comment", "ntp_correction", "ppm"),
+    ("status_comment", "voltage", "V"),
+    ("status_comment", "amperage", "A"),
+    ("status_comment", "cpu_temperature", "C"),
+    ("status_comment", "latency", "s"),
+    ("status_comment", "rf_correction_manual", "ppm"),
+    ("status_comment", "rf_correction_automatic", "ppm"),
+    ("status_comment", "noise", "dB"),
+    ("status_comment", "senders_signal_quality", "dB"),
+    ("status_comment", "good_senders_signal_quality", "dB"),
+    ("status_comment", "tuner_gain", "dB"),
+    ("status_comment", "frequency_correction", "ppm"),
+    ("aprs_packet/data/Position", "latitude", "deg"),
+    ("aprs_packet/data/Position", "longitude", "deg"),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OutputOptions {
+    pub timestamp_format: TimestampFormat,
+    pub unit_format: UnitFormat,
+    // Adds the byte spans of everything in the raw string that was not parsed
+    pub lossless: bool,
+    // Seconds since 1970-01-01 used to resolve the incomplete APRS timestamps,
+    // defaults to the time of the clock
+    pub reference_time: Option<i64>,
+    pub future_timestamps: FutureTimestamps,
+    // With FieldNames::OgnPython the other options are ignored
+    pub field_names: FieldNames,
+    // Replaces the device addresses before the message is serialized
+    pub pseudonymizer: Option<Pseudonymizer>,
+}
+
+pub fn to_value(message: &Message, options: &OutputOptions) -> Value {
+    to_value_with_clock(message, options, &SystemClock)
+}
+
+pub fn to_value_with_clock<C: Clock>(
+    message: &Message,
+    options: &OutputOptions,
+    clock: &C,
+) -> Value {
+    let pseudonymized;
+    let message = match &options.pseudonymizer {
+        Some(pseudonymizer) => {
+            pseudonymized = pseudonymizer.apply(message);
+            &pseudonymized
+        }
+        None => message,
+    };
+    let reference = options.reference_time.unwrap_or_else(|| clock.now());
+    if options.field_names == FieldNames::OgnPython {
+        return ogn_python_value(message, reference);
+    }
+    let mut value = serde_json::to_value(message).unwrap();
+    let mut future = None;
+    if let (Some(timestamp), Some(data)) = (
+        packet_timestamp(message),
+        value.pointer_mut("/aprs_packet/data"),
+    ) {
+        let (formatted, in_future) = format_timestamp(timestamp, reference, options);
+        for field in timestamp_fields(data) {
+            *field = formatted.clone();
+        }
+        future = Some(in_future);
+    }
+    if let (FutureTimestamps::Flag(_), Some(future), Some(object)) =
+        (options.future_timestamps, future, value.as_object_mut())
+    {
+        object.insert("future_timestamp".to_string(), Value::from(future));
+    }
+    if options.unit_format != UnitFormat::None {
+        add_units(&mut value, options.unit_format);
+    }
+    if let (true, Some(object)) = (options.lossless, value.as_object_mut()) {
+        let spans = unparsed_spans(message)
+            .into_iter()
+            .map(|(start, end)| json!({"start": start, "end": end, "text": &message.raw_string[start..end]}))
+            .collect::<Vec<_>>();
+        object.insert("unparsed_spans".to_string(), Value::from(spans));
+    }
+    value
+}
+
+// The timestamps are ISO 8601 strings, python-ogn-client returns them as datetimes
+fn ogn_python_value(message: &Message, reference: i64) -> Value {
+    let mut result = to_ogn_python(message);
+    result.insert("raw_message".to_string(), message.raw_string.clone().into());
+    result.insert(
+        "reference_timestamp".to_string(),
+        time::to_iso8601(reference).into(),
+    );
+    let Ok(packet) = &message.aprs_packet else {
+        return result.into();
+    };
+    let timestamp = packet_timestamp(message)
+        .and_then(|timestamp| time::resolve(timestamp, reference))
+        .map(time::to_iso8601);
+    result.insert("timestamp".to_string(), timestamp.into());
+    result.insert(
+        "relay".to_string(),
+        message.relay().map(|relay| relay.to_string()).into(),
+    );
+    let (aprs_type, comment) = match &packet.data {
+        AprsData::Position(position) => {
+            result.insert(
+                "symboltable".to_string(),
+                position.symbol_table.to_string().into(),
+            );
+            result.insert(
+                "symbolcode".to_string(),
+                position.symbol_code.to_string().into(),
+            );
+            ("position", Some(&position.comment))
+        }
+        AprsData::Status(status) => ("status", Some(&status.comment)),
+        AprsData::Item(_) => ("item", None),
+        AprsData::Message(_) => ("message", None),
+        AprsData::Telemetry(_) => ("telemetry", None),
+        AprsData::Weather(_) => ("weather", None),
+        AprsData::ThirdParty(_) => ("third_party", None),
+        AprsData::Unknown => ("unknown", None),
+    };
+    result.insert("aprs_type".to_string(), aprs_type.into());
+    result.insert("comment".to_string(), comment.cloned().into());
+    result.into()
+}
+
+pub fn to_string(message: &Message, options: &OutputOptions) -> String {
+    to_value(message, options).to_string()
+}
+
+// The formatted timestamp and whether it is too far in the future
+fn format_timestamp(
+    timestamp: &Timestamp,
+    reference: i64,
+    options: &OutputOptions,
+) -> (Value, bool) {
+    let resolved = time::resolve(timestamp, reference);
+    let (resolved, future) = match (resolved, options.future_timestamps) {
+        (Some(resolved), FutureTimestamps::Clamp(tolerance))
+            if resolved > reference + tolerance =>
+        {
+            (Some(reference), true)
+        }
+        (Some(resolved), FutureTimestamps::Flag(tolerance)) => {
+            (Some(resolved), resolved > reference + tolerance)
+        }
+        (resolved, _) => (resolved, false),
+    };
+    let formatted = match options.timestamp_format {
+        TimestampFormat::Raw => Value::from(timestamp.to_string()),
+        TimestampFormat::Iso8601 => resolved.map(time::to_iso8601).into(),
+        TimestampFormat::EpochSeconds => resolved.into(),
+    };
+    (formatted, future)
+}
+
+fn add_units(value: &mut Value, unit_format: UnitFormat) {
+    let mut units = Map::new();
+    for (section, field, unit) in UNITS {
+        let quantity = match value
+            .pointer_mut(&format!("/{}/{}", section, field))
+            .filter(|quantity| quantity.is_number())
+        {
+            Some(quantity) => quantity,
+            None => continue,
+        };
+        if unit_format == UnitFormat::Inline {
+            *quantity = json!({"value": quantity.take(), "unit": unit});
+        } else {
+            units.insert(
+                format!("{}.{}", section.replace('/', "."), field),
+                Value::from(unit),
+            );
+        }
+    }
+    if let (UnitFormat::Sidecar, Some(object)) = (unit_format, value.as_object_mut()) {
+        object.insert("units".to_string(), Value::Object(units));
+    }
+}
+
+// Byte ranges of the raw string which ended up in "unparsed" or could not be parsed at all
+pub fn unparsed_spans(message: &Message) -> Vec<(usize, usize)> {
+    let raw = &message.raw_string;
+    let packet = match &message.aprs_packet {
+        Ok(packet) => packet,
+        Err(_) => return vec![(0, raw.len())],
+    };
+    let (comment, unparsed) = match &packet.data {
+        AprsData::Position(position) => (
+            &position.comment,
+            message
+                .position_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_ref()),
+        ),
+        AprsData::Item(item) => (
+            &item.position.comment,
+            message
+                .position_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_ref()),
+        ),
+        AprsData::Weather(weather) => (
+            &weather.comment,
+            message
+                .weather_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_ref()),
+        ),
+        AprsData::Status(status) => (
+            &status.comment,
+            message
+                .status_comment
+                .as_ref()
+                .and_then(|comment| comment.unparsed.as_ref()),
+        ),
+        AprsData::Message(_)
+        | AprsData::Telemetry(_)
+        | AprsData::ThirdParty(_)
+        | AprsData::Unknown => return vec![],
+    };
+    let (unparsed, offset) = match (unparsed, raw.rfind(comment.as_str())) {
+        (Some(unparsed), Some(offset)) => (unparsed, offset),
+        _ => return vec![],
+    };
+
+    // The unparsed tokens are in the same order as in the comment
+    let mut tokens = split_whitespace_with_offsets(comment);
+    unparsed
+        .split_whitespace()
+        .filter_map(|part| tokens.find(|(_, token)| *token == part))
+        .map(|(start, token)| (offset + start, offset + start + token.len()))
+        .collect()
+}
+
+// The packet data is serialized as {"Position": {..., "timestamp": ...}}
+fn timestamp_fields(data: &mut Value) -> Vec<&mut Value> {
+    data.as_object_mut()
+        .into_iter()
+        .flat_map(|object| object.values_mut())
+        .filter_map(|variant| variant.get_mut("timestamp"))
+        .filter(|timestamp| !timestamp.is_null())
+        .collect()
+}
+
+pub(crate) fn packet_timestamp(message: &Message) -> Option<&Timestamp> {
+    match &message.aprs_packet.as_ref().ok()?.data {
+        AprsData::Position(position) => position.timestamp.as_ref(),
+        AprsData::Status(status) => status.timestamp.as_ref(),
+        AprsData::Item(_)
+        | AprsData::Weather(_)
+        | AprsData::Message(_)
+        | AprsData::Telemetry(_)
+        | AprsData::ThirdParty(_)
+        | AprsData::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-08-01T12:00:00Z
+    const REFERENCE: i64 = 1722513600;
+
+    fn timestamp(format: TimestampFormat) -> Value {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054"
+            .parse::<Message>()
+            .unwrap();
+        let options = OutputOptions {
+            timestamp_format: format,
+            reference_time: Some(REFERENCE),
+            ..Default::default()
+        };
+        to_value(&message, &options)
+            .pointer("/aprs_packet/data/Position/timestamp")
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        assert_eq!(timestamp(TimestampFormat::Raw), Value::from("074849h"));
+        assert_eq!(
+            timestamp(TimestampFormat::Iso8601),
+            Value::from("2024-08-01T07:48:49Z")
+        );
+        assert_eq!(
+            timestamp(TimestampFormat::EpochSeconds),
+            Value::from(1722498529i64)
+        );
+    }
+
+    #[test]
+    fn test_clock() {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054"
+            .parse::<Message>()
+            .unwrap();
+        let options = OutputOptions {
+            timestamp_format: TimestampFormat::Iso8601,
+            ..Default::default()
+        };
+        // Replaying a log of the previous day
+        let clock = time::SimulatedClock::new(REFERENCE - 86400);
+        let value = to_value_with_clock(&message, &options, &clock);
+        assert_eq!(
+            value["aprs_packet"]["data"]["Position"]["timestamp"],
+            "2024-07-31T07:48:49Z"
+        );
+    }
+
+    #[test]
+    fn test_ogn_python_field_names() {
+        let message = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm"
+            .parse::<Message>()
+            .unwrap();
+        let options = OutputOptions {
+            reference_time: Some(REFERENCE),
+            field_names: "ogn-python".parse().unwrap(),
+            ..Default::default()
+        };
+        let value = to_value(&message, &options);
+        assert_eq!(value["name"], "FLRDDFAA3");
+        assert_eq!(value["receiver_name"], "Letzi");
+        assert_eq!(value["aircraft_type"], 2);
+        assert_eq!(value["address_type"], 2);
+        assert_eq!(value["aprs_type"], "position");
+        assert_eq!(value["symbolcode"], "'");
+        assert_eq!(value["relay"], Value::Null);
+        assert_eq!(value["reference_timestamp"], "2024-08-01T12:00:00Z");
+        assert_eq!(value["timestamp"], "2024-08-01T07:45:48Z");
+        assert_eq!(value["raw_message"], message.raw_string.as_str());
+        assert!(value.get("aprs_packet").is_none());
+        assert!("python".parse::<FieldNames>().is_err());
+    }
+
+    #[test]
+    fn test_pseudonymizer() {
+        let message =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3"
+                .parse::<Message>()
+                .unwrap();
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let options = OutputOptions {
+            pseudonymizer: Some(pseudonymizer.clone()),
+            ..Default::default()
+        };
+        let value = to_value(&message, &options);
+        assert!(!value.to_string().contains("DDFAA3"));
+        assert_eq!(
+            value["position_comment"]["id"]["address"],
+            pseudonymizer.address(0xDDFAA3)
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_format() {
+        assert_eq!("epoch".parse(), Ok(TimestampFormat::EpochSeconds));
+        assert!("unix".parse::<TimestampFormat>().is_err());
+    }
+
+    #[test]
+    fn test_future_timestamps() {
+        // 07:48:49 with a receive time of 07:47:49
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054"
+            .parse::<Message>()
+            .unwrap();
+        let value = |future_timestamps| {
+            let options = OutputOptions {
+                timestamp_format: TimestampFormat::EpochSeconds,
+                reference_time: Some(1722498469),
+                future_timestamps,
+                ..Default::default()
+            };
+            to_value(&message, &options)
+        };
+        let timestamp =
+            |value: &Value| value["aprs_packet"]["data"]["Position"]["timestamp"].clone();
+
+        let kept = value(FutureTimestamps::Keep);
+        assert_eq!(timestamp(&kept), json!(1722498529i64));
+        assert!(kept.get("future_timestamp").is_none());
+
+        assert_eq!(
+            timestamp(&value(FutureTimestamps::Clamp(30))),
+            json!(1722498469i64)
+        );
+        assert_eq!(
+            timestamp(&value(FutureTimestamps::Clamp(60))),
+            json!(1722498529i64)
+        );
+
+        let flagged = value(FutureTimestamps::Flag(30));
+        assert_eq!(timestamp(&flagged), json!(1722498529i64));
+        assert_eq!(flagged["future_timestamp"], json!(true));
+        assert_eq!(
+            value(FutureTimestamps::Flag(60))["future_timestamp"],
+            json!(false)
+        );
+
+        assert_eq!("clamp:60".parse(), Ok(FutureTimestamps::Clamp(60)));
+        assert_eq!("keep".parse(), Ok(FutureTimestamps::Keep));
+        assert!("flag:-1".parse::<FutureTimestamps>().is_err());
+        assert!("clamp".parse::<FutureTimestamps>().is_err());
+    }
+
+    #[test]
+    fn test_unit_formats() {
+        let message =
+            r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 -039fpm"
+                .parse::<Message>()
+                .unwrap();
+
+        let value = to_value(&message, &OutputOptions::default());
+        assert_eq!(value["position_comment"]["altitude"], json!(3054));
+        assert!(value.get("units").is_none());
+
+        let options = OutputOptions {
+            unit_format: UnitFormat::Inline,
+            ..Default::default()
+        };
+        let value = to_value(&message, &options);
+        assert_eq!(
+            value["position_comment"]["altitude"],
+            json!({"value": 3054, "unit": "ft"})
+        );
+        assert_eq!(
+            value["position_comment"]["climb_rate"],
+            json!({"value": -39, "unit": "fpm"})
+        );
+        assert_eq!(value["position_comment"]["turn_rate"], Value::Null);
+
+        let options = OutputOptions {
+            unit_format: UnitFormat::Sidecar,
+            ..Default::default()
+        };
+        let value = to_value(&message, &options);
+        assert_eq!(value["position_comment"]["altitude"], json!(3054));
+        assert_eq!(value["units"]["position_comment.altitude"], json!("ft"));
+        assert_eq!(
+            value["units"]["aprs_packet.data.Position.latitude"],
+            json!("deg")
+        );
+        assert!(value["units"].get("position_comment.turn_rate").is_none());
+    }
+
+    #[test]
+    fn test_lossless() {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054 foo -039fpm  bar"
+            .parse::<Message>()
+            .unwrap();
+        let options = OutputOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let value = to_value(&message, &options);
+        assert_eq!(value["raw_string"], json!(message.raw_string));
+        assert_eq!(value["position_comment"]["climb_rate"], json!(-39));
+        assert_eq!(
+            value["unparsed_spans"],
+            json!([
+                {"start": 70, "end": 73, "text": "foo"},
+                {"start": 83, "end": 86, "text": "bar"},
+            ])
+        );
+
+        let message = "not an aprs packet".parse::<Message>().unwrap();
+        assert_eq!(unparsed_spans(&message), vec![(0, 18)]);
+    }
+}
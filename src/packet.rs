@@ -0,0 +1,270 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::aprs_message::AprsMessage;
+use crate::callsign::Callsign;
+use crate::error::AprsError;
+use crate::item::AprsItem;
+use crate::path::QConstruct;
+use crate::position::AprsPosition;
+use crate::status::AprsStatus;
+use crate::telemetry::AprsTelemetry;
+use crate::weather::AprsWeather;
+
+// The borrowed parts of a packet "FROM>TO,VIA1,VIA2:information",
+// so callers can look at the header without any allocation
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AprsHeader<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub via: Vec<&'a str>,
+    // Starts with the data type identifier
+    pub information: &'a str,
+}
+
+impl<'a> AprsHeader<'a> {
+    pub fn parse(s: &'a str) -> Result<AprsHeader<'a>, AprsError> {
+        let invalid = || AprsError::InvalidPacket(s.to_string());
+        let (header, information) = s.split_once(':').ok_or_else(invalid)?;
+        let (from, path) = header.split_once('>').ok_or_else(invalid)?;
+        let mut path = path.split(',');
+        let to = path.next().unwrap_or_default();
+        Ok(AprsHeader {
+            from,
+            to,
+            via: path.collect(),
+            information,
+        })
+    }
+
+    pub fn data_type(&self) -> Option<char> {
+        self.information.chars().next()
+    }
+
+    // The information field without the data type identifier
+    pub fn body(&self) -> &'a str {
+        let mut chars = self.information.chars();
+        chars.next();
+        chars.as_str()
+    }
+
+    // The q construct the APRS-IS server added to the path (e.g. "qAS") and the station
+    // following it, for OGN the receiver or the server
+    pub fn q_construct(&self) -> Option<(&'a str, Option<&'a str>)> {
+        let idx = self
+            .via
+            .iter()
+            .position(|callsign| QConstruct::from_callsign(callsign).is_some())?;
+        Some((self.via[idx], self.via.get(idx + 1).copied()))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum AprsData {
+    Position(AprsPosition),
+    Item(AprsItem),
+    Message(AprsMessage),
+    Status(AprsStatus),
+    Telemetry(AprsTelemetry),
+    Weather(AprsWeather),
+    // A packet forwarded by a gateway: "}FROM>TO,VIA:information"
+    ThirdParty(Box<AprsPacket>),
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AprsPacket {
+    pub from: Callsign,
+    pub to: Callsign,
+    pub via: Vec<Callsign>,
+    pub data: AprsData,
+}
+
+// Third-party packets can be nested, but not without end
+const MAX_THIRD_PARTY_DEPTH: usize = 3;
+
+impl FromStr for AprsPacket {
+    type Err = AprsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AprsPacket::parse(s, 0)
+    }
+}
+
+impl AprsPacket {
+    fn parse(s: &str, depth: usize) -> Result<Self, AprsError> {
+        let header = AprsHeader::parse(s)?;
+        let data = match header.data_type() {
+            Some('/') | Some('@') | Some('!') | Some('=') => {
+                AprsData::Position(header.information.parse::<AprsPosition>()?)
+            }
+            Some('`') | Some('\'') => {
+                AprsData::Position(AprsPosition::from_mic_e(header.to, header.information)?)
+            }
+            Some(')') => AprsData::Item(header.body().parse::<AprsItem>()?),
+            Some('>') => AprsData::Status(header.body().parse::<AprsStatus>()?),
+            Some(':') => AprsData::Message(header.body().parse::<AprsMessage>()?),
+            // Without '#' it is not a telemetry frame, like any other unknown data type
+            Some('T') if header.body().starts_with('#') => {
+                AprsData::Telemetry(header.body().parse::<AprsTelemetry>()?)
+            }
+            Some('_') => AprsData::Weather(header.body().parse::<AprsWeather>()?),
+            Some('}') if depth < MAX_THIRD_PARTY_DEPTH => {
+                AprsData::ThirdParty(Box::new(AprsPacket::parse(header.body(), depth + 1)?))
+            }
+            Some('}') => return Err(AprsError::InvalidPacket(s.to_string())),
+            _ => AprsData::Unknown,
+        };
+        Ok(AprsPacket {
+            from: header.from.parse()?,
+            to: header.to.parse()?,
+            via: header
+                .via
+                .iter()
+                .map(|callsign| callsign.parse())
+                .collect::<Result<Vec<_>, _>>()?,
+            data,
+        })
+    }
+}
+
+impl Display for AprsPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}>{}", self.from, self.to)?;
+        for callsign in &self.via {
+            write!(f, ",{}", callsign)?;
+        }
+        write!(f, ":")?;
+        match &self.data {
+            AprsData::Position(position) => write!(f, "{}", position),
+            AprsData::Item(item) => write!(f, "{}", item),
+            AprsData::Message(message) => write!(f, "{}", message),
+            AprsData::Status(status) => write!(f, "{}", status),
+            AprsData::Telemetry(telemetry) => write!(f, "{}", telemetry),
+            AprsData::Weather(weather) => write!(f, "{}", weather),
+            AprsData::ThirdParty(packet) => write!(f, "}}{}", packet),
+            AprsData::Unknown => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::Timestamp;
+
+    #[test]
+    fn test_header() {
+        let header = AprsHeader::parse("Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7").unwrap();
+        assert_eq!(header.from, "Letzi");
+        assert_eq!(header.to, "OGNSDR");
+        assert_eq!(header.via, vec!["TCPIP*", "qAC", "GLIDERN2"]);
+        assert_eq!(header.data_type(), Some('>'));
+        assert_eq!(header.body(), "085623h v0.2.7");
+        assert_eq!(header.q_construct(), Some(("qAC", Some("GLIDERN2"))));
+
+        // Only the first colon separates the header
+        let header = AprsHeader::parse("OGN123456>OGNAPP::OGNDDFAA3:Hi").unwrap();
+        assert_eq!(header.via, Vec::<&str>::new());
+        assert_eq!(header.q_construct(), None);
+        assert_eq!(header.information, ":OGNDDFAA3:Hi");
+
+        for invalid in ["Letzi>OGNSDR", "Letzi OGNSDR:>085623h"] {
+            assert_eq!(
+                AprsHeader::parse(invalid),
+                Err(AprsError::InvalidPacket(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_packet() {
+        let raw = r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054";
+        let packet = raw.parse::<AprsPacket>().unwrap();
+        assert_eq!(packet.from, Callsign::new("ICA3D17F2", None));
+        assert_eq!(packet.to, Callsign::new("APRS", None));
+        assert_eq!(
+            packet.via,
+            vec![Callsign::new("qAS", None), Callsign::new("dl4mea", None)]
+        );
+        match &packet.data {
+            AprsData::Position(position) => {
+                assert_eq!(position.timestamp, Some(Timestamp::HHMMSS(7, 48, 49)))
+            }
+            _ => panic!("Unexpected data: {:?}", packet.data),
+        }
+        assert_eq!(packet.to_string(), raw);
+
+        let packet = "ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert!(matches!(packet.data, AprsData::Status(_)));
+
+        let raw = "FLRDDFAA3>APRS,qAS,Letzi:T#005,199,000,255,073,123,01101001";
+        let packet = raw.parse::<AprsPacket>().unwrap();
+        match &packet.data {
+            AprsData::Telemetry(telemetry) => assert_eq!(telemetry.sequence, Some(5)),
+            _ => panic!("Unexpected data: {:?}", packet.data),
+        }
+        assert_eq!(packet.to_string(), raw);
+
+        let packet = "FLRDDFAA3>APRS,qAS,Letzi:{Q1qwerty"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert_eq!(packet.data, AprsData::Unknown);
+        let packet = "FLRDDFAA3>APRS,qAS,Letzi:Test"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert_eq!(packet.data, AprsData::Unknown);
+
+        let raw = r#"N0CALL>S32UVT,qAR,Letzi:`(_fn"Oj/]"4-}"#;
+        let packet = raw.parse::<AprsPacket>().unwrap();
+        match &packet.data {
+            AprsData::Position(position) => assert_eq!(position.mic_e.as_ref().unwrap().speed, 20),
+            _ => panic!("Unexpected data: {:?}", packet.data),
+        }
+        assert_eq!(packet.to_string(), raw);
+    }
+
+    #[test]
+    fn test_third_party() {
+        let raw = r"FNB1103CE>OGNFNT,qAS,Letzi:}FNT1103CE>OGNFNT,TCPIP,FNB1103CE*:/183734h5057.94N/00801.00Eg355/002/A=001042";
+        let packet = raw.parse::<AprsPacket>().unwrap();
+        assert_eq!(packet.from, Callsign::new("FNB1103CE", None));
+        match &packet.data {
+            AprsData::ThirdParty(inner) => {
+                assert_eq!(inner.from, Callsign::new("FNT1103CE", None));
+                assert_eq!(inner.via.len(), 2);
+                assert!(matches!(inner.data, AprsData::Position(_)));
+            }
+            _ => panic!("Unexpected data: {:?}", packet.data),
+        }
+        assert_eq!(packet.to_string(), raw);
+
+        // The inner packet must be valid
+        assert_eq!(
+            "FNB1103CE>OGNFNT,qAS,Letzi:}FNT1103CE:>Hello".parse::<AprsPacket>(),
+            Err(AprsError::InvalidPacket("FNT1103CE:>Hello".to_string()))
+        );
+        let nested = "A>B:}C>D:}E>F:}G>H:>ok";
+        assert!(nested.parse::<AprsPacket>().is_ok());
+        assert_eq!(
+            format!("X>Y:}}{}", nested).parse::<AprsPacket>(),
+            Err(AprsError::InvalidPacket("E>F:}G>H:>ok".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_packet_errors() {
+        assert_eq!(
+            ">APRS,qAS,dl4mea:>312359zStatus".parse::<AprsPacket>(),
+            Err(AprsError::EmptyCallsign("".to_string()))
+        );
+        assert_eq!(
+            "ICA3D17F2>APRS,qAS,dl4mea:/07484".parse::<AprsPacket>(),
+            Err(AprsError::InvalidTimestamp("07484".to_string()))
+        );
+    }
+}
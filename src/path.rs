@@ -0,0 +1,272 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::callsign::Callsign;
+use crate::message::Message;
+use crate::packet::AprsPacket;
+
+// The q construct the APRS-IS server adds to the path, it tells how the packet entered
+// APRS-IS. OGN receivers forward the aircraft beacons with qAS, their own beacons have qAC.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum QConstruct {
+    // qAC: from a verified client
+    Client,
+    // qAX: from an unverified client
+    Unverified,
+    // qAU: via UDP
+    Udp,
+    // qAo: gated from RF via a client-only connection
+    ClientOnlyGated,
+    // qAO: via a client-only connection or gated from RF via an unverified connection
+    ClientOnly,
+    // qAS: from a server, or from a client with a login other than the sender
+    Server,
+    // qAr: gated from RF by an IGate, received via another server
+    GatedIndirect,
+    // qAR: gated from RF by a verified IGate
+    Gated,
+    // qAZ: not to be forwarded
+    NoForward,
+    // qAI: with trace
+    Trace,
+}
+
+impl QConstruct {
+    pub fn from_callsign(callsign: &str) -> Option<QConstruct> {
+        match callsign {
+            "qAC" => Some(QConstruct::Client),
+            "qAX" => Some(QConstruct::Unverified),
+            "qAU" => Some(QConstruct::Udp),
+            "qAo" => Some(QConstruct::ClientOnlyGated),
+            "qAO" => Some(QConstruct::ClientOnly),
+            "qAS" => Some(QConstruct::Server),
+            "qAr" => Some(QConstruct::GatedIndirect),
+            "qAR" => Some(QConstruct::Gated),
+            "qAZ" => Some(QConstruct::NoForward),
+            "qAI" => Some(QConstruct::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl Display for QConstruct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let letter = match self {
+            QConstruct::Client => 'C',
+            QConstruct::Unverified => 'X',
+            QConstruct::Udp => 'U',
+            QConstruct::ClientOnlyGated => 'o',
+            QConstruct::ClientOnly => 'O',
+            QConstruct::Server => 'S',
+            QConstruct::GatedIndirect => 'r',
+            QConstruct::Gated => 'R',
+            QConstruct::NoForward => 'Z',
+            QConstruct::Trace => 'I',
+        };
+        write!(f, "qA{}", letter)
+    }
+}
+
+// An entry of the via path
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub enum PathEntry {
+    QConstruct(QConstruct),
+    // A digipeater, relay or receiver, "used" if it or a station after it is marked with '*':
+    // OGN123456*, WIDE1-1*
+    Digipeater { callsign: Callsign, used: bool },
+}
+
+impl From<&Callsign> for PathEntry {
+    fn from(callsign: &Callsign) -> Self {
+        if let Some(q_construct) = QConstruct::from_callsign(&callsign.call) {
+            if callsign.ssid.is_none() {
+                return PathEntry::QConstruct(q_construct);
+            }
+        }
+        // The '*' follows the SSID if there is one
+        let (call, ssid) = match &callsign.ssid {
+            Some(ssid) => (
+                callsign.call.as_str(),
+                ssid.strip_suffix('*').or(Some(ssid)),
+            ),
+            None => (callsign.call.trim_end_matches('*'), None),
+        };
+        let used = callsign.to_string().ends_with('*');
+        PathEntry::Digipeater {
+            callsign: Callsign::new(call, ssid),
+            used,
+        }
+    }
+}
+
+impl AprsPacket {
+    // Only the last station that digipeated the packet gets the '*', all stations before
+    // it are used too: A,B*,C is used by A and B
+    pub fn path(&self) -> Vec<PathEntry> {
+        let mut path = self.via.iter().map(PathEntry::from).collect::<Vec<_>>();
+        let last_used = path
+            .iter()
+            .rposition(|entry| matches!(entry, PathEntry::Digipeater { used: true, .. }));
+        if let Some(last_used) = last_used {
+            for entry in &mut path[..last_used] {
+                if let PathEntry::Digipeater { used, .. } = entry {
+                    *used = true;
+                }
+            }
+        }
+        path
+    }
+
+    // The q construct and the station following it, for OGN the receiver (qAS)
+    // or the server (qAC)
+    pub fn q_construct(&self) -> Option<(QConstruct, Option<Callsign>)> {
+        let path = self.path();
+        let idx = path
+            .iter()
+            .position(|entry| matches!(entry, PathEntry::QConstruct(_)))?;
+        let PathEntry::QConstruct(q_construct) = path[idx] else {
+            return None;
+        };
+        let station = match path.into_iter().nth(idx + 1) {
+            Some(PathEntry::Digipeater { callsign, .. }) => Some(callsign),
+            _ => None,
+        };
+        Some((q_construct, station))
+    }
+}
+
+// OGN relay semantics: the receiver follows the q construct, a station that relayed the
+// beacon before it is marked as used: FLRDDFAA3>OGFLR,OGN123456*,qAS,Letzi:...
+impl Message {
+    // The station that received the packet from RF and forwarded it to APRS-IS
+    pub fn receiver(&self) -> Option<Callsign> {
+        let packet = self.aprs_packet.as_ref().ok()?;
+        match packet.q_construct()? {
+            (
+                QConstruct::Server
+                | QConstruct::Gated
+                | QConstruct::GatedIndirect
+                | QConstruct::ClientOnlyGated,
+                station,
+            ) => station,
+            _ => None,
+        }
+    }
+
+    pub fn is_relayed(&self) -> bool {
+        self.relay().is_some()
+    }
+
+    // The last used station before the q construct, e.g. an OGN tracker relaying the
+    // beacon of another one. TCPIP* only says that the sender is connected directly.
+    pub fn relay(&self) -> Option<Callsign> {
+        let packet = self.aprs_packet.as_ref().ok()?;
+        packet
+            .path()
+            .into_iter()
+            .take_while(|entry| !matches!(entry, PathEntry::QConstruct(_)))
+            .filter_map(|entry| match entry {
+                PathEntry::Digipeater {
+                    callsign,
+                    used: true,
+                } if !callsign.call.starts_with("TCP") => Some(callsign),
+                _ => None,
+            })
+            .last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_q_construct() {
+        for q_construct in [
+            "qAC", "qAX", "qAU", "qAo", "qAO", "qAS", "qAr", "qAR", "qAZ", "qAI",
+        ] {
+            assert_eq!(
+                QConstruct::from_callsign(q_construct).unwrap().to_string(),
+                q_construct
+            );
+        }
+        assert_eq!(QConstruct::from_callsign("qAQ"), None);
+        assert_eq!(QConstruct::from_callsign("QAS"), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let packet = r"FLRDDFAA3>OGFLR,OGN123456*,WIDE1-1*,WIDE2-1,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert_eq!(
+            packet.path(),
+            vec![
+                PathEntry::Digipeater {
+                    callsign: Callsign::new("OGN123456", None),
+                    used: true,
+                },
+                PathEntry::Digipeater {
+                    callsign: Callsign::new("WIDE1", Some("1")),
+                    used: true,
+                },
+                PathEntry::Digipeater {
+                    callsign: Callsign::new("WIDE2", Some("1")),
+                    used: false,
+                },
+                PathEntry::QConstruct(QConstruct::Server),
+                PathEntry::Digipeater {
+                    callsign: Callsign::new("Letzi", None),
+                    used: false,
+                },
+            ]
+        );
+        assert_eq!(
+            packet.q_construct(),
+            Some((QConstruct::Server, Some(Callsign::new("Letzi", None))))
+        );
+
+        let packet = "Letzi>OGNSDR,TCPIP*,qAC:>085623h v0.2.7"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert_eq!(packet.q_construct(), Some((QConstruct::Client, None)));
+        let packet = "OGN123456>OGNAPP::OGNDDFAA3:Hi"
+            .parse::<AprsPacket>()
+            .unwrap();
+        assert_eq!(packet.q_construct(), None);
+    }
+
+    #[test]
+    fn test_receiver_and_relay() {
+        let message =
+            r"FLRDDFAA3>OGFLR,OGN123456*,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+                .parse::<Message>()
+                .unwrap();
+        assert_eq!(message.receiver(), Some(Callsign::new("Letzi", None)));
+        assert!(message.is_relayed());
+        assert_eq!(message.relay(), Some(Callsign::new("OGN123456", None)));
+
+        // The relay without the '*' was used before the one with it
+        let message = r"FLRDDFAA3>OGFLR,OGN123456,OGNABCDEF*,WIDE2-1,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<Message>()
+            .unwrap();
+        let path = message.aprs_packet.as_ref().unwrap().path();
+        assert!(matches!(path[0], PathEntry::Digipeater { used: true, .. }));
+        assert!(matches!(path[2], PathEntry::Digipeater { used: false, .. }));
+        assert_eq!(message.relay(), Some(Callsign::new("OGNABCDEF", None)));
+
+        let message = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(message.receiver(), Some(Callsign::new("Letzi", None)));
+        assert!(!message.is_relayed());
+
+        // Receivers send their own beacons directly to the server
+        let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(message.receiver(), None);
+        assert_eq!(message.relay(), None);
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::json;
+
+use crate::error::AprsError;
+use crate::limits::Limits;
+use crate::message::Message;
+use crate::provenance::Provenance;
+use crate::pseudonym::Pseudonymizer;
+
+#[derive(Default)]
+pub struct Pipeline<'a> {
+    quarantine: Option<Box<dyn Write + 'a>>,
+    limits: Option<Limits>,
+    pseudonymizer: Option<Pseudonymizer>,
+    attach_provenance: bool,
+    connection_id: Option<u64>,
+    // The server of the last keepalive
+    server: Option<String>,
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new() -> Self {
+        Pipeline::default()
+    }
+
+    // Parse failures are written as NDJSON to the quarantine instead of being passed on
+    pub fn with_quarantine<W: Write + 'a>(mut self, writer: W) -> Self {
+        self.quarantine = Some(Box::new(writer));
+        self
+    }
+
+    // Lines exceeding the limits are rejected like parse failures
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    // The accepted messages get pseudonyms instead of the device addresses
+    pub fn with_pseudonymizer(mut self, pseudonymizer: Pseudonymizer) -> Self {
+        self.pseudonymizer = Some(pseudonymizer);
+        self
+    }
+
+    // The accepted messages get a provenance record with the server of the connection,
+    // learned from its keepalives, and the connection id
+    pub fn with_provenance(mut self, connection_id: Option<u64>) -> Self {
+        self.attach_provenance = true;
+        self.connection_id = connection_id;
+        self
+    }
+
+    pub fn process(&mut self, line: &str) -> io::Result<Option<Message>> {
+        let message = match &self.limits {
+            Some(limits) => Message::parse_with_limits(line, limits),
+            None => line.parse::<Message>().unwrap(),
+        };
+        if let Some(server) = message
+            .server_comment
+            .as_ref()
+            .and_then(|server_comment| server_comment.server.as_ref())
+        {
+            self.server = Some(server.clone());
+        }
+        // Server comments are no parse failures
+        if let (Err(error), None, Some(quarantine)) = (
+            &message.aprs_packet,
+            &message.server_comment,
+            self.quarantine.as_mut(),
+        ) {
+            let record = json!({
+                "error_kind": error_kind(error),
+                "error": error.to_string(),
+                "raw_string": message.raw_string,
+            });
+            writeln!(quarantine, "{}", record)?;
+            self.rejected += 1;
+            return Ok(None);
+        }
+        self.accepted += 1;
+        let mut message = match (&self.pseudonymizer, &self.limits) {
+            (Some(pseudonymizer), Some(limits)) => {
+                pseudonymizer.apply_with(&message, |line| Message::parse_with_limits(line, limits))
+            }
+            (Some(pseudonymizer), None) => pseudonymizer.apply(&message),
+            (None, _) => message,
+        };
+        if self.attach_provenance {
+            message.provenance = Some(Provenance::new(
+                &message,
+                self.server.as_deref(),
+                self.connection_id,
+            ));
+        }
+        Ok(Some(message))
+    }
+
+    pub fn run<R: BufRead, F: FnMut(Message)>(&mut self, reader: R, mut f: F) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(message) = self.process(&line)? {
+                f(message);
+            }
+        }
+        if let Some(quarantine) = self.quarantine.as_mut() {
+            quarantine.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// The variant name of the error, e.g. "InvalidPosition"
+pub(crate) fn error_kind(error: &AprsError) -> String {
+    format!("{:?}", error)
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_quarantine() {
+        let lines = "ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!\n\nnot an aprs packet\n# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152\n";
+        let mut quarantine = vec![];
+        let mut messages = vec![];
+        let mut pipeline = Pipeline::new().with_quarantine(&mut quarantine);
+        pipeline
+            .run(lines.as_bytes(), |message| messages.push(message))
+            .unwrap();
+        assert_eq!((pipeline.accepted, pipeline.rejected), (2, 1));
+        drop(pipeline);
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].server_comment.is_some());
+        let quarantine = String::from_utf8(quarantine).unwrap();
+        assert_eq!(quarantine.lines().count(), 1);
+        let record = serde_json::from_str::<Value>(quarantine.trim()).unwrap();
+        assert_eq!(record["raw_string"], Value::from("not an aprs packet"));
+        assert!(!record["error_kind"].as_str().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_without_quarantine() {
+        let mut pipeline = Pipeline::new();
+        let message = pipeline.process("not an aprs packet").unwrap().unwrap();
+        assert!(message.aprs_packet.is_err());
+        assert_eq!((pipeline.accepted, pipeline.rejected), (1, 0));
+    }
+
+    #[test]
+    fn test_pseudonymizer() {
+        let line =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3";
+        let mut pipeline = Pipeline::new().with_pseudonymizer(Pseudonymizer::new(b"secret"));
+        let message = pipeline.process(line).unwrap().unwrap();
+        assert!(!message.raw_string.contains("DDFAA3"));
+    }
+
+    #[test]
+    fn test_limits() {
+        let line = format!("ICA3D17F2>APRS,qAS,dl4mea:>312359z{}", "x".repeat(600));
+        let mut quarantine = vec![];
+        let mut pipeline = Pipeline::new()
+            .with_quarantine(&mut quarantine)
+            .with_limits(Limits::default());
+        assert!(pipeline.process(&line).unwrap().is_none());
+        drop(pipeline);
+        let record =
+            serde_json::from_str::<Value>(std::str::from_utf8(&quarantine).unwrap()).unwrap();
+        assert_eq!(record["error_kind"], Value::from("LimitExceeded"));
+    }
+
+    #[test]
+    fn test_provenance() {
+        let mut pipeline = Pipeline::new().with_provenance(Some(7));
+        let line = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607";
+        let message = pipeline.process(line).unwrap().unwrap();
+        let provenance = message.provenance.unwrap();
+        assert_eq!(provenance.server, None);
+        assert_eq!(provenance.connection_id, Some(7));
+        assert_eq!(provenance.gateway, Some("Letzi".to_string()));
+
+        pipeline
+            .process(
+                "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152",
+            )
+            .unwrap();
+        let message = pipeline.process(line).unwrap().unwrap();
+        assert_eq!(
+            message.provenance.unwrap().server,
+            Some("GLIDERN3".to_string())
+        );
+
+        let message = Pipeline::new().process(line).unwrap().unwrap();
+        assert_eq!(message.provenance, None);
+    }
+}
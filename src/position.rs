@@ -0,0 +1,672 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cursor::FieldCursor;
+use crate::error::AprsError;
+use crate::timestamp::Timestamp;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AprsPosition {
+    pub timestamp: Option<Timestamp>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub symbol_table: char,
+    pub symbol_code: char,
+    // Only for compressed positions
+    pub compression: Option<Compression>,
+    // Only for Mic-E positions
+    pub mic_e: Option<MicE>,
+    // The number of trailing digits of the uncompressed latitude replaced with spaces (0-4)
+    pub ambiguity: u8,
+    pub comment: String,
+}
+
+// The data of a Mic-E position beyond latitude and longitude
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct MicE {
+    // in degrees 0-360, 0: unknown
+    pub course: u16,
+    // in knots
+    pub speed: u16,
+    // The message bits A, B and C of the destination, e.g. 0b111: "Off duty", 0b000: "Emergency"
+    pub message: u8,
+    // Custom message instead of a standard one
+    pub custom_message: bool,
+    // '`': current GPS data, '\'': old GPS data
+    pub current: bool,
+}
+
+// The compression type and the cs bytes of a compressed position
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Compression {
+    // GPS fix (bit 5), NMEA source (bits 3 and 4) and origin (bits 0 to 2)
+    pub compression_type: u8,
+    pub extension: Option<CompressedExtension>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum CompressedExtension {
+    // course in degrees, speed in knots
+    CourseSpeed { course: u16, speed: f64 },
+    // Radio range in miles
+    Range(f64),
+    // in feet
+    Altitude(f64),
+}
+
+impl FromStr for AprsPosition {
+    type Err = AprsError;
+
+    // Parses the information field including the data type identifier,
+    // e.g. "/074849h4821.61N\01224.49E^322/103/A=003054"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cursor = FieldCursor::new(s);
+        let timestamp = match cursor.take_n(1) {
+            Some("/") | Some("@") => {
+                let timestamp = cursor
+                    .take_n(7)
+                    .ok_or_else(|| AprsError::InvalidTimestamp(cursor.rest().to_string()))?;
+                Some(timestamp.parse::<Timestamp>()?)
+            }
+            Some("!") | Some("=") => None,
+            _ => return Err(AprsError::InvalidPosition(s.to_string())),
+        };
+
+        // Uncompressed positions start with the latitude, compressed ones with the symbol table
+        let rest = cursor.rest();
+        if rest.starts_with(['/', '\\']) || rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return parse_compressed(timestamp, rest);
+        }
+        if !rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(AprsError::UnsupportedPositionFormat(rest.to_string()));
+        }
+        let invalid = || AprsError::InvalidPosition(rest.to_string());
+        let symbol = |cursor: &mut FieldCursor| {
+            cursor
+                .take_n(1)
+                .and_then(|s| s.chars().next())
+                .ok_or_else(invalid)
+        };
+        let raw_latitude = cursor.take_n(8).ok_or_else(invalid)?;
+        let latitude = parse_latitude(raw_latitude)?;
+        let ambiguity = raw_latitude.bytes().filter(|byte| *byte == b' ').count() as u8;
+        let symbol_table = symbol(&mut cursor)?;
+        let longitude = parse_longitude(cursor.take_n(9).ok_or_else(invalid)?)?;
+        let symbol_code = symbol(&mut cursor)?;
+        Ok(AprsPosition {
+            timestamp,
+            latitude,
+            symbol_table,
+            longitude,
+            symbol_code,
+            compression: None,
+            mic_e: None,
+            ambiguity,
+            comment: cursor.rest().to_string(),
+        })
+    }
+}
+
+impl AprsPosition {
+    // Mic-E encodes the latitude and the message bits in the destination callsign,
+    // the longitude, speed and course in the information field (e.g. "`(_fn\"Oj/")
+    pub fn from_mic_e(destination: &str, information: &str) -> Result<Self, AprsError> {
+        let invalid = || AprsError::InvalidPosition(information.to_string());
+        let current = match information.chars().next() {
+            Some('`') => true,
+            Some('\'') => false,
+            _ => return Err(invalid()),
+        };
+
+        let destination = destination.split('-').next().unwrap_or_default();
+        let invalid_latitude = || AprsError::InvalidLatitude(destination.to_string());
+        if destination.len() != 6 {
+            return Err(invalid_latitude());
+        }
+        let (mut digits, mut flags) = ([0u32; 6], [false; 6]);
+        let (mut message, mut custom_message) = (0, false);
+        for (idx, byte) in destination.bytes().enumerate() {
+            // (digit, message bit, custom message, flag), spaces of the position ambiguity are 0
+            let (digit, bit, custom, flag) = match byte {
+                b'0'..=b'9' => (byte - b'0', false, false, false),
+                b'A'..=b'J' if idx < 3 => (byte - b'A', true, true, false),
+                b'K' if idx < 3 => (0, true, true, false),
+                b'L' => (0, false, false, false),
+                b'P'..=b'Y' => (byte - b'P', true, false, true),
+                b'Z' => (0, true, false, true),
+                _ => return Err(invalid_latitude()),
+            };
+            digits[idx] = u32::from(digit);
+            flags[idx] = flag;
+            if idx < 3 {
+                message = message << 1 | u8::from(bit);
+                custom_message |= custom;
+            }
+        }
+        let minutes =
+            (digits[2] * 1000 + digits[3] * 100 + digits[4] * 10 + digits[5]) as f64 / 100.0;
+        let degrees = digits[0] * 10 + digits[1];
+        if degrees > 90 || minutes >= 60.0 {
+            return Err(invalid_latitude());
+        }
+        // Flags: 4th north, 5th longitude offset of 100 degrees, 6th west
+        let latitude = degrees as f64 + minutes / 60.0;
+
+        // The data type identifier was checked above
+        let mut cursor = FieldCursor::new(information);
+        cursor.take_n(1);
+        let mut values = |n: usize| -> Result<Vec<u32>, AprsError> {
+            cursor
+                .take_n(n)
+                .filter(|field| field.bytes().all(|byte| (28..=127).contains(&byte)))
+                .map(|field| field.bytes().map(|byte| u32::from(byte - 28)).collect())
+                .ok_or_else(invalid)
+        };
+        let longitude_values = values(3)?;
+        let motion_values = values(3)?;
+        let mut degrees = longitude_values[0] + if flags[4] { 100 } else { 0 };
+        match degrees {
+            180..=189 => degrees -= 80,
+            190..=199 => degrees -= 190,
+            _ => {}
+        }
+        let minutes = match longitude_values[1] {
+            minutes if minutes >= 60 => minutes - 60,
+            minutes => minutes,
+        };
+        let hundredths = longitude_values[2];
+        if degrees > 180 || minutes >= 60 || hundredths >= 100 {
+            let longitude = longitude_values
+                .iter()
+                .map(|value| (value + 28) as u8 as char);
+            return Err(AprsError::InvalidLongitude(longitude.collect()));
+        }
+        let longitude = degrees as f64 + (minutes as f64 + hundredths as f64 / 100.0) / 60.0;
+
+        let (sp, dc, se) = (motion_values[0], motion_values[1], motion_values[2]);
+        let speed = sp * 10 + dc / 10;
+        let course = dc % 10 * 100 + se;
+        let mut symbol = || {
+            cursor
+                .take_n(1)
+                .and_then(|s| s.chars().next())
+                .filter(|c| c.is_ascii_graphic())
+                .ok_or_else(invalid)
+        };
+        let symbol_code = symbol()?;
+        let symbol_table = symbol()?;
+        Ok(AprsPosition {
+            timestamp: None,
+            latitude: if flags[3] { latitude } else { -latitude },
+            longitude: if flags[5] { -longitude } else { longitude },
+            symbol_code,
+            symbol_table,
+            compression: None,
+            mic_e: Some(MicE {
+                course: (if course >= 400 { course - 400 } else { course }) as u16,
+                speed: (if speed >= 800 { speed - 800 } else { speed }) as u16,
+                message,
+                custom_message,
+                current,
+            }),
+            ambiguity: 0,
+            comment: cursor.rest().to_string(),
+        })
+    }
+}
+
+impl Display for AprsPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Only the information field, the latitude stays in the destination
+        if let Some(mic_e) = &self.mic_e {
+            return write!(
+                f,
+                "{}{}{}{}{}",
+                if mic_e.current { '`' } else { '\'' },
+                encode_mic_e(self.longitude, mic_e),
+                self.symbol_code,
+                self.symbol_table,
+                self.comment
+            );
+        }
+        match &self.timestamp {
+            Some(timestamp) => write!(f, "/{}", timestamp)?,
+            None => write!(f, "!")?,
+        }
+        if let Some(compression) = &self.compression {
+            return write!(
+                f,
+                "{}{}{}{}{}{}",
+                self.symbol_table,
+                encode_base91((380926.0 * (90.0 - self.latitude)).round() as u32),
+                encode_base91((190463.0 * (180.0 + self.longitude)).round() as u32),
+                self.symbol_code,
+                encode_extension(compression),
+                self.comment
+            );
+        }
+        write!(
+            f,
+            "{}{}{}{}{}",
+            blank_digits(encode_latitude(self.latitude), self.ambiguity),
+            self.symbol_table,
+            blank_digits(encode_longitude(self.longitude), self.ambiguity),
+            self.symbol_code,
+            self.comment
+        )
+    }
+}
+
+// "/5L!!<*e7>7P[": symbol table, latitude, longitude, symbol code, cs bytes and compression type
+fn parse_compressed(timestamp: Option<Timestamp>, s: &str) -> Result<AprsPosition, AprsError> {
+    let base91 = |field: &str| {
+        field.bytes().try_fold(0u32, |value, byte| {
+            (b'!'..=b'{')
+                .contains(&byte)
+                .then(|| value * 91 + u32::from(byte - b'!'))
+        })
+    };
+    let invalid = || AprsError::InvalidPosition(s.to_string());
+    let mut cursor = FieldCursor::new(s);
+    let symbol_table = cursor
+        .take_n(1)
+        .and_then(|symbol_table| symbol_table.chars().next())
+        .ok_or_else(invalid)?;
+    let (y, x) = match (cursor.take_n(4), cursor.take_n(4)) {
+        (Some(y), Some(x)) => (y, x),
+        _ => return Err(invalid()),
+    };
+    let (latitude, longitude) = match (base91(y), base91(x)) {
+        (Some(y), Some(x)) => (90.0 - y as f64 / 380926.0, -180.0 + x as f64 / 190463.0),
+        _ => return Err(invalid()),
+    };
+    if latitude < -90.0 {
+        return Err(AprsError::InvalidLatitude(y.to_string()));
+    }
+    if longitude > 180.0 {
+        return Err(AprsError::InvalidLongitude(x.to_string()));
+    }
+    let (symbol_code, c, s_byte, t) = match cursor.take_n(4).map(str::as_bytes) {
+        Some(&[symbol_code, c, s_byte, t]) if symbol_code.is_ascii() => {
+            (symbol_code as char, c, s_byte, t)
+        }
+        _ => return Err(invalid()),
+    };
+    let compression_type = match t {
+        b'!'..=b'{' => t - b'!',
+        _ => return Err(invalid()),
+    };
+    // The cs bytes are unused if c is a space
+    let extension = match (c, s_byte) {
+        (b' ', _) => None,
+        (b'!'..=b'{', b'!'..=b'{') => {
+            let (c, s_value) = (c - b'!', s_byte - b'!');
+            if compression_type & 0b0001_1000 == 0b0001_0000 {
+                // From a GGA sentence
+                let cs = i32::from(c) * 91 + i32::from(s_value);
+                Some(CompressedExtension::Altitude(1.002f64.powi(cs)))
+            } else if c <= 89 {
+                Some(CompressedExtension::CourseSpeed {
+                    course: u16::from(c) * 4,
+                    speed: 1.08f64.powi(i32::from(s_value)) - 1.0,
+                })
+            } else {
+                Some(CompressedExtension::Range(
+                    2.0 * 1.08f64.powi(i32::from(s_value)),
+                ))
+            }
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(AprsPosition {
+        timestamp,
+        latitude,
+        longitude,
+        symbol_table,
+        symbol_code,
+        compression: Some(Compression {
+            compression_type,
+            extension,
+        }),
+        mic_e: None,
+        ambiguity: 0,
+        comment: cursor.rest().to_string(),
+    })
+}
+
+fn encode_base91(mut value: u32) -> String {
+    let mut digits = [b'!'; 4];
+    for digit in digits.iter_mut().rev() {
+        *digit = b'!' + (value % 91) as u8;
+        value /= 91;
+    }
+    String::from_utf8_lossy(&digits).into_owned()
+}
+
+// The cs bytes and the compression type byte
+fn encode_extension(compression: &Compression) -> String {
+    let byte = |value: i64| (b'!' + value.clamp(0, 90) as u8) as char;
+    let (c, s) = match compression.extension {
+        None => (' ', ' '),
+        Some(CompressedExtension::CourseSpeed { course, speed }) => (
+            byte(i64::from(course / 4)),
+            byte(((speed + 1.0).ln() / 1.08f64.ln()).round() as i64),
+        ),
+        Some(CompressedExtension::Range(range)) => (
+            byte(90),
+            byte(((range / 2.0).ln() / 1.08f64.ln()).round() as i64),
+        ),
+        Some(CompressedExtension::Altitude(altitude)) => {
+            let cs = (altitude.ln() / 1.002f64.ln()).round() as i64;
+            (byte(cs / 91), byte(cs % 91))
+        }
+    };
+    format!(
+        "{}{}{}",
+        c,
+        s,
+        byte(i64::from(compression.compression_type))
+    )
+}
+
+// The longitude, speed and course bytes of Mic-E, with the offsets which keep them printable
+fn encode_mic_e(longitude: f64, mic_e: &MicE) -> String {
+    let (degrees, hundredths) = split_degrees(longitude);
+    let degrees = match degrees {
+        0..=9 => degrees + 90,
+        10..=99 => degrees,
+        100..=109 => degrees - 20,
+        _ => degrees - 100,
+    };
+    let minutes = match hundredths / 100 {
+        minutes @ 0..=9 => minutes + 60,
+        minutes => minutes,
+    };
+    let (speed, course) = (u32::from(mic_e.speed) + 800, u32::from(mic_e.course) + 400);
+    [
+        degrees,
+        minutes,
+        hundredths % 100,
+        speed / 10,
+        speed % 10 * 10 + course / 100,
+        course % 100,
+    ]
+    .iter()
+    .map(|value| (value + 28) as u8 as char)
+    .collect()
+}
+
+// "4821.61N", position ambiguity (spaces instead of digits) is resolved to the lower bound
+fn parse_latitude(s: &str) -> Result<f64, AprsError> {
+    let error = || AprsError::InvalidLatitude(s.to_string());
+    let value = parse_degrees(s, 2).ok_or_else(error)?;
+    match &s[7..] {
+        "N" if value <= 90.0 => Ok(value),
+        "S" if value <= 90.0 => Ok(-value),
+        _ => Err(error()),
+    }
+}
+
+// "01224.49E"
+fn parse_longitude(s: &str) -> Result<f64, AprsError> {
+    let error = || AprsError::InvalidLongitude(s.to_string());
+    let value = parse_degrees(s, 3).ok_or_else(error)?;
+    match &s[8..] {
+        "E" if value <= 180.0 => Ok(value),
+        "W" if value <= 180.0 => Ok(-value),
+        _ => Err(error()),
+    }
+}
+
+fn parse_degrees(s: &str, digits: usize) -> Option<f64> {
+    let s = s.replace(' ', "0");
+    if s.as_bytes().get(digits + 2) != Some(&b'.')
+        || !s.as_bytes()[..digits + 5]
+            .iter()
+            .enumerate()
+            .all(|(idx, b)| idx == digits + 2 || b.is_ascii_digit())
+    {
+        return None;
+    }
+    let degrees = s[..digits].parse::<f64>().ok()?;
+    let minutes = s[digits..digits + 5].parse::<f64>().ok()?;
+    if minutes >= 60.0 {
+        return None;
+    }
+    Some((degrees * 60.0 + minutes) / 60.0)
+}
+
+fn encode_latitude(latitude: f64) -> String {
+    let (degrees, minutes) = split_degrees(latitude);
+    let hemisphere = if latitude < 0.0 { 'S' } else { 'N' };
+    format!(
+        "{:02}{:02}.{:02}{}",
+        degrees,
+        minutes / 100,
+        minutes % 100,
+        hemisphere
+    )
+}
+
+fn encode_longitude(longitude: f64) -> String {
+    let (degrees, minutes) = split_degrees(longitude);
+    let hemisphere = if longitude < 0.0 { 'W' } else { 'E' };
+    format!(
+        "{:03}{:02}.{:02}{}",
+        degrees,
+        minutes / 100,
+        minutes % 100,
+        hemisphere
+    )
+}
+
+// Replaces the last digits of the minutes with spaces: "4821.61N" -> "4821.  N"
+fn blank_digits(encoded: String, ambiguity: u8) -> String {
+    let mut blanked = 0;
+    let mut bytes = encoded.into_bytes();
+    for byte in bytes.iter_mut().rev() {
+        if blanked == ambiguity.min(4) {
+            break;
+        }
+        if byte.is_ascii_digit() {
+            *byte = b' ';
+            blanked += 1;
+        }
+    }
+    String::from_utf8(bytes).unwrap()
+}
+
+// Degrees and hundredths of minutes, rounded as a whole so we never get 60 minutes
+fn split_degrees(value: f64) -> (u32, u32) {
+    let hundredths = (value.abs() * 6000.0).round() as u32;
+    (hundredths / 6000, hundredths % 6000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let position = r"/074849h4821.61N\01224.49E^322/103/A=003054"
+            .parse::<AprsPosition>()
+            .unwrap();
+        assert_eq!(position.timestamp, Some(Timestamp::HHMMSS(7, 48, 49)));
+        assert_eq!(position.latitude, 48.36016666666667);
+        assert_eq!(position.longitude, 12.408166666666666);
+        assert_eq!(position.symbol_table, '\\');
+        assert_eq!(position.symbol_code, '^');
+        assert_eq!(position.comment, "322/103/A=003054");
+        assert_eq!(
+            position.to_string(),
+            r"/074849h4821.61N\01224.49E^322/103/A=003054"
+        );
+
+        let position = "!5111.32S/00102.04W'".parse::<AprsPosition>().unwrap();
+        assert_eq!(position.timestamp, None);
+        assert!(position.latitude < -51.0 && position.longitude < -1.0);
+        assert_eq!(position.comment, "");
+        assert_eq!(position.to_string(), "!5111.32S/00102.04W'");
+    }
+
+    #[test]
+    fn test_position_errors() {
+        assert_eq!(
+            "/074849h_4821.61N".parse::<AprsPosition>(),
+            Err(AprsError::UnsupportedPositionFormat(
+                "_4821.61N".to_string()
+            ))
+        );
+        assert!(matches!(
+            "/074849h/5L!!<*e7>7".parse::<AprsPosition>(),
+            Err(AprsError::InvalidPosition(_))
+        ));
+        assert!(matches!(
+            "/074849h/5L!}<*e7>7P[".parse::<AprsPosition>(),
+            Err(AprsError::InvalidPosition(_))
+        ));
+        assert_eq!(
+            r"/074849h9921.61N\01224.49E^".parse::<AprsPosition>(),
+            Err(AprsError::InvalidLatitude("9921.61N".to_string()))
+        );
+        assert_eq!(
+            r"/074849h4821.61N\01274.49E^".parse::<AprsPosition>(),
+            Err(AprsError::InvalidLongitude("01274.49E".to_string()))
+        );
+        assert_eq!(
+            r"/074849h4821.61N\01224.4".parse::<AprsPosition>(),
+            Err(AprsError::InvalidPosition(r"4821.61N\01224.4".to_string()))
+        );
+        assert!(matches!(
+            r"/07484".parse::<AprsPosition>(),
+            Err(AprsError::InvalidTimestamp(_))
+        ));
+    }
+
+    #[test]
+    fn test_compressed() {
+        // The example of the APRS specification: 49°30'N 72°45'W, course 88°, speed 36.2 kt
+        let position = "/074849h/5L!!<*e7>7P[".parse::<AprsPosition>().unwrap();
+        assert_eq!(position.timestamp, Some(Timestamp::HHMMSS(7, 48, 49)));
+        assert!((position.latitude - 49.5).abs() < 1e-5);
+        assert!((position.longitude - -72.75).abs() < 1e-5);
+        assert_eq!(position.symbol_table, '/');
+        assert_eq!(position.symbol_code, '>');
+        assert_eq!(position.comment, "");
+        let compression = position.compression.clone().unwrap();
+        assert_eq!(compression.compression_type, 58);
+        match compression.extension {
+            Some(CompressedExtension::CourseSpeed { course, speed }) => {
+                assert_eq!(course, 88);
+                assert!((speed - 36.2).abs() < 0.1);
+            }
+            extension => panic!("Unexpected extension {:?}", extension),
+        }
+        assert_eq!(position.to_string(), "/074849h/5L!!<*e7>7P[");
+
+        // Altitude of 10004 ft from a GGA sentence
+        let position = "!/5L!!<*e7OS]S comment".parse::<AprsPosition>().unwrap();
+        match position.compression.as_ref().unwrap().extension {
+            Some(CompressedExtension::Altitude(altitude)) => {
+                assert!((altitude - 10004.0).abs() < 1.0)
+            }
+            ref extension => panic!("Unexpected extension {:?}", extension),
+        }
+        assert_eq!(position.comment, " comment");
+        assert_eq!(position.to_string(), "!/5L!!<*e7OS]S comment");
+
+        // Radio range of 20 miles
+        let position = "!/5L!!<*e7>{?!".parse::<AprsPosition>().unwrap();
+        match position.compression.as_ref().unwrap().extension {
+            Some(CompressedExtension::Range(range)) => assert!((range - 20.0).abs() < 0.5),
+            ref extension => panic!("Unexpected extension {:?}", extension),
+        }
+        assert_eq!(position.to_string(), "!/5L!!<*e7>{?!");
+
+        // Without course and speed
+        let position = r"!\5L!!<*e7>  !".parse::<AprsPosition>().unwrap();
+        assert_eq!(position.compression.as_ref().unwrap().extension, None);
+        assert_eq!(position.to_string(), r"!\5L!!<*e7>  !");
+    }
+
+    #[test]
+    fn test_mic_e() {
+        // The example of the APRS specification: 33°25.64'N 112°07.74'W, 20 knots, course 251°
+        let position = AprsPosition::from_mic_e("S32UVT", "`(_fn\"Oj/]\"4-}").unwrap();
+        assert!((position.latitude - 33.427333).abs() < 1e-5);
+        assert!((position.longitude - -112.129).abs() < 1e-5);
+        assert_eq!(position.symbol_code, 'j');
+        assert_eq!(position.symbol_table, '/');
+        assert_eq!(position.comment, "]\"4-}");
+        assert_eq!(
+            position.mic_e,
+            Some(MicE {
+                course: 251,
+                speed: 20,
+                message: 0b100,
+                custom_message: false,
+                current: true,
+            })
+        );
+        assert_eq!(position.to_string(), "`(_fn\"Oj/]\"4-}");
+
+        // South, east, with a custom message and old GPS data
+        let position = AprsPosition::from_mic_e("D32360-9", "'(_fn\"Oj/").unwrap();
+        assert!(position.latitude < -33.3 && position.longitude > 12.1);
+        assert_eq!(position.mic_e.as_ref().unwrap().message, 0b100);
+        assert!(position.mic_e.as_ref().unwrap().custom_message);
+        assert!(!position.mic_e.as_ref().unwrap().current);
+
+        assert_eq!(
+            AprsPosition::from_mic_e("S32UV", "`(_fn\"Oj/"),
+            Err(AprsError::InvalidLatitude("S32UV".to_string()))
+        );
+        assert_eq!(
+            AprsPosition::from_mic_e("S32UVT", "`(_fn\""),
+            Err(AprsError::InvalidPosition("`(_fn\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        let position = r"!4821.  N\01224.  E^".parse::<AprsPosition>().unwrap();
+        assert!((position.latitude - 48.35).abs() < 1e-9);
+        assert!((position.longitude - 12.4).abs() < 1e-9);
+        assert_eq!(position.ambiguity, 2);
+        assert_eq!(position.to_string(), r"!4821.  N\01224.  E^");
+
+        let position = r"!4821.6 N\01224.4 E^".parse::<AprsPosition>().unwrap();
+        assert_eq!(position.ambiguity, 1);
+        assert_eq!(position.to_string(), r"!4821.6 N\01224.4 E^");
+        let position = r"!48  .  N\012  .  E^".parse::<AprsPosition>().unwrap();
+        assert_eq!(position.ambiguity, 4);
+        assert_eq!(position.to_string(), r"!48  .  N\012  .  E^");
+    }
+
+    #[test]
+    fn test_encode_rounding() {
+        assert_eq!(encode_latitude(47.99999999), "4800.00N");
+        assert_eq!(encode_longitude(-0.5), "00030.00W");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let positions = [
+            r"/074849h4821.61N\01224.49E^322/103/A=003054"
+                .parse::<AprsPosition>()
+                .unwrap(),
+            r"!48  .  N\012  .  E^".parse().unwrap(),
+            "!/5L!!<*e7OS]S comment".parse().unwrap(),
+            AprsPosition::from_mic_e("S32UVT", "`(_fn\"Oj/]\"4-}").unwrap(),
+        ];
+        for position in positions {
+            let json = serde_json::to_string(&position).unwrap();
+            assert_eq!(
+                serde_json::from_str::<AprsPosition>(&json).unwrap(),
+                position
+            );
+        }
+    }
+}
@@ -1,23 +1,214 @@
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
 use std::{convert::Infallible, str::FromStr};
 
-use crate::utils::split_value_unit;
-#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize)]
+use crate::cursor::FieldCursor;
+use crate::grammar::{comment_grammar, decimal, digits, signed_decimal};
+use crate::utils::{parse_digits, split_value_unit, Unit};
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
 pub struct AdditionalPrecision {
     pub lat: u8,
     pub lon: u8,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+// The address type of the id, serialized as the number of the protocol
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub enum AddressType {
+    Random,
+    Icao,
+    Flarm,
+    Ogn,
+    // The longer Naviter ids have 6 bits for the address type
+    Other(u8),
+}
+
+impl From<u8> for AddressType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => AddressType::Random,
+            1 => AddressType::Icao,
+            2 => AddressType::Flarm,
+            3 => AddressType::Ogn,
+            other => AddressType::Other(other),
+        }
+    }
+}
+
+impl From<AddressType> for u8 {
+    fn from(address_type: AddressType) -> Self {
+        match address_type {
+            AddressType::Random => 0,
+            AddressType::Icao => 1,
+            AddressType::Flarm => 2,
+            AddressType::Ogn => 3,
+            AddressType::Other(other) => other,
+        }
+    }
+}
+
+impl Serialize for AddressType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for AddressType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u8::deserialize(deserializer).map(AddressType::from)
+    }
+}
+
+// The conventional callsign prefix: RND, ICA, FLR, OGN
+// Only the Naviter ids have the other address types, their callsigns start with NAV
+impl Display for AddressType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self {
+            AddressType::Random => "RND",
+            AddressType::Icao => "ICA",
+            AddressType::Flarm => "FLR",
+            AddressType::Ogn => "OGN",
+            AddressType::Other(_) => "NAV",
+        };
+        write!(f, "{}", prefix)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct ID {
-    pub address_type: u8,
+    pub address_type: AddressType,
     pub aircraft_type: u8,
     pub is_stealth: bool,
     pub is_notrack: bool,
     pub address: u32,
 }
 
-#[derive(Debug, PartialEq, Default, Clone, Serialize)]
+impl ID {
+    // The canonical device id like the callsigns of the aircraft beacons: FLRDDFAA3
+    pub fn device_id(&self) -> String {
+        format!("{}{:06X}", self.address_type, self.address)
+    }
+
+    // The state the address is allocated to, only for ICAO addresses
+    pub fn icao_country(&self) -> Option<&'static str> {
+        if self.address_type == AddressType::Icao {
+            crate::icao::country(self.address)
+        } else {
+            None
+        }
+    }
+}
+
+// Remote ID data (ASTM F3411) of drones gatewayed into OGN, e.g. with the tocall OGNDVS
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct Uas {
+    // Operator registration: Op:FIN87astrdge12k8
+    pub operator_id: Option<String>,
+    // UA type: UA:2
+    // 0: none, 1: aeroplane, 2: helicopter or multirotor, 3: gyroplane, 4: hybrid lift,
+    // 5: ornithopter, 6: glider, 7: kite, 8: free balloon, 9: captive balloon, 10: airship,
+    // 11: parachute, 12: rocket, 13: tethered powered aircraft, 14: ground obstacle, 15: other
+    pub ua_type: Option<u8>,
+}
+
+// Telemetry of high-altitude balloon trackers gatewayed into OGN
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
+pub struct Balloon {
+    // Burst altitude in feet: Burst=aaaaaa
+    pub burst_altitude: Option<u32>,
+    // Battery voltage in V: Bat:3.31V
+    pub battery_voltage: Option<f32>,
+}
+
+// FANET specific fields of OGNFNT beacons
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct Fanet {
+    // FANET aircraft type: 0: other, 1: paraglider, 2: hang glider, 3: balloon, 4: glider,
+    // 5: powered aircraft, 6: helicopter, 7: UAV
+    pub aircraft_type: Option<u8>,
+    // The position is shared with online tracking services
+    pub online_tracking: Option<bool>,
+    // The manufacturer byte of the FANET address, e.g. 0x11 of FNT1103CE
+    pub manufacturer: Option<u8>,
+    // Firmware build of the device: FW:xxxx
+    pub firmware: Option<String>,
+}
+
+// PilotAware specific fields of OGPAW beacons
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
+pub struct PilotAware {
+    // Software release: sw:20231012
+    pub software: Option<String>,
+    // Hardware: hw:Rosetta
+    pub hardware: Option<String>,
+    // Signal strength at the bridge in dBm: RSSI:-85.5
+    pub rssi: Option<f32>,
+    // Radio channel: ch:3
+    pub channel: Option<u8>,
+}
+
+// ADS-B derived fields of OGADSB and OGNSKY beacons
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct AdsB {
+    // Transponder code, 4 octal digits: Sq7000
+    pub squawk: Option<String>,
+    // Registration: regD-EABC
+    pub registration: Option<String>,
+    // ADS-B emitter category A0-D7 and flight number: fnA3:DLH4AB
+    pub category: Option<String>,
+    pub flight: Option<String>,
+    // gnd: on ground, air: airborne
+    pub on_ground: Option<bool>,
+}
+
+// FlyMaster specific fields of OGFLYM beacons
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct FlyMaster {
+    // Decimal serial number of the instrument: id1234567
+    pub serial: Option<u32>,
+    // Battery level in %: Bat:85%
+    pub battery: Option<u8>,
+}
+
+// Fields of the satellite and phone tracker feeds (OGSPOT, OGNINRE, OGLT24, OGSKYL, OGCAPT)
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct ExternalTracker {
+    // The id of the tracker in the feed, e.g. id0-2860357 or id300434060496190
+    pub id: Option<String>,
+    // Sequence number of the feed: seq:123
+    pub sequence: Option<u32>,
+    // Battery level in %: bat:56%
+    pub battery: Option<u8>,
+    // Everything else, e.g. the model and state "SPOT3 GOOD"
+    pub text: Option<String>,
+}
+
+// Direction finding report of an experimental receiver for a target without GPS,
+// the position of the beacon is the one of the receiver
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct BearingReport {
+    // in degrees, 1-360
+    pub bearing: u16,
+    // Number of hits 0-9, 9: 100% of the time
+    pub hits: u8,
+    // in miles
+    pub range: u32,
+    // 0: useless, 1: < 240 degrees beam width ... 9: < 1 degree
+    pub quality: u8,
+    // The callsign of the target: TGT:FLRDDFAA3
+    pub target: Option<String>,
+}
+
+// Course and speed recomputed by a relay or a buffering receiver (e.g. from the Doppler shift),
+// consumers should prefer the original course and speed of the sender
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct DerivedMotion {
+    // in degrees 0-360
+    pub course: u16,
+    // in km/h
+    pub speed: u16,
+}
+
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
 pub struct PositionComment {
     pub course: Option<u16>,
     pub speed: Option<u16>,
@@ -30,191 +221,565 @@ pub struct PositionComment {
     pub error: Option<u8>,
     pub frequency_offset: Option<f32>,
     pub gps_quality: Option<String>,
+    // Satellite systems in addition to GPS, e.g. ["GAL", "GLO"] from gps3x5+GAL+GLO
+    pub gps_constellations: Option<Vec<String>>,
     pub flight_level: Option<f32>,
     pub signal_power: Option<f32>,
     pub software_version: Option<f32>,
     pub hardware_version: Option<u8>,
     pub original_address: Option<u32>,
+    pub uas: Option<Uas>,
+    pub balloon: Option<Balloon>,
+    pub fanet: Option<Fanet>,
+    pub pilot_aware: Option<PilotAware>,
+    pub adsb: Option<AdsB>,
+    pub external_tracker: Option<ExternalTracker>,
+    pub flymaster: Option<FlyMaster>,
+    // Sequence number of the tracker, for packet loss estimation: FN:1234
+    pub frame_counter: Option<u32>,
+    pub bearing_report: Option<BearingReport>,
+    pub derived_motion: Option<DerivedMotion>,
+    // Fields matched by the single letter tokens (s7.07, h41, rD002F8) of a comment without id,
+    // they can as well be free text
+    pub low_confidence_fields: Option<Vec<String>>,
     pub unparsed: Option<String>,
 }
 
+comment_grammar! {
+    fn match_position_token(PositionComment) {
+        // Flight level: FLxx.yy
+        ("FL", "", 1.., signed_decimal) => flight_level,
+        // Software version: sXX.YY
+        ("s", "", 1.., decimal) => software_version,
+        // Hardware version: hXX
+        ("h", "", 2..=2, hardware_version) => hardware_version,
+        // Original address: rXXXXXX
+        ("r", "", 6..=6, original_address) => original_address,
+        // Drone operator id: Op:XXXX, up to 20 letters, digits or '-'
+        ("Op:", "", 1..=20, operator_id) => uas.operator_id,
+        // Drone UA type: UA:n
+        ("UA:", "", 1.., digits, |ua_type: &u8| *ua_type <= 15) => uas.ua_type,
+        // Balloon burst altitude in feet: Burst=aaaaaa
+        ("Burst=", "", 1.., digits) => balloon.burst_altitude,
+        // Balloon battery: Bat:x.yyV
+        ("Bat:", "V", 1.., decimal) => balloon.battery_voltage,
+        // Frame counter: FN:n
+        ("FN:", "", 1.., digits) => frame_counter,
+    }
+}
+
+// XX: hexadecimal value, with a digit so words like "had" don't match
+fn hardware_version(raw: &str) -> Option<u8> {
+    if raw.chars().all(|c| c.is_ascii_hexdigit()) && raw.chars().any(|c| c.is_ascii_digit()) {
+        u8::from_str_radix(raw, 16).ok()
+    } else {
+        None
+    }
+}
+
+// XXXXXX: upper case hex digits for 24 bit address
+fn original_address(raw: &str) -> Option<u32> {
+    if raw
+        .chars()
+        .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() && c.is_ascii_hexdigit())
+    {
+        u32::from_str_radix(raw, 16).ok()
+    } else {
+        None
+    }
+}
+
+comment_grammar! {
+    fn match_fanet_grammar(PositionComment) {
+        // Firmware: FW:xxxx, up to 16 letters, digits, '.' or '-'
+        ("FW:", "", 1..=16, identifier) => fanet.firmware,
+    }
+}
+
+// Naviter sends a longer idXXXXYYYYYY
+// XXXX to binary-> STttttaaaaaarrrr
+// S: stealth flag, T: no-tracking flag, tttt: aircraft type,
+// aaaaaa: address type, rrrr: reserved
+fn match_naviter_token(comment: &mut PositionComment, part: &str) -> Option<bool> {
+    if part.len() != 12 || !part.starts_with("id") || comment.id.is_some() {
+        return None;
+    }
+    let (Some(detail), Some(address)) = (
+        part.get(2..6)
+            .and_then(|detail| u16::from_str_radix(detail, 16).ok()),
+        part.get(6..12)
+            .and_then(|address| u32::from_str_radix(address, 16).ok()),
+    ) else {
+        return Some(false);
+    };
+    comment.id = Some(ID {
+        address_type: AddressType::from(((detail >> 4) & 0b11_1111) as u8),
+        aircraft_type: ((detail >> 10) & 0b1111) as u8,
+        is_notrack: (detail & 0x4000) != 0,
+        is_stealth: (detail & 0x8000) != 0,
+        address,
+    });
+    Some(true)
+}
+
+// The tracking byte of FANET: FNTxx
+// bit 7: online tracking, bits 4-6: aircraft type
+fn match_fanet_token(comment: &mut PositionComment, part: &str) -> Option<bool> {
+    if let Some(raw) = part.strip_prefix("FNT").filter(|raw| raw.len() == 2) {
+        if comment
+            .fanet
+            .as_ref()
+            .is_some_and(|fanet| fanet.aircraft_type.is_some())
+        {
+            return None;
+        }
+        let Ok(byte) = u8::from_str_radix(raw, 16) else {
+            return Some(false);
+        };
+        let fanet = comment.fanet.get_or_insert_with(Default::default);
+        fanet.aircraft_type = Some((byte >> 4) & 0b0111);
+        fanet.online_tracking = Some(byte & 0b1000_0000 != 0);
+        return Some(true);
+    }
+    match_fanet_grammar(comment, part)
+}
+
+comment_grammar! {
+    fn match_pilot_aware_token(PositionComment) {
+        ("sw:", "", 1..=16, identifier) => pilot_aware.software,
+        ("hw:", "", 1..=16, identifier) => pilot_aware.hardware,
+        ("RSSI:", "", 1.., signed_decimal) => pilot_aware.rssi,
+        ("ch:", "", 1..=2, digits) => pilot_aware.channel,
+    }
+}
+
+comment_grammar! {
+    fn match_adsb_grammar(AdsB) {
+        ("Sq", "", 4..=4, squawk) => squawk,
+        ("reg", "", 1..=10, identifier) => registration,
+    }
+}
+
+fn squawk(raw: &str) -> Option<String> {
+    raw.bytes()
+        .all(|byte| (b'0'..=b'7').contains(&byte))
+        .then(|| raw.to_string())
+}
+
+fn match_adsb_token(comment: &mut PositionComment, part: &str) -> Option<bool> {
+    let adsb = comment.adsb.get_or_insert_with(Default::default);
+    let on_ground = match part {
+        "gnd" => Some(true),
+        "air" => Some(false),
+        _ => None,
+    };
+    if let (Some(on_ground), None) = (on_ground, adsb.on_ground) {
+        adsb.on_ground = Some(on_ground);
+        return Some(true);
+    }
+    // fnCC:flight, the category is a letter A-D and a digit 0-7
+    if let Some((category, flight)) = part.strip_prefix("fn").and_then(|raw| raw.split_once(':')) {
+        if adsb.category.is_some() || adsb.flight.is_some() {
+            return None;
+        }
+        let valid = matches!(category.as_bytes(), [b'A'..=b'D', b'0'..=b'7'])
+            && identifier(flight).is_some_and(|flight| (1..=8).contains(&flight.len()));
+        if valid {
+            adsb.category = Some(category.to_string());
+            adsb.flight = Some(flight.to_string());
+        }
+        return Some(valid);
+    }
+    match_adsb_grammar(adsb, part)
+}
+
+comment_grammar! {
+    fn match_external_tracker_token(ExternalTracker) {
+        ("id", "", 1..=20, identifier) => id,
+        ("seq:", "", 1.., digits) => sequence,
+        ("bat:", "%", 1..=3, digits, |battery: &u8| *battery <= 100) => battery,
+    }
+}
+
+comment_grammar! {
+    fn match_flymaster_token(PositionComment) {
+        // Shorter than the hexadecimal idXXYYYYYY
+        ("id", "", 1..=7, digits) => flymaster.serial,
+        ("Bat:", "%", 1..=3, digits, |battery: &u8| *battery <= 100) => flymaster.battery,
+    }
+}
+
+fn identifier(raw: &str) -> Option<String> {
+    raw.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        .then(|| raw.to_string())
+}
+
+fn operator_id(raw: &str) -> Option<String> {
+    raw.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        .then(|| raw.to_string())
+}
+
+// The spelling of the prefixes some gateways get wrong, e.g. Gps3x5 or fl050.00, and
+// whether a value can follow them
+type ValueCheck = fn(&str) -> bool;
+
+const LENIENT_PREFIXES: [(&str, ValueCheck); 4] = [
+    ("gps", starts_with_digit),
+    ("FL", starts_with_digit),
+    ("FN:", starts_with_digit),
+    ("id", is_hex),
+];
+
+fn starts_with_digit(value: &str) -> bool {
+    value
+        .bytes()
+        .next()
+        .is_some_and(|byte| byte.is_ascii_digit())
+}
+
+fn is_hex(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+// Fixes the case of the prefixes and joins a prefix with its value in the next token
+fn normalize_prefixes(s: &str) -> String {
+    let mut parts = vec![];
+    let mut tokens = s.split_ascii_whitespace().peekable();
+    while let Some(part) = tokens.next() {
+        let normalized = LENIENT_PREFIXES.iter().find_map(|(prefix, is_value)| {
+            let head = part.get(..prefix.len())?;
+            if !head.eq_ignore_ascii_case(prefix) {
+                return None;
+            }
+            match &part[prefix.len()..] {
+                "" => tokens
+                    .next_if(|next| is_value(next))
+                    .map(|value| format!("{}{}", prefix, value)),
+                value if is_value(value) => Some(format!("{}{}", prefix, value)),
+                _ => None,
+            }
+        });
+        parts.push(normalized.unwrap_or_else(|| part.to_string()));
+    }
+    parts.join(" ")
+}
+
+impl PositionComment {
+    pub fn parse_lenient(s: &str) -> PositionComment {
+        normalize_prefixes(s).parse().unwrap()
+    }
+
+    // Like parse(), but low confidence matches are left unparsed
+    pub fn parse_strict(s: &str) -> PositionComment {
+        parse_comment(s, true)
+    }
+
+    fn clear_field(&mut self, field: &str) {
+        match field {
+            "software_version" => self.software_version = None,
+            "hardware_version" => self.hardware_version = None,
+            _ => self.original_address = None,
+        }
+    }
+}
+
+// The fields of the single letter tokens of the grammar
+fn heuristic_field(part: &str) -> Option<&'static str> {
+    match part.as_bytes().first() {
+        Some(b's') => Some("software_version"),
+        Some(b'h') => Some("hardware_version"),
+        Some(b'r') => Some("original_address"),
+        _ => None,
+    }
+}
+
 impl FromStr for PositionComment {
     type Err = Infallible;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut position_comment = PositionComment {
-            ..Default::default()
-        };
-        let mut unparsed: Vec<_> = vec![];
-        for (idx, part) in s.split_ascii_whitespace().enumerate() {
-            // The first part can be course + speed + altitude: ccc/sss/A=aaaaaa
-            // ccc: course in degrees 0-360
-            // sss: speed in km/h
-            // aaaaaa: altitude in feet
-            if idx == 0 && part.len() == 16 && position_comment.course.is_none() {
-                let subparts = part.split('/').collect::<Vec<_>>();
-                let course = subparts[0].parse::<u16>().ok();
-                let speed = subparts[1].parse::<u16>().ok();
-                let altitude = if &subparts[2][0..2] == "A=" {
-                    subparts[2][2..].parse::<u32>().ok()
-                } else {
-                    None
-                };
-                if course.is_some()
-                    && course.unwrap() <= 360
-                    && speed.is_some()
-                    && altitude.is_some()
+        Ok(parse_comment(s, false))
+    }
+}
+
+fn parse_comment(s: &str, strict: bool) -> PositionComment {
+    let mut position_comment = PositionComment {
+        ..Default::default()
+    };
+    let mut unparsed: Vec<_> = vec![];
+    let mut heuristic = vec![];
+    for (idx, part) in s.split_ascii_whitespace().enumerate() {
+        // The first part can be course + speed + altitude: ccc/sss/A=aaaaaa
+        // ccc: course in degrees 0-360
+        // sss: speed in km/h
+        // aaaaaa: altitude in feet
+        if idx == 0 && part.len() == 16 && position_comment.course.is_none() {
+            let mut cursor = FieldCursor::new(part);
+            let course = cursor.take_until("/").and_then(parse_digits::<u16>);
+            let speed = cursor.take_until("/A=").and_then(parse_digits::<u16>);
+            let altitude = parse_digits::<u32>(cursor.rest());
+            if course.is_some() && course.unwrap() <= 360 && speed.is_some() && altitude.is_some() {
+                position_comment.course = course;
+                position_comment.speed = speed;
+                position_comment.altitude = altitude;
+            } else {
+                unparsed.push(part);
+            }
+        // ... or an APRS DF report: ccc/sss/bbb/nrq
+        // bbb: bearing in degrees 1-360
+        // n: number of hits, r: range 2^r in miles, q: quality
+        } else if idx == 0 && part.len() == 15 && position_comment.course.is_none() {
+            let mut cursor = FieldCursor::new(part);
+            let mut number = || cursor.take_until("/").and_then(parse_digits::<u16>);
+            let (course, speed, bearing) = (number(), number(), number());
+            let nrq = Some(cursor.rest())
+                .filter(|nrq| nrq.len() == 3 && nrq.bytes().all(|byte| byte.is_ascii_digit()))
+                .map(|nrq| nrq.bytes().map(|byte| byte - b'0').collect::<Vec<_>>());
+            match (course, speed, bearing, nrq) {
+                (Some(course), Some(speed), Some(bearing), Some(nrq))
+                    if course <= 360 && (1..=360).contains(&bearing) =>
                 {
-                    position_comment.course = course;
-                    position_comment.speed = speed;
-                    position_comment.altitude = altitude;
-                } else {
-                    unparsed.push(part);
-                }
-            // ... or just the altitude: /A=aaaaaa
-            // aaaaaa: altitude in feet
-            } else if idx == 0
-                && part.len() == 9
-                && &part[0..3] == "/A="
-                && position_comment.altitude.is_none()
-            {
-                match part[3..].parse::<u32>().ok() {
-                    Some(altitude) => position_comment.altitude = Some(altitude),
-                    None => unparsed.push(part),
-                }
-            // The second part can be the additional precision: !Wab!
-            // a: additional latitude precision
-            // b: additional longitude precision
-            } else if idx == 1
-                && part.len() == 5
-                && &part[0..2] == "!W"
-                && &part[4..] == "!"
-                && position_comment.additional_precision.is_none()
-            {
-                let add_lat = part[2..3].parse::<u8>().ok();
-                let add_lon = part[3..4].parse::<u8>().ok();
-                match (add_lat, add_lon) {
-                    (Some(add_lat), Some(add_lon)) => {
-                        position_comment.additional_precision = Some(AdditionalPrecision {
-                            lat: add_lat,
-                            lon: add_lon,
-                        })
-                    }
-                    _ => unparsed.push(part),
-                }
-            // idXXYYYYYY is for the ID
-            // YYYYYY: 24 bit address in hex digits
-            // XX in hex digits encodes stealth mode, no-tracking flag and address type
-            // XX to binary-> STttttaa
-            // S: stealth flag
-            // T: no-tracking flag
-            // tttt: aircraft type
-            // aa: address type
-            } else if part.len() == 10 && &part[0..2] == "id" && position_comment.id.is_none() {
-                if let (Some(detail), Some(address)) = (
-                    u8::from_str_radix(&part[2..4], 16).ok(),
-                    u32::from_str_radix(&part[4..10], 16).ok(),
-                ) {
-                    let address_type = detail & 0b0000_0011;
-                    let aircraft_type = (detail & 0b0011_1100) >> 2;
-                    let is_notrack = (detail & 0b0100_0000) != 0;
-                    let is_stealth = (detail & 0b1000_0000) != 0;
-                    position_comment.id = Some(ID {
-                        address_type,
-                        aircraft_type,
-                        is_notrack,
-                        is_stealth,
-                        address,
+                    position_comment.course = Some(course);
+                    position_comment.speed = Some(speed);
+                    position_comment.bearing_report = Some(BearingReport {
+                        bearing,
+                        hits: nrq[0],
+                        range: 1 << nrq[1],
+                        quality: nrq[2],
+                        target: None,
                     });
-                } else {
-                    unparsed.push(part);
                 }
-            } else if let Some((value, unit)) = split_value_unit(part) {
-                if unit == "fpm" && position_comment.climb_rate.is_none() {
+                _ => unparsed.push(part),
+            }
+        // ... or just the altitude: /A=aaaaaa
+        // aaaaaa: altitude in feet
+        } else if idx == 0
+            && part.len() == 9
+            && part.starts_with("/A=")
+            && position_comment.altitude.is_none()
+        {
+            match parse_digits::<u32>(&part[3..]) {
+                Some(altitude) => position_comment.altitude = Some(altitude),
+                None => unparsed.push(part),
+            }
+        // The second part can be the additional precision: !Wab!
+        // a: additional latitude precision
+        // b: additional longitude precision
+        } else if idx == 1
+            && part.len() == 5
+            && part.starts_with("!W")
+            && part.ends_with('!')
+            && position_comment.additional_precision.is_none()
+        {
+            let add_lat = part.get(2..3).and_then(|lat| lat.parse::<u8>().ok());
+            let add_lon = part.get(3..4).and_then(|lon| lon.parse::<u8>().ok());
+            match (add_lat, add_lon) {
+                (Some(add_lat), Some(add_lon)) => {
+                    position_comment.additional_precision = Some(AdditionalPrecision {
+                        lat: add_lat,
+                        lon: add_lon,
+                    })
+                }
+                _ => unparsed.push(part),
+            }
+        // idXXYYYYYY is for the ID
+        // YYYYYY: 24 bit address in hex digits
+        // XX in hex digits encodes stealth mode, no-tracking flag and address type
+        // XX to binary-> STttttaa
+        // S: stealth flag
+        // T: no-tracking flag
+        // tttt: aircraft type
+        // aa: address type
+        } else if part.len() == 10 && part.starts_with("id") && position_comment.id.is_none() {
+            if let (Some(detail), Some(address)) = (
+                part.get(2..4)
+                    .and_then(|detail| u8::from_str_radix(detail, 16).ok()),
+                part.get(4..10)
+                    .and_then(|address| u32::from_str_radix(address, 16).ok()),
+            ) {
+                let address_type = AddressType::from(detail & 0b0000_0011);
+                let aircraft_type = (detail & 0b0011_1100) >> 2;
+                let is_notrack = (detail & 0b0100_0000) != 0;
+                let is_stealth = (detail & 0b1000_0000) != 0;
+                position_comment.id = Some(ID {
+                    address_type,
+                    aircraft_type,
+                    is_notrack,
+                    is_stealth,
+                    address,
+                });
+            } else {
+                unparsed.push(part);
+            }
+        } else if let Some((value, unit)) = split_value_unit(part) {
+            match Unit::from_suffix(unit) {
+                Some(Unit::FeetPerMinute) if position_comment.climb_rate.is_none() => {
                     position_comment.climb_rate = value.parse::<i16>().ok();
-                } else if unit == "rot" && position_comment.turn_rate.is_none() {
+                }
+                Some(Unit::Rotation) if position_comment.turn_rate.is_none() => {
                     position_comment.turn_rate = value.parse::<f32>().ok();
-                } else if unit == "dB" && position_comment.signal_quality.is_none() {
-                    position_comment.signal_quality = value.parse::<f32>().ok();
-                } else if unit == "kHz" && position_comment.frequency_offset.is_none() {
-                    position_comment.frequency_offset = value.parse::<f32>().ok();
-                } else if unit == "e" && position_comment.error.is_none() {
-                    position_comment.error = value.parse::<u8>().ok();
-                } else if unit == "dBm" && position_comment.signal_power.is_none() {
-                    position_comment.signal_power = value.parse::<f32>().ok();
-                } else {
-                    unparsed.push(part);
                 }
-            // Gps precision: gpsAxB
-            // A: integer
-            // B: integer
-            } else if part.len() >= 6
-                && &part[0..3] == "gps"
-                && position_comment.gps_quality.is_none()
-            {
-                if let Some((first, second)) = part[3..].split_once('x') {
-                    if first.parse::<u8>().is_ok() && second.parse::<u8>().is_ok() {
-                        position_comment.gps_quality = Some(part[3..].to_string());
-                    } else {
-                        unparsed.push(part);
-                    }
-                } else {
-                    unparsed.push(part);
+                Some(Unit::Decibel) if position_comment.signal_quality.is_none() => {
+                    position_comment.signal_quality = value.parse::<f32>().ok();
                 }
-            // Flight level: FLxx.yy
-            // xx.yy: float value for flight level
-            } else if part.len() >= 3
-                && &part[0..2] == "FL"
-                && position_comment.flight_level.is_none()
-            {
-                if let Ok(flight_level) = part[2..].parse::<f32>() {
-                    position_comment.flight_level = Some(flight_level);
-                } else {
-                    unparsed.push(part);
+                Some(Unit::Kilohertz) if position_comment.frequency_offset.is_none() => {
+                    position_comment.frequency_offset = value.parse::<f32>().ok();
                 }
-            // Software version: sXX.YY
-            // XX.YY: float value for software version
-            } else if part.len() >= 2
-                && &part[0..1] == "s"
-                && position_comment.software_version.is_none()
-            {
-                if let Ok(software_version) = part[1..].parse::<f32>() {
-                    position_comment.software_version = Some(software_version);
-                } else {
-                    unparsed.push(part);
+                Some(Unit::Errors) if position_comment.error.is_none() => {
+                    position_comment.error = value.parse::<u8>().ok();
                 }
-            // Hardware version: hXX
-            // XX: hexadecimal value for hardware version
-            } else if part.len() == 3
-                && &part[0..1] == "h"
-                && position_comment.hardware_version.is_none()
-            {
-                if part[1..3].chars().all(|c| c.is_ascii_hexdigit()) {
-                    position_comment.hardware_version = u8::from_str_radix(&part[1..3], 16).ok();
-                } else {
-                    unparsed.push(part);
+                Some(Unit::DecibelMilliwatt) if position_comment.signal_power.is_none() => {
+                    position_comment.signal_power = value.parse::<f32>().ok();
                 }
-            // Original address: rXXXXXX
-            // XXXXXX: hex digits for 24 bit address
-            } else if part.len() == 7
-                && &part[0..1] == "r"
-                && position_comment.original_address.is_none()
-            {
-                if part[1..7].chars().all(|c| c.is_ascii_hexdigit()) {
-                    position_comment.original_address = u32::from_str_radix(&part[1..7], 16).ok();
-                } else {
-                    unparsed.push(part);
+                _ => unparsed.push(part),
+            }
+        // Gps precision: gpsAxB[+CCC...]
+        // A: integer
+        // B: integer
+        // CCC: optional constellations, e.g. GAL, GLO, BDS
+        } else if part.len() >= 6
+            && part.starts_with("gps")
+            && position_comment.gps_quality.is_none()
+        {
+            let mut subparts = part[3..].split('+');
+            let precision = subparts.next().unwrap_or_default();
+            let constellations = subparts
+                .map(|constellation| {
+                    (constellation.len() == 3
+                        && constellation.bytes().all(|byte| byte.is_ascii_uppercase()))
+                    .then(|| constellation.to_string())
+                })
+                .collect::<Option<Vec<_>>>();
+            match (precision.split_once('x'), constellations) {
+                (Some((first, second)), Some(constellations))
+                    if first.parse::<u8>().is_ok() && second.parse::<u8>().is_ok() =>
+                {
+                    position_comment.gps_quality = Some(precision.to_string());
+                    position_comment.gps_constellations =
+                        (!constellations.is_empty()).then_some(constellations);
                 }
-            } else {
+                _ => unparsed.push(part),
+            }
+        // The simple tokens of the grammar table below
+        } else if let Some(parsed) = match_position_token(&mut position_comment, part) {
+            if !parsed {
                 unparsed.push(part);
+            } else if let Some(field) = heuristic_field(part) {
+                heuristic.push((field, part, unparsed.len()));
+            }
+        // Derived course and speed: D:ccc/sss
+        } else if part.len() == 9
+            && part.starts_with("D:")
+            && position_comment.derived_motion.is_none()
+        {
+            let course = part.get(2..5).and_then(parse_digits::<u16>);
+            let speed = part.get(6..).and_then(parse_digits::<u16>);
+            match (course, speed) {
+                (Some(course), Some(speed)) if part.as_bytes()[5] == b'/' && course <= 360 => {
+                    position_comment.derived_motion = Some(DerivedMotion { course, speed });
+                }
+                _ => unparsed.push(part),
+            }
+        // Target of a DF report: TGT:callsign
+        } else if part.len() > 4
+            && part.starts_with("TGT:")
+            && position_comment
+                .bearing_report
+                .as_ref()
+                .is_some_and(|bearing_report| bearing_report.target.is_none())
+        {
+            if let Some(bearing_report) = position_comment.bearing_report.as_mut() {
+                bearing_report.target = Some(part[4..].to_string());
             }
+        } else {
+            unparsed.push(part);
         }
-        position_comment.unparsed = if !unparsed.is_empty() {
-            Some(unparsed.join(" "))
+    }
+    // The single letter tokens are only trusted in beacons of devices with an id
+    if position_comment.id.is_none() && !heuristic.is_empty() {
+        if strict {
+            // Back to their place among the unparsed tokens, from the last one so the
+            // places of the others don't move
+            for (field, part, idx) in heuristic.into_iter().rev() {
+                position_comment.clear_field(field);
+                unparsed.insert(idx, part);
+            }
         } else {
-            None
-        };
+            position_comment.low_confidence_fields = Some(
+                heuristic
+                    .iter()
+                    .map(|(field, _, _)| field.to_string())
+                    .collect(),
+            );
+        }
+    }
+    position_comment.unparsed = if !unparsed.is_empty() {
+        Some(unparsed.join(" "))
+    } else {
+        None
+    };
+
+    crate::profiling::record(
+        "position",
+        &position_comment,
+        position_comment.unparsed.as_deref(),
+    );
+    position_comment
+}
+
+impl PositionComment {
+    // Parses the FANET tokens of OGNFNT beacons from the unparsed rest,
+    // address is the one of the callsign
+    pub(crate) fn parse_fanet_tokens(&mut self, address: Option<u32>) {
+        self.reparse_unparsed(match_fanet_token);
+        if let Some(address) = address {
+            self.fanet.get_or_insert_with(Default::default).manufacturer =
+                Some((address >> 16) as u8);
+        }
+    }
+
+    // Parses the longer id of OGNAVI beacons from the unparsed rest
+    pub(crate) fn parse_naviter_tokens(&mut self) {
+        self.reparse_unparsed(match_naviter_token);
+    }
+
+    // Parses the PilotAware tokens of OGPAW beacons from the unparsed rest
+    pub(crate) fn parse_pilot_aware_tokens(&mut self) {
+        self.reparse_unparsed(match_pilot_aware_token);
+    }
 
-        Ok(position_comment)
+    // Parses the ADS-B tokens of OGADSB and OGNSKY beacons from the unparsed rest
+    pub(crate) fn parse_adsb_tokens(&mut self) {
+        self.reparse_unparsed(match_adsb_token);
+        if self.adsb.as_ref() == Some(&AdsB::default()) {
+            self.adsb = None;
+        }
+    }
+
+    // Parses the FlyMaster tokens of OGFLYM beacons from the unparsed rest
+    pub(crate) fn parse_flymaster_tokens(&mut self) {
+        self.reparse_unparsed(match_flymaster_token);
+    }
+
+    // Parses the tokens of the tracker feeds from the unparsed rest, the remaining
+    // free text is moved to the tracker too
+    pub(crate) fn parse_external_tracker_tokens(&mut self) {
+        let mut tracker = ExternalTracker::default();
+        self.reparse_unparsed(|_, part| match_external_tracker_token(&mut tracker, part));
+        tracker.text = self.unparsed.take();
+        self.external_tracker = Some(tracker).filter(|tracker| *tracker != Default::default());
+    }
+
+    // Source specific tokens are only known after the generic parsing
+    fn reparse_unparsed(&mut self, mut matcher: impl FnMut(&mut Self, &str) -> Option<bool>) {
+        let Some(unparsed) = self.unparsed.take() else {
+            return;
+        };
+        let rest = unparsed
+            .split_ascii_whitespace()
+            .filter(|part| matcher(self, part) != Some(true))
+            .collect::<Vec<_>>();
+        self.unparsed = (!rest.is_empty()).then(|| rest.join(" "));
     }
 }
 
@@ -229,7 +794,7 @@ fn test_flr() {
             altitude: Some(3399),
             additional_precision: Some(AdditionalPrecision { lat: 0, lon: 3 }),
             id: Some(ID {
-                address_type: 2,
+                address_type: AddressType::Flarm,
                 aircraft_type: 1,
                 is_stealth: false,
                 is_notrack: false,
@@ -263,7 +828,7 @@ fn test_trk() {
             altitude: Some(126433),
             additional_precision: Some(AdditionalPrecision { lat: 0, lon: 5 }),
             id: Some(ID {
-                address_type: 1,
+                address_type: AddressType::Icao,
                 aircraft_type: 5,
                 is_stealth: false,
                 is_notrack: false,
@@ -280,6 +845,18 @@ fn test_trk() {
             software_version: None,
             hardware_version: None,
             original_address: None,
+            uas: None,
+            balloon: None,
+            fanet: None,
+            pilot_aware: None,
+            adsb: None,
+            external_tracker: None,
+            flymaster: None,
+            low_confidence_fields: None,
+            frame_counter: None,
+            gps_constellations: None,
+            derived_motion: None,
+            bearing_report: None,
             unparsed: None
         }
     );
@@ -296,7 +873,7 @@ fn test_trk2() {
             altitude: Some(2280),
             additional_precision: Some(AdditionalPrecision { lat: 5, lon: 9 }),
             id: Some(ID {
-                address_type: 3,
+                address_type: AddressType::Ogn,
                 aircraft_type: 1,
                 is_stealth: false,
                 is_notrack: false,
@@ -326,7 +903,7 @@ fn test_trk2_different_order() {
             altitude: Some(2280),
             additional_precision: Some(AdditionalPrecision { lat: 5, lon: 9 }),
             id: Some(ID {
-                address_type: 3,
+                address_type: AddressType::Ogn,
                 aircraft_type: 1,
                 is_stealth: false,
                 is_notrack: false,
@@ -353,3 +930,300 @@ fn test_bad_gps() {
     assert_eq!(result.gps_quality.is_some(), false);
     assert_eq!(result.unparsed, Some("gps2xFLRD0".to_string()));
 }
+
+// Free text which merely resembles OGN tokens
+#[cfg(test)]
+const FREE_TEXT_COMMENTS: [&str; 14] = [
+    "Hello/A=001000",
+    "Hello world",
+    "Fly safe 73 de DL1ABC",
+    "inf/nan/A=000100",
+    "+12/+34/A=+00100",
+    "/A=+00100",
+    "/A=-00100",
+    "/A=1e+0100",
+    "FLinf FLnan FL1e5 FL+1",
+    "sNaN sinfinity s1e5 s+1 s.5",
+    "had hab hee",
+    "Ride gps1x RAM FL s h r",
+    "e-mail ideas idiotic",
+    "refaced rDDFAAG",
+];
+
+#[test]
+fn test_free_text() {
+    for comment in FREE_TEXT_COMMENTS {
+        let result = comment.parse::<PositionComment>().unwrap();
+        assert_eq!(
+            result,
+            PositionComment {
+                unparsed: Some(comment.to_string()),
+                ..Default::default()
+            },
+            "{}",
+            comment
+        );
+    }
+}
+
+#[test]
+fn test_uas() {
+    let result = "000/000/A=000394 id353DE5F0 +020fpm Op:FIN87astrdge12k8 UA:2 UA:3 Op:x!"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(
+        result.uas,
+        Some(Uas {
+            operator_id: Some("FIN87astrdge12k8".into()),
+            ua_type: Some(2),
+        })
+    );
+    assert_eq!(result.unparsed, Some("UA:3 Op:x!".into()));
+
+    let result = "UA:16 Op:".parse::<PositionComment>().unwrap();
+    assert_eq!(result.uas, None);
+    assert_eq!(result.unparsed, Some("UA:16 Op:".into()));
+}
+
+#[test]
+fn test_balloon() {
+    let result = "/A=098425 Burst=101706 Bat:3.31V FN:1234 FN:1235 Bat:V"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.altitude, Some(98425));
+    assert_eq!(
+        result.balloon,
+        Some(Balloon {
+            burst_altitude: Some(101706),
+            battery_voltage: Some(3.31),
+        })
+    );
+    assert_eq!(result.frame_counter, Some(1234));
+    assert_eq!(result.unparsed, Some("FN:1235 Bat:V".into()));
+}
+
+#[test]
+fn test_gps_constellations() {
+    let result = "gps3x5+GAL".parse::<PositionComment>().unwrap();
+    assert_eq!(result.gps_quality, Some("3x5".into()));
+    assert_eq!(result.gps_constellations, Some(vec!["GAL".into()]));
+    assert_eq!(result.unparsed, None);
+
+    let result = "gps3x5+GAL+GLO".parse::<PositionComment>().unwrap();
+    assert_eq!(
+        result.gps_constellations,
+        Some(vec!["GAL".into(), "GLO".into()])
+    );
+
+    let result = "gps3x5".parse::<PositionComment>().unwrap();
+    assert_eq!(result.gps_constellations, None);
+
+    for token in ["gps3x5+", "gps3x5+gal", "gps3x5+GALILEO", "gps3x5+GAL+"] {
+        let result = token.parse::<PositionComment>().unwrap();
+        assert_eq!(result.gps_quality, None);
+        assert_eq!(result.unparsed, Some(token.into()));
+    }
+}
+
+#[test]
+fn test_derived_motion() {
+    let result = "086/007/A=000607 D:090/011"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.course, Some(86));
+    assert_eq!(result.speed, Some(7));
+    assert_eq!(
+        result.derived_motion,
+        Some(DerivedMotion {
+            course: 90,
+            speed: 11
+        })
+    );
+    assert_eq!(result.unparsed, None);
+
+    let result = "D:361/011 D:09/0110".parse::<PositionComment>().unwrap();
+    assert_eq!(result.derived_motion, None);
+    assert_eq!(result.unparsed, Some("D:361/011 D:09/0110".into()));
+}
+
+#[test]
+fn test_bearing_report() {
+    let result = "088/036/270/729 TGT:FLRDDFAA3"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.course, Some(88));
+    assert_eq!(result.speed, Some(36));
+    assert_eq!(
+        result.bearing_report,
+        Some(BearingReport {
+            bearing: 270,
+            hits: 7,
+            range: 4,
+            quality: 9,
+            target: Some("FLRDDFAA3".into()),
+        })
+    );
+    assert_eq!(result.unparsed, None);
+
+    // Bearing 0 is invalid, and without a report there is no target
+    let result = "088/036/000/729 TGT:FLRDDFAA3"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.bearing_report, None);
+    assert_eq!(
+        result.unparsed,
+        Some("088/036/000/729 TGT:FLRDDFAA3".into())
+    );
+}
+
+#[test]
+fn test_non_ascii() {
+    // Multi-byte characters must not be sliced
+    let result = "°086/007/A=0006 !W°2! id°0ADDFA FL°12.3 -°75.0dBm s°7 h°3 r°DDFA3 21°C"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(
+        result.unparsed,
+        Some("°086/007/A=0006 !W°2! id°0ADDFA FL°12.3 -°75.0dBm s°7 h°3 r°DDFA3 21°C".into())
+    );
+    let result = "-019fpm 5.5dB µ".parse::<PositionComment>().unwrap();
+    assert_eq!(result.climb_rate, Some(-19));
+    assert_eq!(result.signal_quality, Some(5.5));
+}
+
+#[test]
+fn test_lenient_prefixes() {
+    // Seen from gateways with hand-written encoders
+    let comment = "id1E3E5906 +000fpm Gps3x5 fl050.00 FN: 17 ID 06DDFAA3 Flarm";
+    let strict = comment.parse::<PositionComment>().unwrap();
+    assert_eq!(strict.gps_quality, None);
+    assert_eq!(strict.flight_level, None);
+
+    let result = PositionComment::parse_lenient(comment);
+    assert_eq!(result.gps_quality, Some("3x5".into()));
+    assert_eq!(result.flight_level, Some(50.0));
+    assert_eq!(result.frame_counter, Some(17));
+    assert_eq!(result.id.as_ref().unwrap().address, 0x3E5906);
+    // The second id is still unparsed, words are kept as they are
+    assert_eq!(result.unparsed, Some("id06DDFAA3 Flarm".into()));
+}
+
+#[test]
+fn test_address_type() {
+    let id = "id06DDFAA3".parse::<PositionComment>().unwrap().id.unwrap();
+    assert_eq!(id.address_type, AddressType::Flarm);
+    assert_eq!(id.device_id(), "FLRDDFAA3");
+    assert_eq!(serde_json::to_value(&id).unwrap()["address_type"], 2);
+    assert_eq!(AddressType::from(0).to_string(), "RND");
+    assert_eq!(AddressType::from(3).to_string(), "OGN");
+    assert_eq!(u8::from(AddressType::from(42)), 42);
+}
+
+#[test]
+fn test_icao_country() {
+    let result = "id053D17F2".parse::<PositionComment>().unwrap();
+    assert_eq!(result.id.unwrap().icao_country(), Some("Germany"));
+    let result = "id063D17F2".parse::<PositionComment>().unwrap();
+    assert_eq!(result.id.unwrap().icao_country(), None);
+}
+
+#[test]
+fn test_low_confidence() {
+    let result = "id06DDFAA3 s7.07 h41".parse::<PositionComment>().unwrap();
+    assert_eq!(result.software_version, Some(7.07));
+    assert_eq!(result.low_confidence_fields, None);
+
+    // Free text of a tracker without id
+    let comment = "Pilot s1.5 rAAAAAA";
+    let result = comment.parse::<PositionComment>().unwrap();
+    assert_eq!(result.software_version, Some(1.5));
+    assert_eq!(
+        result.low_confidence_fields,
+        Some(vec!["software_version".into(), "original_address".into()])
+    );
+    let result = PositionComment::parse_strict(comment);
+    assert_eq!(result.software_version, None);
+    assert_eq!(result.original_address, None);
+    assert_eq!(result.low_confidence_fields, None);
+    assert_eq!(result.unparsed, Some("Pilot s1.5 rAAAAAA".into()));
+    assert_eq!(
+        PositionComment::parse_strict("id06DDFAA3 s7.07").software_version,
+        Some(7.07)
+    );
+    assert_eq!(
+        PositionComment::parse_strict("s1.5 Pilot h41 rAAAAAA Name").unparsed,
+        Some("s1.5 Pilot h41 rAAAAAA Name".into())
+    );
+}
+
+#[test]
+fn test_naviter_id() {
+    let mut result = "id8450049C6B +198fpm +0.0rot"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.id, None);
+    assert_eq!(result.unparsed, Some("id8450049C6B".into()));
+    result.parse_naviter_tokens();
+    assert_eq!(
+        result.id,
+        Some(ID {
+            address_type: AddressType::Other(5),
+            aircraft_type: 1,
+            is_stealth: true,
+            is_notrack: false,
+            address: 0x049C6B,
+        })
+    );
+    assert_eq!(result.unparsed, None);
+    assert_eq!(result.id.unwrap().device_id(), "NAV049C6B");
+
+    let mut result = "id0450049CXB".parse::<PositionComment>().unwrap();
+    result.parse_naviter_tokens();
+    assert_eq!(result.id, None);
+    assert_eq!(result.unparsed, Some("id0450049CXB".into()));
+}
+
+#[test]
+fn test_fanet_tokens() {
+    let mut result = "id1E1103CE +03fpm FNT11 FW:2023-03 11.0dB FNTxx"
+        .parse::<PositionComment>()
+        .unwrap();
+    assert_eq!(result.unparsed, Some("FNT11 FW:2023-03 FNTxx".into()));
+    result.parse_fanet_tokens(Some(0x1103CE));
+    assert_eq!(
+        result.fanet,
+        Some(Fanet {
+            aircraft_type: Some(1),
+            online_tracking: Some(false),
+            manufacturer: Some(0x11),
+            firmware: Some("2023-03".into()),
+        })
+    );
+    assert_eq!(result.climb_rate, Some(3));
+    assert_eq!(result.unparsed, Some("FNTxx".into()));
+
+    let mut result = "FNTC0".parse::<PositionComment>().unwrap();
+    result.parse_fanet_tokens(None);
+    let fanet = result.fanet.unwrap();
+    assert_eq!(fanet.aircraft_type, Some(4));
+    assert_eq!(fanet.online_tracking, Some(true));
+    assert_eq!(fanet.manufacturer, None);
+    assert_eq!(result.unparsed, None);
+}
+
+#[test]
+fn test_deserialize() {
+    let result =
+        "255/045/A=003399 !W03! id2A3D17F2 -178fpm +0.1rot 14.5dB 1e -4.3kHz gps2x3 s7.07 h41"
+            .parse::<PositionComment>()
+            .unwrap();
+    let json = serde_json::to_string(&result).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PositionComment>(&json).unwrap(),
+        result
+    );
+    assert_eq!(
+        serde_json::from_str::<AddressType>("7").unwrap(),
+        AddressType::Other(7)
+    );
+}
@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+
+use crate::category::{self, aircraft_type_name};
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::time::Clock;
+
+const FEET_TO_METERS: f64 = 0.3048;
+const FPM_TO_MS: f64 = 0.00508;
+
+// A one line summary of a message for humans:
+// FLR DDFAAE via LFLE: 47.123,5.456 1200m +1.2m/s glider 12.5dB
+pub struct Pretty<'a>(pub &'a Message);
+
+impl Message {
+    pub fn pretty(&self) -> Pretty<'_> {
+        Pretty(self)
+    }
+}
+
+impl Display for Pretty<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = self.0;
+        let packet = match &message.aprs_packet {
+            Ok(packet) => packet,
+            Err(_) if message.server_comment.is_some() => {
+                return write!(
+                    f,
+                    "server: {}",
+                    message.raw_string.trim_start_matches('#').trim()
+                )
+            }
+            Err(error) => return write!(f, "error: {}", error),
+        };
+        match (packet.from.device_prefix(), packet.from.device_address()) {
+            (Some(prefix), Some(address)) => write!(f, "{} {:06X}", prefix, address)?,
+            _ => write!(f, "{}", packet.from)?,
+        }
+        if let Some((_, Some(station))) = packet.q_construct() {
+            write!(f, " via {}", station)?;
+        }
+        write!(f, ":")?;
+
+        match &packet.data {
+            AprsData::Position(position) => {
+                write!(f, " {:.3},{:.3}", position.latitude, position.longitude)?
+            }
+            AprsData::Item(item) => write!(
+                f,
+                " item {} {:.3},{:.3}",
+                item.name, item.position.latitude, item.position.longitude
+            )?,
+            AprsData::Status(status) => write!(f, " status {}", status.comment.trim())?,
+            AprsData::Message(aprs_message) => write!(
+                f,
+                " message to {}: {}",
+                aprs_message.addressee, aprs_message.text
+            )?,
+            AprsData::Telemetry(_) => write!(f, " telemetry")?,
+            AprsData::Weather(_) => write!(f, " weather")?,
+            AprsData::ThirdParty(inner) => write!(f, " third party from {}", inner.from)?,
+            AprsData::Unknown => write!(f, " unknown")?,
+        }
+        if let Some(comment) = &message.position_comment {
+            if let Some(altitude) = comment.altitude {
+                write!(f, " {:.0}m", altitude as f64 * FEET_TO_METERS)?;
+            }
+            if let Some(climb_rate) = comment.climb_rate {
+                write!(f, " {:+.1}m/s", climb_rate as f64 * FPM_TO_MS)?;
+            }
+        }
+        if let Some(classification) = category::classify(message) {
+            write!(f, " {}", aircraft_type_name(classification.aircraft_type))?;
+        }
+        if let Some(signal_quality) = message
+            .position_comment
+            .as_ref()
+            .and_then(|comment| comment.signal_quality)
+        {
+            write!(f, " {:.1}dB", signal_quality)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes the pretty messages, but at most max_lines per second, so a live feed stays
+// readable. The skipped messages are counted in the first line of the next second.
+pub struct RateLimitedSink<W: Write> {
+    writer: W,
+    max_lines: usize,
+    second: i64,
+    lines: usize,
+    skipped: usize,
+}
+
+impl RateLimitedSink<io::Stdout> {
+    pub fn stdout(max_lines: usize) -> Self {
+        RateLimitedSink::new(io::stdout(), max_lines)
+    }
+}
+
+impl<W: Write> RateLimitedSink<W> {
+    pub fn new(writer: W, max_lines: usize) -> Self {
+        RateLimitedSink {
+            writer,
+            max_lines,
+            second: i64::MIN,
+            lines: 0,
+            skipped: 0,
+        }
+    }
+
+    // time: seconds since 1970-01-01, e.g. the time the message was received
+    pub fn write(&mut self, message: &Message, time: i64) -> io::Result<()> {
+        if time != self.second {
+            if self.skipped > 0 {
+                writeln!(self.writer, "... {} messages skipped", self.skipped)?;
+            }
+            self.second = time;
+            self.lines = 0;
+            self.skipped = 0;
+        }
+        if self.lines >= self.max_lines {
+            self.skipped += 1;
+            return Ok(());
+        }
+        self.lines += 1;
+        writeln!(self.writer, "{}", message.pretty())
+    }
+
+    pub fn write_now<C: Clock>(&mut self, message: &Message, clock: &C) -> io::Result<()> {
+        self.write(message, clock.now())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pretty(line: &str) -> String {
+        line.parse::<Message>().unwrap().pretty().to_string()
+    }
+
+    #[test]
+    fn test_pretty() {
+        assert_eq!(
+            pretty(
+                r"FLRDDFAAE>OGFLR,qAS,LFLE:/074548h4707.38N/00527.36E'086/007/A=003937 !W18! id06DDFAAE +236fpm 12.5dB"
+            ),
+            "FLR DDFAAE via LFLE: 47.123,5.456 1200m +1.2m/s glider 12.5dB"
+        );
+        assert_eq!(
+            pretty(r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7"),
+            "Letzi via GLIDERN2: status v0.2.7"
+        );
+        assert_eq!(
+            pretty("Letzi>OGNSDR:/0748"),
+            "error: Invalid timestamp: 0748"
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_sink() {
+        let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>085623h v0.2.7"
+            .parse::<Message>()
+            .unwrap();
+        let mut sink = RateLimitedSink::new(vec![], 2);
+        let clock = crate::time::SimulatedClock::new(100);
+        for time in [100, 100, 100, 100, 101] {
+            clock.set(time);
+            sink.write_now(&message, &clock).unwrap();
+        }
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "Letzi via GLIDERN2: status v0.2.7",
+                "Letzi via GLIDERN2: status v0.2.7",
+                "... 2 messages skipped",
+                "Letzi via GLIDERN2: status v0.2.7",
+            ]
+        );
+    }
+}
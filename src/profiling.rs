@@ -0,0 +1,94 @@
+// Counts which comment fields are found and how many tokens end up unparsed,
+// to see which dialects are worth implementing next. Only active with the "profiling" feature.
+#[cfg(feature = "profiling")]
+use std::collections::BTreeMap;
+#[cfg(feature = "profiling")]
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+#[cfg(feature = "profiling")]
+static COUNTERS: Mutex<BTreeMap<(&str, String), u64>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct TokenCounter {
+    pub dialect: String,
+    // The field name, or "unparsed"
+    pub token: String,
+    pub count: u64,
+}
+
+#[cfg(feature = "profiling")]
+pub(crate) fn record<T: Serialize>(dialect: &'static str, comment: &T, unparsed: Option<&str>) {
+    let mut counters = COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(comment) {
+        for (field, value) in fields {
+            if !value.is_null() && field != "unparsed" {
+                *counters.entry((dialect, field)).or_default() += 1;
+            }
+        }
+    }
+    let unparsed = unparsed.map_or(0, |unparsed| unparsed.split_whitespace().count());
+    if unparsed > 0 {
+        *counters
+            .entry((dialect, "unparsed".to_string()))
+            .or_default() += unparsed as u64;
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+#[inline(always)]
+pub(crate) fn record<T: Serialize>(_dialect: &'static str, _comment: &T, _unparsed: Option<&str>) {}
+
+// Sorted by dialect and token, empty without the "profiling" feature
+pub fn counters() -> Vec<TokenCounter> {
+    #[cfg(feature = "profiling")]
+    {
+        let counters = COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+        counters
+            .iter()
+            .map(|((dialect, token), count)| TokenCounter {
+                dialect: dialect.to_string(),
+                token: token.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+    #[cfg(not(feature = "profiling"))]
+    vec![]
+}
+
+pub fn reset() {
+    #[cfg(feature = "profiling")]
+    COUNTERS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+
+    fn count(dialect: &str, token: &str) -> u64 {
+        counters()
+            .into_iter()
+            .find(|counter| counter.dialect == dialect && counter.token == token)
+            .map_or(0, |counter| counter.count)
+    }
+
+    #[test]
+    fn test_counters() {
+        // Other tests parse in parallel, so we only check the increase
+        let (climb_rate, unparsed) = (
+            count("position", "climb_rate"),
+            count("position", "unparsed"),
+        );
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm foo bar"
+            .parse::<Message>()
+            .unwrap();
+        assert!(count("position", "climb_rate") > climb_rate);
+        assert!(count("position", "unparsed") >= unparsed + 2);
+        assert!(counters()
+            .iter()
+            .all(|counter| counter.token != "unparsed" || counter.count > 0));
+    }
+}
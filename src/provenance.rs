@@ -0,0 +1,130 @@
+use serde::Serialize;
+
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::path::{PathEntry, QConstruct};
+
+// Where a beacon came from, for deployments merging several feeds:
+// FLRDDFAA3>OGFLR,OGN123456*,qAS,Letzi:... received from GLIDERN3 on connection 2
+// has the relay path [OGN123456] and the gateway Letzi.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct Provenance {
+    // The APRS-IS server of the connection, as announced in its keepalives
+    pub server: Option<String>,
+    // Chosen by the application to tell its connections apart
+    pub connection_id: Option<u64>,
+    pub q_construct: Option<QConstruct>,
+    // The station following the q construct: the receiver that gated the beacon
+    // or the server a receiver is logged in to
+    pub gateway: Option<String>,
+    // The used stations before the q construct in the order they relayed the beacon,
+    // for third-party packets those of the inner path come first, then the gateway
+    // that repackaged it and the stations of the outer path
+    pub relay_path: Vec<String>,
+}
+
+impl Provenance {
+    pub fn new(message: &Message, server: Option<&str>, connection_id: Option<u64>) -> Self {
+        let mut provenance = Provenance {
+            server: server.map(str::to_string),
+            connection_id,
+            q_construct: None,
+            gateway: None,
+            relay_path: vec![],
+        };
+        let Ok(mut packet) = message.aprs_packet.as_ref() else {
+            return provenance;
+        };
+        if let Some((q_construct, gateway)) = packet.q_construct() {
+            provenance.q_construct = Some(q_construct);
+            provenance.gateway = gateway.map(|gateway| gateway.to_string());
+        }
+        let mut segments = vec![];
+        loop {
+            let mut segment = vec![];
+            let inner = match &packet.data {
+                AprsData::ThirdParty(inner) => {
+                    segment.push(packet.from.to_string());
+                    Some(inner)
+                }
+                _ => None,
+            };
+            segment.extend(
+                packet
+                    .path()
+                    .into_iter()
+                    .take_while(|entry| !matches!(entry, PathEntry::QConstruct(_)))
+                    .filter_map(|entry| match entry {
+                        PathEntry::Digipeater {
+                            callsign,
+                            used: true,
+                        } if !callsign.call.starts_with("TCP") => Some(callsign.to_string()),
+                        _ => None,
+                    }),
+            );
+            segments.push(segment);
+            match inner {
+                Some(inner) => packet = inner,
+                None => break,
+            }
+        }
+        // The innermost path comes first
+        provenance.relay_path = segments.into_iter().rev().flatten().collect();
+        provenance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance() {
+        let message = r"FLRDDFAA3>OGFLR,OGN123456*,OGNABCDEF*,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(
+            Provenance::new(&message, Some("GLIDERN3"), Some(2)),
+            Provenance {
+                server: Some("GLIDERN3".to_string()),
+                connection_id: Some(2),
+                q_construct: Some(QConstruct::Server),
+                gateway: Some("Letzi".to_string()),
+                relay_path: vec!["OGN123456".to_string(), "OGNABCDEF".to_string()],
+            }
+        );
+
+        // Only the last relay is marked
+        let message = r"FLRDDFAA3>OGFLR,OGN123456,OGNABCDEF*,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(
+            Provenance::new(&message, None, None).relay_path,
+            vec!["OGN123456".to_string(), "OGNABCDEF".to_string()]
+        );
+
+        let message = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765"
+            .parse::<Message>()
+            .unwrap();
+        let provenance = Provenance::new(&message, None, None);
+        assert_eq!(provenance.q_construct, Some(QConstruct::Client));
+        assert_eq!(provenance.gateway, Some("GLIDERN2".to_string()));
+        assert!(provenance.relay_path.is_empty());
+
+        let message = r"GATE>APRS,WIDE1-1*,qAR,N0CALL:}FLRDDFAA3>OGFLR,TCPIP,OGN123456*:/074548h4708.92N/00653.67E'086/007/A=000607"
+            .parse::<Message>()
+            .unwrap();
+        assert_eq!(
+            Provenance::new(&message, None, None).relay_path,
+            vec![
+                "OGN123456".to_string(),
+                "GATE".to_string(),
+                "WIDE1-1".to_string()
+            ]
+        );
+
+        let provenance = Provenance::new(&"invalid".parse().unwrap(), Some("GLIDERN3"), None);
+        assert_eq!(provenance.server, Some("GLIDERN3".to_string()));
+        assert_eq!(provenance.gateway, None);
+    }
+}
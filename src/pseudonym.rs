@@ -0,0 +1,160 @@
+use std::fmt::{Debug, Formatter};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::corpus::replace_addresses;
+use crate::message::Message;
+
+// Replaces the device addresses by pseudonyms derived with HMAC-SHA256 from a secret key:
+// they are stable across a dataset, so tracks stay consistent, and can't be reversed
+// without the key. The address is replaced in the callsigns of the sender and the relays
+// and in the id and r tokens of the comment.
+//
+// The pseudonyms only have the 24 bits of the address, so different devices can get the
+// same pseudonym: a dataset of n devices has about n^2 / 2^25 collisions, with 4096
+// devices there is already one collision with a probability of 40 %.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+// The key is secret, it is not printed
+impl Debug for Pseudonymizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pseudonymizer").finish_non_exhaustive()
+    }
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &[u8]) -> Self {
+        Pseudonymizer { key: key.to_vec() }
+    }
+
+    // The 24 bit pseudonym of the 24 bit address: the first 3 bytes of the MAC
+    pub fn address(&self, address: u32) -> u32 {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(format!("{:06X}", address & 0xFFFFFF).as_bytes());
+        let mac = mac.finalize().into_bytes();
+        u32::from_be_bytes([0, mac[0], mac[1], mac[2]])
+    }
+
+    // The line with the pseudonyms, lines without device address are kept
+    pub fn line(&self, line: &str) -> String {
+        let message = line.parse::<Message>().unwrap();
+        self.apply(&message).raw_string
+    }
+
+    // The message reparsed from the line with the pseudonyms, failed lines too: their raw
+    // string is in the output, the fields are taken from the line parsed with parse().
+    // The line is parsed with parse(), messages of parse_lenient(), parse_strict() or
+    // parse_with_limits() are pseudonymized with apply_with() and the same function
+    pub fn apply(&self, message: &Message) -> Message {
+        self.apply_with(message, |s| s.parse().unwrap())
+    }
+
+    pub fn apply_with<F: Fn(&str) -> Message>(&self, message: &Message, parse: F) -> Message {
+        let parsed;
+        let fields = match message.aprs_packet {
+            Ok(_) => message,
+            Err(_) => {
+                parsed = message.raw_string.parse::<Message>().unwrap();
+                &parsed
+            }
+        };
+        let line = replace_addresses(fields, |address| self.address(address));
+        if line == message.raw_string {
+            return message.clone();
+        }
+        parse(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Limits;
+
+    #[test]
+    fn test_address() {
+        // The pseudonyms of existing datasets must stay the same
+        assert_eq!(Pseudonymizer::new(b"secret").address(0xDDFAA3), 0x6A198F);
+    }
+
+    #[test]
+    fn test_pseudonymizer() {
+        let line = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 -019fpm";
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let pseudonym = format!("{:06X}", pseudonymizer.address(0xDDFAA3));
+        let message = pseudonymizer.apply(&line.parse::<Message>().unwrap());
+        assert_eq!(message.raw_string, line.replace("DDFAA3", &pseudonym));
+        assert_eq!(
+            message.position_comment.unwrap().id.unwrap().address,
+            pseudonymizer.address(0xDDFAA3)
+        );
+        assert_eq!(pseudonymizer.line(line), message.raw_string);
+        assert_ne!(Pseudonymizer::new(b"other").line(line), message.raw_string);
+        assert!(!format!("{:?}", pseudonymizer).contains("secret"));
+
+        let receiver = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765";
+        assert_eq!(pseudonymizer.line(receiver), receiver);
+    }
+
+    #[test]
+    fn test_parsed_fields() {
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let pseudonym = |address| format!("{:06X}", pseudonymizer.address(address));
+
+        // Lower case callsigns, relays and the original address, but not the free text
+        let line = r"FLRddfaa3>OGFLR,OGN123456*,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 rDDFAA3 DDFAA3";
+        let message = pseudonymizer.apply(&line.parse::<Message>().unwrap());
+        assert_eq!(
+            message.raw_string,
+            format!(
+                r"FLR{0}>OGFLR,OGN{1}*,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06{0} r{0} DDFAA3",
+                pseudonym(0xDDFAA3),
+                pseudonym(0x123456)
+            )
+        );
+        assert_eq!(
+            message.position_comment.unwrap().original_address,
+            Some(pseudonymizer.address(0xDDFAA3))
+        );
+
+        // The message is reparsed like the original one
+        let line = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 Gps3x5";
+        let message =
+            pseudonymizer.apply_with(&Message::parse_lenient(line), Message::parse_lenient);
+        assert_eq!(
+            message.position_comment.unwrap().gps_quality,
+            Some("3x5".into())
+        );
+    }
+
+    #[test]
+    fn test_failed_lines() {
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let pseudonym = format!("FLR{:06X}", pseudonymizer.address(0xDDFAA3));
+
+        let message = pseudonymizer.apply(&r"FLRDDFAA3>APRS,qAS,Letzi:/garbage".parse().unwrap());
+        assert!(message.aprs_packet.is_err());
+        assert_eq!(
+            message.raw_string,
+            format!(r"{}>APRS,qAS,Letzi:/garbage", pseudonym)
+        );
+
+        // Rejected by the limits, the error has the replaced line too
+        let limits = Limits {
+            max_tokens: 2,
+            ..Default::default()
+        };
+        let line = r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 -019fpm +0.0rot";
+        let message = pseudonymizer
+            .apply_with(&Message::parse_with_limits(line, &limits), |line| {
+                Message::parse_with_limits(line, &limits)
+            });
+        assert!(message.raw_string.starts_with(&pseudonym));
+        assert!(!format!("{:?}", message).contains("DDFAA3"));
+    }
+}
@@ -1,15 +1,37 @@
+use crate::callsign::Callsign;
 use crate::message::*;
-use aprs_parser::{AprsData, Callsign};
+use crate::output::{self, FieldNames, FutureTimestamps, OutputOptions};
+use crate::packet::{AprsData, AprsHeader};
+use crate::position::CompressedExtension;
+use crate::telemetry::TelemetryDefinition;
 use pyo3::prelude::*;
 use pyo3::types::IntoPyDict;
 use pyo3::types::PyList;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 #[pyfunction]
-pub fn parse_to_json(py: Python<'_>, o: PyObject) -> PyResult<PyObject> {
+#[pyo3(signature = (o, timestamp_format = None, unit_format = None, lossless = false, future_timestamps = None, field_names = None))]
+pub fn parse_to_json(
+    py: Python<'_>,
+    o: PyObject,
+    timestamp_format: Option<&str>,
+    unit_format: Option<&str>,
+    lossless: bool,
+    future_timestamps: Option<&str>,
+    field_names: Option<&str>,
+) -> PyResult<PyObject> {
+    let options = OutputOptions {
+        timestamp_format: parse_option(timestamp_format)?,
+        unit_format: parse_option(unit_format)?,
+        lossless,
+        future_timestamps: parse_option::<FutureTimestamps>(future_timestamps)?,
+        field_names: parse_option::<FieldNames>(field_names)?,
+        ..Default::default()
+    };
     if let Ok(s) = o.extract::<&str>(py) {
         let message = s.parse::<Message>().unwrap();
-        let result = serde_json::to_string(&message).unwrap();
+        let result = output::to_string(&message, &options);
         Ok(result.into_py(py))
     } else {
         Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
@@ -18,6 +40,66 @@ pub fn parse_to_json(py: Python<'_>, o: PyObject) -> PyResult<PyObject> {
     }
 }
 
+#[pyfunction]
+pub fn inspect(py: Python<'_>, o: PyObject) -> PyResult<PyObject> {
+    if let Ok(s) = o.extract::<&str>(py) {
+        let report = serde_json::to_string(&crate::inspect(s)).unwrap();
+        let json = py.import_bound("json")?;
+        Ok(json.call_method1("loads", (report,))?.into_py(py))
+    } else {
+        Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "Expected a string",
+        ))
+    }
+}
+
+// The optional features the module was built with, e.g. `maturin build --features mmap`
+// adds read_archive()
+#[pyfunction]
+pub fn features() -> Vec<&'static str> {
+    [
+        ("mmap", cfg!(feature = "mmap")),
+        ("chrono-tz", cfg!(feature = "chrono-tz")),
+        ("profiling", cfg!(feature = "profiling")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+// The parsed lines of a raw log in the time window [start, end), without a window all lines
+#[cfg(feature = "mmap")]
+#[pyfunction]
+#[pyo3(signature = (path, reference_time, start = None, end = None))]
+pub fn read_archive(
+    py: Python<'_>,
+    path: &str,
+    reference_time: i64,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> PyResult<PyObject> {
+    let archive = crate::archive::ArchiveReader::open(path, reference_time)?;
+    let (start, end) = (
+        archive.seek(start.unwrap_or(i64::MIN)),
+        archive.seek(end.unwrap_or(i64::MAX)),
+    );
+    let results = (start..end)
+        .filter_map(|idx| archive.line(idx))
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_str(py, line))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(results.into_py(py))
+}
+
+fn parse_option<T: FromStr<Err = String> + Default>(value: Option<&str>) -> PyResult<T> {
+    value
+        .map(str::parse::<T>)
+        .transpose()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+        .map(Option::unwrap_or_default)
+}
+
 #[pyfunction]
 pub fn parse(py: Python<'_>, o: PyObject) -> PyResult<PyObject> {
     if let Ok(s) = o.extract::<&str>(py) {
@@ -45,6 +127,9 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
 
     let message = s.parse::<Message>().unwrap();
     result.insert("raw_message".to_string(), message.raw_string.into_py(py));
+    if let Some(flavor) = message.flavor {
+        result.insert("flavor".to_string(), format!("{:?}", flavor).into_py(py));
+    }
 
     if let Ok(aprs_packet) = message.aprs_packet {
         result.insert("from".to_string(), aprs_packet.from.to_string().into_py(py));
@@ -73,6 +158,50 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
                     position.symbol_table.into_py(py),
                 );
                 aprs_data.insert("symbol_code".to_string(), position.symbol_code.into_py(py));
+                if let Some(compression) = &position.compression {
+                    let mut compressed: HashMap<String, PyObject> = HashMap::new();
+                    compressed.insert(
+                        "compression_type".to_string(),
+                        compression.compression_type.into_py(py),
+                    );
+                    match compression.extension {
+                        Some(CompressedExtension::CourseSpeed { course, speed }) => {
+                            compressed.insert("course".to_string(), course.into_py(py));
+                            compressed.insert("speed".to_string(), speed.into_py(py));
+                        }
+                        Some(CompressedExtension::Range(range)) => {
+                            compressed.insert("range".to_string(), range.into_py(py));
+                        }
+                        Some(CompressedExtension::Altitude(altitude)) => {
+                            compressed.insert("altitude".to_string(), altitude.into_py(py));
+                        }
+                        None => {}
+                    }
+                    aprs_data.insert(
+                        "compression".to_string(),
+                        compressed.into_py_dict_bound(py).into(),
+                    );
+                }
+                if let Some(mic_e) = &position.mic_e {
+                    aprs_data.insert(
+                        "mic_e".to_string(),
+                        vec![
+                            ("course".to_string(), mic_e.course.into_py(py)),
+                            ("speed".to_string(), mic_e.speed.into_py(py)),
+                            ("message".to_string(), mic_e.message.into_py(py)),
+                            (
+                                "custom_message".to_string(),
+                                mic_e.custom_message.into_py(py),
+                            ),
+                            ("current".to_string(), mic_e.current.into_py(py)),
+                        ]
+                        .into_py_dict_bound(py)
+                        .into(),
+                    );
+                }
+                if position.ambiguity > 0 {
+                    aprs_data.insert("ambiguity".to_string(), position.ambiguity.into_py(py));
+                }
                 aprs_data.insert("comment".to_string(), position.comment.clone().into_py(py));
 
                 result.insert(
@@ -80,6 +209,37 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
                     aprs_data.into_py_dict_bound(py).into(),
                 );
             }
+            AprsData::Item(item) => {
+                let position = item.position;
+                result.insert(
+                    "item".to_string(),
+                    vec![
+                        ("name".to_string(), item.name.into_py(py)),
+                        ("live".to_string(), item.live.into_py(py)),
+                        ("latitude".to_string(), position.latitude.into_py(py)),
+                        ("longitude".to_string(), position.longitude.into_py(py)),
+                        (
+                            "symbol_table".to_string(),
+                            position.symbol_table.into_py(py),
+                        ),
+                        ("symbol_code".to_string(), position.symbol_code.into_py(py)),
+                        ("comment".to_string(), position.comment.into_py(py)),
+                    ]
+                    .into_py_dict_bound(py)
+                    .into(),
+                );
+            }
+            AprsData::Weather(weather) => {
+                result.insert(
+                    "weather_report".to_string(),
+                    vec![
+                        ("timestamp".to_string(), weather.timestamp.into_py(py)),
+                        ("comment".to_string(), weather.comment.into_py(py)),
+                    ]
+                    .into_py_dict_bound(py)
+                    .into(),
+                );
+            }
             AprsData::Status(status) => {
                 let mut aprs_data: HashMap<String, PyObject> = HashMap::new();
 
@@ -94,12 +254,112 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
                     aprs_data.into_py_dict_bound(py).into(),
                 );
             }
-            AprsData::Message(_) | AprsData::Unknown => {
+            AprsData::Telemetry(telemetry) => {
+                let mut aprs_data: HashMap<String, PyObject> = HashMap::new();
+
+                telemetry
+                    .sequence
+                    .map(|sequence| aprs_data.insert("sequence".to_string(), sequence.into_py(py)));
+                aprs_data.insert("values".to_string(), telemetry.values.clone().into_py(py));
+                telemetry
+                    .bits
+                    .map(|bits| aprs_data.insert("bits".to_string(), bits.into_py(py)));
+                aprs_data.insert("comment".to_string(), telemetry.comment.clone().into_py(py));
+
+                result.insert(
+                    "telemetry".to_string(),
+                    aprs_data.into_py_dict_bound(py).into(),
+                );
+            }
+            AprsData::Message(message) => {
+                let mut aprs_data: HashMap<String, PyObject> = HashMap::new();
+
+                aprs_data.insert("addressee".to_string(), message.addressee.into_py(py));
+                aprs_data.insert("text".to_string(), message.text.into_py(py));
+                message
+                    .id
+                    .map(|id| aprs_data.insert("id".to_string(), id.into_py(py)));
+                aprs_data.insert(
+                    "kind".to_string(),
+                    format!("{:?}", message.kind).into_py(py),
+                );
+
+                result.insert(
+                    "message".to_string(),
+                    aprs_data.into_py_dict_bound(py).into(),
+                );
+            }
+            AprsData::ThirdParty(_) => {
+                // The inner packet as received, encoding it again would drop unknown data
+                if let Ok(header) = AprsHeader::parse(s) {
+                    result.insert("third_party".to_string(), parse_str(py, header.body())?);
+                }
+            }
+            AprsData::Unknown => {
                 result.insert("message_type".to_string(), "unknown".into_py(py));
             }
         }
     }
 
+    if let Some(telemetry_definition) = message.telemetry_definition {
+        let definition = match telemetry_definition {
+            TelemetryDefinition::Parameters(parameters) => {
+                vec![("parameters".to_string(), parameters.into_py(py))]
+            }
+            TelemetryDefinition::Units(units) => vec![("units".to_string(), units.into_py(py))],
+            TelemetryDefinition::Equations(equations) => vec![(
+                "equations".to_string(),
+                equations
+                    .into_iter()
+                    .map(|equation| equation.to_vec())
+                    .collect::<Vec<_>>()
+                    .into_py(py),
+            )],
+            TelemetryDefinition::Bits { active, project } => vec![
+                ("active".to_string(), active.into_py(py)),
+                ("project".to_string(), project.into_py(py)),
+            ],
+        };
+        result.insert(
+            "telemetry_definition".to_string(),
+            definition.into_py_dict_bound(py).into_py(py),
+        );
+    }
+
+    if let Some(server_comment) = message.server_comment {
+        let mut comment: HashMap<String, PyObject> = HashMap::new();
+        let values = [
+            ("software", server_comment.software),
+            ("version", server_comment.version),
+            ("server", server_comment.server),
+            ("endpoint", server_comment.endpoint),
+        ];
+        for (key, value) in values {
+            value.map(|value| comment.insert(key.to_string(), value.into_py(py)));
+        }
+        server_comment
+            .timestamp
+            .map(|timestamp| comment.insert("timestamp".to_string(), timestamp.into_py(py)));
+        comment.insert("text".to_string(), server_comment.text.into_py(py));
+
+        result.insert(
+            "server_comment".to_string(),
+            comment.into_py_dict_bound(py).into(),
+        );
+    }
+
+    if let Some(raw_data) = message.raw_data {
+        result.insert(
+            "raw_data".to_string(),
+            vec![
+                ("data_type".to_string(), raw_data.data_type.into_py(py)),
+                ("body".to_string(), raw_data.body.into_py(py)),
+            ]
+            .into_py_dict_bound(py)
+            .into_py(py),
+        );
+    }
+
     if let Some(position_comment) = message.position_comment {
         let mut comment: HashMap<String, PyObject> = HashMap::new();
         position_comment
@@ -126,7 +386,10 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
             comment.insert(
                 "id".to_string(),
                 vec![
-                    ("address_type".to_string(), id.address_type.into_py(py)),
+                    (
+                        "address_type".to_string(),
+                        u8::from(id.address_type).into_py(py),
+                    ),
                     ("aircraft_type".to_string(), id.aircraft_type.into_py(py)),
                     ("is_stealth".to_string(), id.is_stealth.into_py(py)),
                     ("is_notrack".to_string(), id.is_notrack.into_py(py)),
@@ -154,6 +417,14 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
         position_comment
             .gps_quality
             .map(|gps_quality| comment.insert("gps_quality".to_string(), gps_quality.into_py(py)));
+        position_comment
+            .gps_constellations
+            .map(|gps_constellations| {
+                comment.insert(
+                    "gps_constellations".to_string(),
+                    gps_constellations.into_py(py),
+                )
+            });
         position_comment.flight_level.map(|flight_level| {
             comment.insert("flight_level".to_string(), flight_level.into_py(py))
         });
@@ -169,6 +440,135 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
         position_comment.original_address.map(|original_address| {
             comment.insert("original_address".to_string(), original_address.into_py(py))
         });
+        if let Some(uas) = position_comment.uas {
+            comment.insert(
+                "uas".to_string(),
+                vec![
+                    ("operator_id".to_string(), uas.operator_id.into_py(py)),
+                    ("ua_type".to_string(), uas.ua_type.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(balloon) = position_comment.balloon {
+            comment.insert(
+                "balloon".to_string(),
+                vec![
+                    (
+                        "burst_altitude".to_string(),
+                        balloon.burst_altitude.into_py(py),
+                    ),
+                    (
+                        "battery_voltage".to_string(),
+                        balloon.battery_voltage.into_py(py),
+                    ),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(fanet) = position_comment.fanet {
+            comment.insert(
+                "fanet".to_string(),
+                vec![
+                    ("aircraft_type".to_string(), fanet.aircraft_type.into_py(py)),
+                    (
+                        "online_tracking".to_string(),
+                        fanet.online_tracking.into_py(py),
+                    ),
+                    ("manufacturer".to_string(), fanet.manufacturer.into_py(py)),
+                    ("firmware".to_string(), fanet.firmware.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(pilot_aware) = position_comment.pilot_aware {
+            comment.insert(
+                "pilot_aware".to_string(),
+                vec![
+                    ("software".to_string(), pilot_aware.software.into_py(py)),
+                    ("hardware".to_string(), pilot_aware.hardware.into_py(py)),
+                    ("rssi".to_string(), pilot_aware.rssi.into_py(py)),
+                    ("channel".to_string(), pilot_aware.channel.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(adsb) = position_comment.adsb {
+            comment.insert(
+                "adsb".to_string(),
+                vec![
+                    ("squawk".to_string(), adsb.squawk.into_py(py)),
+                    ("registration".to_string(), adsb.registration.into_py(py)),
+                    ("category".to_string(), adsb.category.into_py(py)),
+                    ("flight".to_string(), adsb.flight.into_py(py)),
+                    ("on_ground".to_string(), adsb.on_ground.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(tracker) = position_comment.external_tracker {
+            comment.insert(
+                "external_tracker".to_string(),
+                vec![
+                    ("id".to_string(), tracker.id.into_py(py)),
+                    ("sequence".to_string(), tracker.sequence.into_py(py)),
+                    ("battery".to_string(), tracker.battery.into_py(py)),
+                    ("text".to_string(), tracker.text.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(flymaster) = position_comment.flymaster {
+            comment.insert(
+                "flymaster".to_string(),
+                vec![
+                    ("serial".to_string(), flymaster.serial.into_py(py)),
+                    ("battery".to_string(), flymaster.battery.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        position_comment.frame_counter.map(|frame_counter| {
+            comment.insert("frame_counter".to_string(), frame_counter.into_py(py))
+        });
+        if let Some(derived_motion) = position_comment.derived_motion {
+            comment.insert(
+                "derived_motion".to_string(),
+                vec![
+                    ("course".to_string(), derived_motion.course.into_py(py)),
+                    ("speed".to_string(), derived_motion.speed.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(bearing_report) = position_comment.bearing_report {
+            comment.insert(
+                "bearing_report".to_string(),
+                vec![
+                    ("bearing".to_string(), bearing_report.bearing.into_py(py)),
+                    ("hits".to_string(), bearing_report.hits.into_py(py)),
+                    ("range".to_string(), bearing_report.range.into_py(py)),
+                    ("quality".to_string(), bearing_report.quality.into_py(py)),
+                    ("target".to_string(), bearing_report.target.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
+        if let Some(low_confidence_fields) = position_comment.low_confidence_fields {
+            comment.insert(
+                "low_confidence_fields".to_string(),
+                low_confidence_fields.into_py(py),
+            );
+        }
         position_comment
             .unparsed
             .map(|unparsed| comment.insert("unparsed".to_string(), unparsed.into_py(py)));
@@ -176,6 +576,54 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
         result.insert("ogn".to_string(), comment.into_py_dict_bound(py).into());
     }
 
+    if let Some(weather_comment) = message.weather_comment {
+        let mut weather: HashMap<String, PyObject> = HashMap::new();
+        let values = [
+            (
+                "wind_direction",
+                weather_comment.wind_direction.map(u32::from),
+            ),
+            ("wind_speed", weather_comment.wind_speed.map(u32::from)),
+            ("wind_gust", weather_comment.wind_gust.map(u32::from)),
+            ("rain_1h", weather_comment.rain_1h.map(u32::from)),
+            ("rain_24h", weather_comment.rain_24h.map(u32::from)),
+            (
+                "rain_since_midnight",
+                weather_comment.rain_since_midnight.map(u32::from),
+            ),
+            ("humidity", weather_comment.humidity.map(u32::from)),
+            ("barometric_pressure", weather_comment.barometric_pressure),
+            ("luminosity", weather_comment.luminosity.map(u32::from)),
+        ];
+        for (key, value) in values {
+            value.map(|value| weather.insert(key.to_string(), value.into_py(py)));
+        }
+        weather_comment
+            .temperature
+            .map(|temperature| weather.insert("temperature".to_string(), temperature.into_py(py)));
+        weather_comment
+            .unparsed
+            .map(|unparsed| weather.insert("unparsed".to_string(), unparsed.into_py(py)));
+
+        result.insert("weather".to_string(), weather.into_py_dict_bound(py).into());
+    }
+
+    if let Some(embedded_message) = message.embedded_message {
+        result.insert(
+            "embedded_message".to_string(),
+            vec![
+                (
+                    "addressee".to_string(),
+                    embedded_message.addressee.into_py(py),
+                ),
+                ("text".to_string(), embedded_message.text.into_py(py)),
+                ("id".to_string(), embedded_message.id.into_py(py)),
+            ]
+            .into_py_dict_bound(py)
+            .into_py(py),
+        );
+    }
+
     if let Some(status_comment) = message.status_comment {
         let mut comment: HashMap<String, PyObject> = HashMap::new();
         status_comment
@@ -184,6 +632,12 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
         status_comment
             .platform
             .map(|platform| comment.insert("platform".to_string(), platform.into_py(py)));
+        status_comment.platform_kind.map(|platform_kind| {
+            comment.insert(
+                "platform_kind".to_string(),
+                format!("{:?}", platform_kind).into_py(py),
+            )
+        });
         status_comment
             .cpu_load
             .map(|cpu_load| comment.insert("cpu_load".to_string(), cpu_load.into_py(py)));
@@ -202,6 +656,9 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
         status_comment
             .voltage
             .map(|voltage| comment.insert("voltage".to_string(), voltage.into_py(py)));
+        status_comment
+            .voltages
+            .map(|voltages| comment.insert("voltages".to_string(), voltages.into_py(py)));
         status_comment
             .amperage
             .map(|amperage| comment.insert("amperage".to_string(), amperage.into_py(py)));
@@ -266,6 +723,46 @@ fn parse_str(py: Python<'_>, s: &str) -> PyResult<PyObject> {
                     good_and_bad_senders.into_py(py),
                 )
             });
+        status_comment
+            .tuner_gain
+            .map(|tuner_gain| comment.insert("tuner_gain".to_string(), tuner_gain.into_py(py)));
+        status_comment
+            .frequency_correction
+            .map(|frequency_correction| {
+                comment.insert(
+                    "frequency_correction".to_string(),
+                    frequency_correction.into_py(py),
+                )
+            });
+        if status_comment.maintenance {
+            comment.insert("maintenance".to_string(), true.into_py(py));
+        }
+        if let Some(tracker) = status_comment.tracker {
+            comment.insert(
+                "tracker".to_string(),
+                vec![
+                    (
+                        "hardware_version".to_string(),
+                        tracker.hardware_version.into_py(py),
+                    ),
+                    (
+                        "software_version".to_string(),
+                        tracker.software_version.into_py(py),
+                    ),
+                    ("satellites".to_string(), tracker.satellites.into_py(py)),
+                    ("fix_quality".to_string(), tracker.fix_quality.into_py(py)),
+                    ("altitude".to_string(), tracker.altitude.into_py(py)),
+                    ("pressure".to_string(), tracker.pressure.into_py(py)),
+                    ("temperature".to_string(), tracker.temperature.into_py(py)),
+                    ("humidity".to_string(), tracker.humidity.into_py(py)),
+                    ("tx_power".to_string(), tracker.tx_power.into_py(py)),
+                    ("rx_noise".to_string(), tracker.rx_noise.into_py(py)),
+                    ("packet_rate".to_string(), tracker.packet_rate.into_py(py)),
+                ]
+                .into_py_dict_bound(py)
+                .into_py(py),
+            );
+        }
         status_comment
             .unparsed
             .map(|unparsed| comment.insert("unparsed".to_string(), unparsed.into_py(py)));
@@ -487,4 +984,13 @@ mod tests {
             compare_dicts(py, result_dict, &expected);
         });
     }
+
+    #[test]
+    fn test_features() {
+        assert_eq!(features().contains(&"mmap"), cfg!(feature = "mmap"));
+        assert_eq!(
+            features().contains(&"profiling"),
+            cfg!(feature = "profiling")
+        );
+    }
 }
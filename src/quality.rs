@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::position_comment::AddressType;
+use crate::time::seconds_of_day;
+use crate::vendor::classify;
+
+// A single 0-100 score per aircraft beacon, so consumers can filter with one knob.
+// It starts at 100 and every finding subtracts its penalty:
+//
+// - validation: 20 for each failed check (address type doesn't match the callsign prefix,
+//   the address doesn't match the callsign, climb rate > 10000 fpm, altitude > 60000 ft)
+// - bit errors: 5 per corrected error ("3e"), at most 25
+// - GPS: "gpsAxB" gives the horizontal accuracy A in meters, 2 per meter above 5, at most 25.
+//   Without GPS quality 10
+// - signal: 2.5 per dB below 10 dB, at most 25. Without signal quality 10
+//
+// The score is clamped to 0 and rounded. Beacons without a position comment have no score.
+pub fn quality_score(message: &Message) -> Option<u8> {
+    let comment = message.position_comment.as_ref()?;
+    let mut penalty = 20.0 * validation_failures(message) as f32;
+    penalty += comment
+        .error
+        .map_or(0.0, |errors| (5.0 * errors as f32).min(25.0));
+    penalty += match horizontal_accuracy(comment.gps_quality.as_deref()) {
+        Some(accuracy) => (2.0 * (accuracy - 5.0).max(0.0)).min(25.0),
+        None => 10.0,
+    };
+    penalty += match comment.signal_quality {
+        Some(signal_quality) => (2.5 * (10.0 - signal_quality).max(0.0)).min(25.0),
+        None => 10.0,
+    };
+    Some((100.0 - penalty).max(0.0).round() as u8)
+}
+
+// The number of failed plausibility checks
+pub fn validation_failures(message: &Message) -> u32 {
+    let Some(comment) = message.position_comment.as_ref() else {
+        return 0;
+    };
+    let address_mismatch = match (&message.aprs_packet, &comment.id) {
+        (Ok(packet), Some(id)) if matches!(packet.data, AprsData::Position(_)) => packet
+            .from
+            .device_address()
+            .is_some_and(|address| address != id.address),
+        _ => false,
+    };
+    [
+        classify(message).is_some_and(|classification| classification.mismatch),
+        address_mismatch,
+        comment
+            .climb_rate
+            .is_some_and(|climb_rate| climb_rate.unsigned_abs() > 10000),
+        comment.altitude.is_some_and(|altitude| altitude > 60000),
+    ]
+    .into_iter()
+    .filter(|failed| *failed)
+    .count() as u32
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Inconsistency {
+    // The pressure altitude of the flight level (FL * 100) and the GNSS altitude in feet
+    FlightLevel {
+        pressure_altitude: f32,
+        altitude: u32,
+    },
+    // The reported climb rate of two successive beacons (their mean)
+    // and the one of their altitudes in feet per minute
+    ClimbRate {
+        reported: f32,
+        observed: f32,
+    },
+}
+
+struct Sample {
+    seconds_of_day: i64,
+    altitude: u32,
+    climb_rate: Option<i16>,
+}
+
+// Compares the fields of the aircraft beacons which measure the same thing,
+// inconsistent beacons typically indicate baro sensor problems
+pub struct ConsistencyChecker {
+    max_altitude_difference: f32,
+    max_climb_difference: f32,
+    window: i64,
+    samples: HashMap<(AddressType, u32), Sample>,
+}
+
+impl ConsistencyChecker {
+    // max_altitude_difference: in feet, the pressure altitude differs from the GNSS altitude
+    //   with the weather, usually less than 1000 ft
+    // max_climb_difference: in feet per minute
+    // window: only beacons at most this many seconds apart are compared
+    pub fn new(max_altitude_difference: f32, max_climb_difference: f32, window: i64) -> Self {
+        ConsistencyChecker {
+            max_altitude_difference,
+            max_climb_difference,
+            window,
+            samples: HashMap::new(),
+        }
+    }
+
+    pub fn check(&mut self, message: &Message) -> Vec<Inconsistency> {
+        let mut inconsistencies = vec![];
+        let Some(comment) = message.position_comment.as_ref() else {
+            return inconsistencies;
+        };
+        if let (Some(flight_level), Some(altitude)) = (comment.flight_level, comment.altitude) {
+            let pressure_altitude = flight_level * 100.0;
+            if (pressure_altitude - altitude as f32).abs() > self.max_altitude_difference {
+                inconsistencies.push(Inconsistency::FlightLevel {
+                    pressure_altitude,
+                    altitude,
+                });
+            }
+        }
+
+        let timestamp = match &message.aprs_packet {
+            Ok(packet) => match &packet.data {
+                AprsData::Position(position) => position.timestamp.as_ref(),
+                _ => None,
+            },
+            Err(_) => None,
+        };
+        let (key, sample) = match (
+            &comment.id,
+            comment.altitude,
+            timestamp.and_then(seconds_of_day),
+        ) {
+            (Some(id), Some(altitude), Some(seconds_of_day)) => (
+                (id.address_type, id.address),
+                Sample {
+                    seconds_of_day,
+                    altitude,
+                    climb_rate: comment.climb_rate,
+                },
+            ),
+            _ => return inconsistencies,
+        };
+        if let Some(previous) = self.samples.get(&key) {
+            // The timestamps wrap around at midnight
+            let time_difference =
+                (sample.seconds_of_day - previous.seconds_of_day).rem_euclid(86400);
+            let reported = match (previous.climb_rate, sample.climb_rate) {
+                (Some(previous), Some(current)) => Some((previous as f32 + current as f32) / 2.0),
+                (None, current) => current.map(f32::from),
+                (previous, None) => previous.map(f32::from),
+            };
+            if let Some(reported) =
+                reported.filter(|_| (1..=self.window).contains(&time_difference))
+            {
+                let observed = (sample.altitude as f32 - previous.altitude as f32) * 60.0
+                    / time_difference as f32;
+                if (observed - reported).abs() > self.max_climb_difference {
+                    inconsistencies.push(Inconsistency::ClimbRate { reported, observed });
+                }
+            }
+        }
+        self.samples.insert(key, sample);
+        inconsistencies
+    }
+}
+
+fn horizontal_accuracy(gps_quality: Option<&str>) -> Option<f32> {
+    let (horizontal, _) = gps_quality?.split_once('x')?;
+    horizontal.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(comment: &str) -> Option<u8> {
+        let line = format!(
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 {}",
+            comment
+        );
+        quality_score(&line.parse::<Message>().unwrap())
+    }
+
+    #[test]
+    fn test_quality_score() {
+        assert_eq!(score("id06DDFAA3 12.5dB 0e gps2x3"), Some(100));
+        // 2 errors: -10, 8.0dB: -5, 9 m: -8
+        assert_eq!(score("id06DDFAA3 8.0dB 2e gps9x3"), Some(77));
+        // Neither GPS nor signal quality
+        assert_eq!(score("id06DDFAA3"), Some(80));
+        // Wrong address type and address
+        assert_eq!(score("id05DDFAA4 12.5dB gps2x3"), Some(60));
+        assert_eq!(score("id05DDFAA4 0.0dB 9e gps40x3 +20000fpm"), Some(0));
+
+        let status = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!";
+        assert_eq!(quality_score(&status.parse::<Message>().unwrap()), None);
+    }
+
+    #[test]
+    fn test_validation_failures() {
+        let message =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=070000 id06DDFAA3 -12000fpm"
+                .parse::<Message>()
+                .unwrap();
+        assert_eq!(validation_failures(&message), 2);
+    }
+
+    fn beacon(timestamp: &str, comment: &str) -> Message {
+        format!(
+            r"FLRDDFAA3>APRS,qAS,Letzi:/{}5111.32N/00102.04W'086/007/{} id06DDFAA3",
+            timestamp, comment
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_consistency() {
+        let mut checker = ConsistencyChecker::new(1000.0, 300.0, 60);
+        assert_eq!(
+            checker.check(&beacon("074548h", "A=005000 +000fpm FL49.50")),
+            vec![]
+        );
+        assert_eq!(
+            checker.check(&beacon("074552h", "A=005000 +600fpm FL62.00")),
+            vec![Inconsistency::FlightLevel {
+                pressure_altitude: 6200.0,
+                altitude: 5000,
+            }]
+        );
+        // 40 ft in 4 s: 600 fpm, the mean of the reported climb rates is 597 fpm
+        assert_eq!(
+            checker.check(&beacon("074556h", "A=005040 +594fpm")),
+            vec![]
+        );
+        assert_eq!(
+            checker.check(&beacon("074600h", "A=005040 +594fpm")),
+            vec![Inconsistency::ClimbRate {
+                reported: 594.0,
+                observed: 0.0,
+            }]
+        );
+        // Too far apart
+        assert_eq!(
+            checker.check(&beacon("075600h", "A=001000 +000fpm")),
+            vec![]
+        );
+        // Across midnight
+        let mut checker = ConsistencyChecker::new(1000.0, 300.0, 60);
+        assert_eq!(
+            checker.check(&beacon("235958h", "A=005000 -200fpm")),
+            vec![]
+        );
+        assert_eq!(
+            checker.check(&beacon("000002h", "A=004987 -200fpm")),
+            vec![]
+        );
+    }
+}
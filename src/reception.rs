@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::detection::is_receiver;
+use crate::geo::distance;
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::time;
+
+// The columns of the reverse beacon network exports, with the OGN measurements
+const HEADER: &str = "spotter,dx,date,distance_km,db,freq_offset_khz";
+
+// Writes one CSV row per received aircraft beacon:
+// Letzi,FLRDDFAA3,2024-08-01 07:45:48,12.3,10.0,-0.4
+// The distance needs the position of the receiver, it is learned from its beacons
+// and empty until then.
+pub struct ReceptionWriter<W: Write> {
+    writer: W,
+    receivers: HashMap<String, (f64, f64)>,
+    header_written: bool,
+}
+
+impl<W: Write> ReceptionWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ReceptionWriter {
+            writer,
+            receivers: HashMap::new(),
+            header_written: false,
+        }
+    }
+
+    // reference_time: seconds since 1970-01-01 used to resolve the APRS timestamp
+    pub fn update(&mut self, message: &Message, reference_time: i64) -> io::Result<()> {
+        let Ok(packet) = &message.aprs_packet else {
+            return Ok(());
+        };
+        let AprsData::Position(position) = &packet.data else {
+            return Ok(());
+        };
+        if is_receiver(packet) {
+            self.receivers.insert(
+                packet.from.to_string(),
+                (position.latitude, position.longitude),
+            );
+            return Ok(());
+        }
+        // Aircraft beacons are forwarded by the receiver: FLRDDFAA3>APRS,qAS,Letzi:...
+        let (Some(comment), Some(receiver)) = (&message.position_comment, message.receiver())
+        else {
+            return Ok(());
+        };
+        let Some(time) = position
+            .timestamp
+            .as_ref()
+            .and_then(|timestamp| time::resolve(timestamp, reference_time))
+        else {
+            return Ok(());
+        };
+
+        let receiver = receiver.to_string();
+        let distance = self.receivers.get(&receiver).map(|(latitude, longitude)| {
+            distance(*latitude, *longitude, position.latitude, position.longitude) / 1000.0
+        });
+        if !self.header_written {
+            writeln!(self.writer, "{}", HEADER)?;
+            self.header_written = true;
+        }
+        let iso8601 = time::to_iso8601(time);
+        writeln!(
+            self.writer,
+            "{},{},{} {},{},{},{}",
+            receiver,
+            packet.from,
+            &iso8601[..10],
+            &iso8601[11..19],
+            column(distance),
+            column(comment.signal_quality.map(f64::from)),
+            column(comment.frequency_offset.map(f64::from)),
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// One decimal, empty if missing
+fn column(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| format!("{:.1}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reception_rows() {
+        let lines = [
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3 10.0dB -0.4kHz",
+            r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765",
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074610h4714.92N/00653.67E'086/007/A=000607 id06DDFAA3 8.5dB",
+            r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!",
+        ];
+        let mut writer = ReceptionWriter::new(vec![]);
+        // 2024-08-01 08:00:00
+        let reference_time = 1722499200;
+        for line in lines {
+            writer
+                .update(&line.parse::<Message>().unwrap(), reference_time)
+                .unwrap();
+        }
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                HEADER,
+                "Letzi,FLRDDFAA3,2024-08-01 07:45:48,,10.0,-0.4",
+                "Letzi,FLRDDFAA3,2024-08-01 07:46:10,11.1,8.5,",
+            ]
+        );
+    }
+}
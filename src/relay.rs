@@ -0,0 +1,313 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::filter::FilterSet;
+use crate::login::Login;
+use crate::message::Message;
+use crate::pipeline::Pipeline;
+use crate::server_comment::ServerComment;
+use crate::time::{Clock, SystemClock};
+
+// Clients have to log in within this time
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+// A client not reading is disconnected after this time, instead of stalling the feed
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+// The servers send a keepalive every 20 seconds
+const KEEPALIVE_INTERVAL: i64 = 20;
+
+struct Client {
+    // Locked for a whole line, so lines of publish and keepalive don't interleave
+    stream: Mutex<TcpStream>,
+    endpoint: String,
+    login: Login,
+    filter: FilterSet,
+}
+
+impl Client {
+    fn send(&self, line: &str) -> bool {
+        self.stream
+            .lock()
+            .unwrap()
+            .write_all(line.as_bytes())
+            .is_ok()
+    }
+}
+
+// Re-serves an upstream feed to local APRS-IS clients, each client only gets the parsed
+// packets matching the filter of its login:
+//     let relay = Relay::bind("0.0.0.0:14580", "CLUB")?;
+//     relay.run(upstream, &mut Pipeline::new())?;
+// Clients are never verified, the relay is read-only. The clients are written to
+// outside the lock of the client list, one at a time with a write timeout.
+pub struct Relay {
+    address: SocketAddr,
+    server: String,
+    clients: Arc<Mutex<Vec<Arc<Client>>>>,
+    clock: Arc<dyn Clock + Send + Sync>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Relay {
+    // server: the name in the banner and the logresp
+    pub fn bind<A: ToSocketAddrs>(address: A, server: &str) -> io::Result<Self> {
+        Relay::bind_with_clock(address, server, SystemClock)
+    }
+
+    // The clock gives the time of the banners and keepalives
+    pub fn bind_with_clock<A: ToSocketAddrs, C: Clock + Send + Sync + 'static>(
+        address: A,
+        server: &str,
+        clock: C,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(vec![]));
+        let clock: Arc<dyn Clock + Send + Sync> = Arc::new(clock);
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let clients = clients.clone();
+            let clock = clock.clone();
+            let stopped = stopped.clone();
+            let server = server.to_string();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let clients = clients.clone();
+                        let clock = clock.clone();
+                        let server = server.clone();
+                        // A client failing the login is just dropped
+                        thread::spawn(move || {
+                            let _ = handshake(stream, &server, &clients, &clock);
+                        });
+                    }
+                }
+            })
+        };
+        Ok(Relay {
+            address,
+            server: server.to_string(),
+            clients,
+            clock,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    // The logins of the connected clients
+    pub fn logins(&self) -> Vec<Login> {
+        let clients = self.clients.lock().unwrap();
+        clients.iter().map(|client| client.login.clone()).collect()
+    }
+
+    // Sends the raw string to the matching clients and returns their number.
+    // Parse failures and server comments of the upstream are not relayed,
+    // clients that can't be written to are disconnected.
+    pub fn publish(&self, message: &Message) -> usize {
+        if message.aprs_packet.is_err() {
+            return 0;
+        }
+        let line = format!("{}\r\n", message.raw_string);
+        let (sent, failed): (Vec<_>, Vec<_>) = self
+            .receivers(|client| client.filter.matches(message))
+            .into_iter()
+            .partition(|client| client.send(&line));
+        self.disconnect(&failed);
+        sent.len()
+    }
+
+    // timestamp: seconds since 1970-01-01
+    pub fn keepalive(&self, timestamp: i64) {
+        let failed = self
+            .receivers(|_| true)
+            .into_iter()
+            .filter(|client| {
+                let keepalive = keepalive(&self.server, &client.endpoint, timestamp);
+                !client.send(&format!("{}\r\n", keepalive))
+            })
+            .collect::<Vec<_>>();
+        self.disconnect(&failed);
+    }
+
+    // Relays the upstream feed until it ends, with the keepalives in between
+    pub fn run<R: BufRead>(&self, upstream: R, pipeline: &mut Pipeline) -> io::Result<()> {
+        let mut last_keepalive = self.clock.now();
+        pipeline.run(upstream, |message| {
+            self.publish(&message);
+            let now = self.clock.now();
+            if now - last_keepalive >= KEEPALIVE_INTERVAL {
+                self.keepalive(now);
+                last_keepalive = now;
+            }
+        })
+    }
+
+    // Only holds the lock of the client list while they are selected
+    fn receivers<F: Fn(&Client) -> bool>(&self, selected: F) -> Vec<Arc<Client>> {
+        let clients = self.clients.lock().unwrap();
+        clients
+            .iter()
+            .filter(|client| selected(client))
+            .cloned()
+            .collect()
+    }
+
+    fn disconnect(&self, failed: &[Arc<Client>]) {
+        if failed.is_empty() {
+            return;
+        }
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|client| !failed.iter().any(|failed| Arc::ptr_eq(client, failed)));
+        for client in failed {
+            let _ = client.stream.lock().unwrap().shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes up the accept loop
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        for client in self.clients.lock().unwrap().drain(..) {
+            let _ = client.stream.lock().unwrap().shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn keepalive(server: &str, endpoint: &str, timestamp: i64) -> ServerComment {
+    ServerComment::keepalive(
+        "ognparser",
+        env!("CARGO_PKG_VERSION"),
+        timestamp,
+        server,
+        endpoint,
+    )
+}
+
+fn handshake<C: Clock + ?Sized>(
+    stream: TcpStream,
+    server: &str,
+    clients: &Mutex<Vec<Arc<Client>>>,
+    clock: &C,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    writer.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let endpoint = writer
+        .local_addr()
+        .map_or(String::new(), |address| address.to_string());
+    write!(writer, "{}\r\n", keepalive(server, &endpoint, clock.now()))?;
+
+    stream.set_read_timeout(Some(LOGIN_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let Some(login) = Login::parse(&line) else {
+        return write!(writer, "# invalid login\r\n");
+    };
+    let filter = login
+        .filter
+        .as_deref()
+        .unwrap_or_default()
+        .parse::<FilterSet>()
+        .unwrap();
+
+    let logresp = ServerComment::logresp(&login.user, false, server);
+    let client = Arc::new(Client {
+        stream: Mutex::new(writer),
+        endpoint,
+        login,
+        filter,
+    });
+    // The stream stays locked until the logresp is written, so no packet is published
+    // before it
+    let mut stream = client.stream.lock().unwrap();
+    clients.lock().unwrap().push(client.clone());
+    write!(stream, "{}\r\n", logresp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect(relay: &Relay, login: &str) -> BufReader<TcpStream> {
+        let mut stream = TcpStream::connect(relay.address()).unwrap();
+        writeln!(stream, "{}", login).unwrap();
+        let mut reader = BufReader::new(stream);
+        // The banner and the logresp
+        for _ in 0..2 {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("# "));
+        }
+        reader
+    }
+
+    #[test]
+    fn test_relay() {
+        let relay = Relay::bind("127.0.0.1:0", "CLUB").unwrap();
+        let nearby = connect(
+            &relay,
+            "user N0CALL pass -1 vers test 1.0 filter r/47.1/6.9/50 -p/Letzi",
+        );
+        let all = connect(&relay, "user N0CALL-1 pass -1");
+        assert_eq!(relay.logins().len(), 2);
+
+        let upstream = [
+            r"# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152",
+            r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3",
+            r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:/074600h4708.92N/00653.67E&/A=001765",
+            r"ICA3D17F2>APRS,qAS,dl4mea:/074849h4821.61N\01224.49E^322/103/A=003054",
+            "invalid",
+        ]
+        .join("\n");
+        relay
+            .run(upstream.as_bytes(), &mut Pipeline::new())
+            .unwrap();
+        relay.keepalive(1719655537);
+        drop(relay);
+
+        let lines = |reader: BufReader<TcpStream>| {
+            reader
+                .lines()
+                .map(Result::unwrap)
+                .filter(|line| !line.starts_with('#'))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(nearby),
+            vec![
+                r"FLRDDFAA3>OGFLR,qAS,Letzi:/074548h4708.92N/00653.67E'086/007/A=000607 id06DDFAA3"
+            ]
+        );
+        assert_eq!(lines(all).len(), 3);
+    }
+
+    #[test]
+    fn test_clock() {
+        let clock = Arc::new(crate::time::SimulatedClock::new(1719655537));
+        let relay = Relay::bind_with_clock("127.0.0.1:0", "CLUB", clock.clone()).unwrap();
+        clock.advance(20);
+        let stream = TcpStream::connect(relay.address()).unwrap();
+        let mut banner = String::new();
+        BufReader::new(stream).read_line(&mut banner).unwrap();
+        let message = banner.trim_end().parse::<Message>().unwrap();
+        assert_eq!(message.server_comment.unwrap().timestamp, Some(1719655557));
+    }
+}
@@ -0,0 +1,275 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::aggregation::{Aggregator, ReceiverStatistics};
+use crate::message::Message;
+use crate::time;
+
+const STATISTICS: [&str; 4] = ["signal_quality", "signal_power", "noise", "latency"];
+const STATISTIC_FIELDS: [&str; 6] = ["count", "mean", "min", "max", "p50", "p95"];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum RollupPeriod {
+    #[default]
+    Hourly,
+    Daily,
+}
+
+impl RollupPeriod {
+    fn seconds(&self) -> i64 {
+        match self {
+            RollupPeriod::Hourly => 3600,
+            RollupPeriod::Daily => 86400,
+        }
+    }
+}
+
+impl FromStr for RollupPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hourly" => Ok(RollupPeriod::Hourly),
+            "daily" => Ok(RollupPeriod::Daily),
+            _ => Err(format!("Unknown rollup period: {}", s)),
+        }
+    }
+}
+
+// Parquet would need a heavy dependency, so we write formats every tool can import
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum RollupFormat {
+    // One JSON object per line
+    #[default]
+    Json,
+    Csv,
+}
+
+impl RollupFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            RollupFormat::Json => "ndjson",
+            RollupFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for RollupFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(RollupFormat::Json),
+            "csv" => Ok(RollupFormat::Csv),
+            _ => Err(format!("Unknown rollup format: {}", s)),
+        }
+    }
+}
+
+// Writes the aggregation state of every period into its own partition, e.g.
+// "2024-08-01/07.csv" for hourly or "2024-08-01.csv" for daily rollups, one row per receiver
+pub struct RollupWriter {
+    directory: PathBuf,
+    aggregator: Aggregator,
+    period: RollupPeriod,
+    format: RollupFormat,
+    current: Option<i64>,
+}
+
+impl RollupWriter {
+    // The aggregator keeps its calibration over all periods
+    pub fn new<P: AsRef<Path>>(directory: P, aggregator: Aggregator) -> Self {
+        RollupWriter {
+            directory: directory.as_ref().to_path_buf(),
+            aggregator,
+            period: RollupPeriod::default(),
+            format: RollupFormat::default(),
+            current: None,
+        }
+    }
+
+    pub fn with_period(mut self, period: RollupPeriod) -> Self {
+        self.period = period;
+        self
+    }
+
+    pub fn with_format(mut self, format: RollupFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // time: seconds since 1970-01-01 when the message was received.
+    // The previous period is written as soon as a message of a new period arrives.
+    pub fn update(&mut self, message: &Message, time: i64) -> io::Result<()> {
+        let start = time - time.rem_euclid(self.period.seconds());
+        if self.current.is_some_and(|current| current != start) {
+            self.flush()?;
+        }
+        self.current = Some(start);
+        self.aggregator.update(message);
+        Ok(())
+    }
+
+    // Writes the current period, call it at the end of the stream
+    pub fn flush(&mut self) -> io::Result<()> {
+        let receivers = self.aggregator.take_receivers();
+        let start = match self.current.take() {
+            Some(start) if !receivers.is_empty() => start,
+            _ => return Ok(()),
+        };
+        let path = self.partition(start);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let period_start = time::to_iso8601(start);
+        let rows = receivers
+            .iter()
+            .map(|(receiver, statistics)| row(&period_start, receiver, statistics))
+            .collect::<Vec<_>>();
+        let content = match self.format {
+            RollupFormat::Json => rows
+                .iter()
+                .map(|row| Value::Object(row.clone()).to_string() + "\n")
+                .collect::<String>(),
+            RollupFormat::Csv => to_csv(&rows),
+        };
+        fs::write(path, content)
+    }
+
+    fn partition(&self, start: i64) -> PathBuf {
+        let datetime = time::to_iso8601(start);
+        let (date, hour) = (&datetime[..10], &datetime[11..13]);
+        let extension = self.format.extension();
+        match self.period {
+            RollupPeriod::Hourly => self
+                .directory
+                .join(date)
+                .join(format!("{}.{}", hour, extension)),
+            RollupPeriod::Daily => self.directory.join(format!("{}.{}", date, extension)),
+        }
+    }
+}
+
+fn row(period_start: &str, receiver: &str, statistics: &ReceiverStatistics) -> Map<String, Value> {
+    let mut row = Map::new();
+    row.insert("period_start".to_string(), Value::from(period_start));
+    row.insert("receiver".to_string(), Value::from(receiver));
+    if let Ok(Value::Object(statistics)) = serde_json::to_value(statistics) {
+        row.extend(statistics);
+    }
+    row
+}
+
+// Flat columns like "noise_p95"
+fn to_csv(rows: &[Map<String, Value>]) -> String {
+    let mut columns = vec![
+        "period_start".to_string(),
+        "receiver".to_string(),
+        "beacons".to_string(),
+        "restarts".to_string(),
+        "maintenance".to_string(),
+        "uptime".to_string(),
+    ];
+    for statistic in STATISTICS {
+        for field in STATISTIC_FIELDS {
+            columns.push(format!("{}_{}", statistic, field));
+        }
+    }
+
+    let mut csv = columns.join(",") + "\n";
+    for row in rows {
+        let mut values = vec![
+            csv_value(&row["period_start"]),
+            csv_value(&row["receiver"]),
+            csv_value(&row["beacons"]),
+            csv_value(&row["restarts"]),
+            csv_value(&row["maintenance"]),
+            csv_value(&row["uptime"]),
+        ];
+        for statistic in STATISTICS {
+            for field in STATISTIC_FIELDS {
+                values.push(csv_value(&row[statistic][field]));
+            }
+        }
+        csv += &(values.join(",") + "\n");
+    }
+    csv
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) if s.contains([',', '"', '\n']) => {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        }
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beacon(receiver: &str) -> Message {
+        format!(
+            r"FLRDDFAA3>APRS,qAS,{}:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3 10.0dB",
+            receiver
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hourly_rollups() {
+        let directory = std::env::temp_dir().join(format!("ogn-rollup-{}", std::process::id()));
+        let mut writer =
+            RollupWriter::new(&directory, Aggregator::new().with_calibration("Letzi", 1.0))
+                .with_format(RollupFormat::Csv);
+        // 2024-08-01T07:45:48Z
+        writer.update(&beacon("Letzi"), 1722498348).unwrap();
+        let status = r"Letzi>OGNSDR,TCPIP*,qAC,GLIDERN2:>074549h v0.2.8.RPI-GPU MAINT"
+            .parse::<Message>()
+            .unwrap();
+        writer.update(&status, 1722498349).unwrap();
+        writer.update(&beacon("dl4mea"), 1722498350).unwrap();
+        // The next hour flushes the previous one
+        writer.update(&beacon("Letzi"), 1722502000).unwrap();
+        writer.flush().unwrap();
+
+        let csv = fs::read_to_string(directory.join("2024-08-01").join("07.csv")).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(
+            "period_start,receiver,beacons,restarts,maintenance,uptime,signal_quality_count,"
+        ));
+        assert!(lines[1].starts_with("2024-08-01T07:00:00Z,Letzi,1,0,1,0,1,11.0,"));
+        assert!(lines[2].starts_with("2024-08-01T07:00:00Z,dl4mea,1,0,0,,1,10.0,"));
+        assert!(directory.join("2024-08-01").join("08.csv").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_partition() {
+        let writer =
+            RollupWriter::new("rollups", Aggregator::new()).with_period(RollupPeriod::Daily);
+        assert_eq!(
+            writer.partition(1722470400),
+            Path::new("rollups").join("2024-08-01.ndjson")
+        );
+        assert_eq!("daily".parse(), Ok(RollupPeriod::Daily));
+        assert!("parquet".parse::<RollupFormat>().is_err());
+    }
+
+    #[test]
+    fn test_csv_value() {
+        assert_eq!(csv_value(&Value::Null), "");
+        assert_eq!(csv_value(&Value::from("a,b")), "\"a,b\"");
+        assert_eq!(csv_value(&Value::from(2.5)), "2.5");
+    }
+}
@@ -0,0 +1,449 @@
+use std::fmt::{Display, Formatter};
+
+use serde_json::{Map, Value};
+
+use crate::aprs_message::MessageKind;
+use crate::flavor::BeaconFlavor;
+use crate::packet::{AprsData, AprsPacket};
+use crate::status_comment::{PlatformKind, StatusComment};
+
+// Version history of the serialized records:
+// 0: ogn-parser-rs <= 0.2.0, records without "schema_version"
+// 1: records are stamped with "schema_version", adds
+//    "embedded_message", "raw_data", "telemetry_definition", "weather_comment",
+//    "server_comment", "flavor" and "provenance",
+//    the token fields of "position_comment" from "uas" to "low_confidence_fields",
+//    "voltages", "tuner_gain", "frequency_correction", "platform_kind", "maintenance"
+//    and "tracker" to "status_comment", "compression", "mic_e" and "ambiguity" to
+//    "aprs_packet/data/Position" and "kind" to "aprs_packet/data/Message",
+//    "aprs_packet/data" of telemetry frames, items and third-party packets is
+//    "Telemetry", "Item" and "ThirdParty" instead of "Unknown"
+pub const SCHEMA_VERSION: u64 = 1;
+
+// Migration from version n to version n + 1 is at index n
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize] = [migrate_v0_to_v1];
+
+// The fields of version 1 that a version 0 record has not, all of them optional
+const NEW_FIELDS: [&str; 6] = [
+    "embedded_message",
+    "raw_data",
+    "telemetry_definition",
+    "weather_comment",
+    "server_comment",
+    "provenance",
+];
+
+const NEW_POSITION_COMMENT_FIELDS: [&str; 12] = [
+    "uas",
+    "balloon",
+    "frame_counter",
+    "bearing_report",
+    "gps_constellations",
+    "derived_motion",
+    "fanet",
+    "pilot_aware",
+    "adsb",
+    "external_tracker",
+    "flymaster",
+    "low_confidence_fields",
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SchemaError {
+    NotAnObject,
+    InvalidVersion(Value),
+    UnsupportedVersion(u64),
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::NotAnObject => write!(f, "Record is not a JSON object"),
+            SchemaError::InvalidVersion(value) => write!(f, "Invalid schema version: {}", value),
+            SchemaError::UnsupportedVersion(version) => write!(
+                f,
+                "Schema version {} is newer than the supported version {}",
+                version, SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+pub fn schema_version(record: &Value) -> Result<u64, SchemaError> {
+    let object = record.as_object().ok_or(SchemaError::NotAnObject)?;
+    match object.get("schema_version") {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| SchemaError::InvalidVersion(value.clone())),
+    }
+}
+
+// Brings a record of any older schema version to the current SCHEMA_VERSION
+pub fn migrate(record: Value) -> Result<Value, SchemaError> {
+    migrate_to(record, SCHEMA_VERSION)
+}
+
+pub fn migrate_to(mut record: Value, target_version: u64) -> Result<Value, SchemaError> {
+    let version = schema_version(&record)?;
+    if version > SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion(version));
+    }
+    if target_version > SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion(target_version));
+    }
+    if let Some(object) = record.as_object_mut() {
+        for migration in MIGRATIONS
+            .iter()
+            .take(target_version as usize)
+            .skip(version as usize)
+        {
+            migration(object);
+        }
+    }
+    Ok(record)
+}
+
+fn migrate_v0_to_v1(record: &mut Map<String, Value>) {
+    for field in NEW_FIELDS {
+        record.insert(field.to_string(), Value::Null);
+    }
+
+    if let Some(Value::Object(position_comment)) = record.get_mut("position_comment") {
+        for field in NEW_POSITION_COMMENT_FIELDS {
+            position_comment.insert(field.to_string(), Value::Null);
+        }
+    }
+
+    if let Some(Value::Object(status_comment)) = record.get_mut("status_comment") {
+        let voltages = match status_comment.get("voltage") {
+            Some(Value::Null) | None => Value::Null,
+            Some(voltage) => Value::Array(vec![voltage.clone()]),
+        };
+        status_comment.insert("voltages".to_string(), voltages);
+        status_comment.insert("tuner_gain".to_string(), Value::Null);
+        status_comment.insert("frequency_correction".to_string(), Value::Null);
+        let platform_kind = match status_comment.get("platform") {
+            Some(Value::String(platform)) => {
+                serde_json::to_value(PlatformKind::from_platform(platform)).unwrap()
+            }
+            _ => Value::Null,
+        };
+        status_comment.insert("platform_kind".to_string(), platform_kind);
+        status_comment.insert("maintenance".to_string(), Value::Bool(false));
+        status_comment.insert("tracker".to_string(), Value::Null);
+    }
+
+    reclassify_unknown_data(record);
+
+    if let Some(Value::Object(position)) = record
+        .get_mut("aprs_packet")
+        .and_then(|packet| packet.pointer_mut("/data/Position"))
+    {
+        position.insert("compression".to_string(), Value::Null);
+        position.insert("mic_e".to_string(), Value::Null);
+        position.insert("ambiguity".to_string(), Value::from(0u64));
+    }
+
+    if let Some(Value::Object(message)) = record
+        .get_mut("aprs_packet")
+        .and_then(|packet| packet.pointer_mut("/data/Message"))
+    {
+        let (kind, id) = match (message.get("text"), message.get("id")) {
+            (Some(Value::String(text)), Some(Value::Null)) => MessageKind::classify(text),
+            _ => (MessageKind::Message, None),
+        };
+        if let Some(id) = id {
+            message.insert("id".to_string(), Value::from(id));
+        }
+        message.insert("kind".to_string(), serde_json::to_value(kind).unwrap());
+    }
+
+    let flavor = record
+        .get("aprs_packet")
+        .and_then(|packet| packet.pointer("/to/call"))
+        .and_then(Value::as_str)
+        .map(BeaconFlavor::from_tocall);
+    if flavor.is_some_and(|flavor| flavor.is_aircraft()) {
+        parse_tracker_tokens(record);
+    }
+    record.insert("flavor".to_string(), serde_json::to_value(flavor).unwrap());
+
+    record.insert("schema_version".to_string(), Value::from(1u64));
+}
+
+// Version 0 parsed the statuses of trackers like receiver statuses, the tracker tokens
+// are in "unparsed"
+fn parse_tracker_tokens(record: &mut Map<String, Value>) {
+    let Some(Value::Object(status_comment)) = record.get_mut("status_comment") else {
+        return;
+    };
+    let Some(Value::String(unparsed)) = status_comment.get("unparsed") else {
+        return;
+    };
+    let mut parsed = StatusComment {
+        unparsed: Some(unparsed.clone()),
+        ..Default::default()
+    };
+    parsed.parse_tracker_tokens();
+    // Through the text, to_value would widen the f32 values with their rounding errors
+    let tracker = serde_json::to_string(&parsed.tracker).unwrap();
+    status_comment.insert(
+        "tracker".to_string(),
+        serde_json::from_str(&tracker).unwrap(),
+    );
+    status_comment.insert(
+        "unparsed".to_string(),
+        serde_json::to_value(parsed.unparsed).unwrap(),
+    );
+}
+
+// Version 0 wrote "Unknown" for all data types it didn't parse, the data types parsed
+// since then are parsed again from the raw string
+fn reclassify_unknown_data(record: &mut Map<String, Value>) {
+    let Some(Value::String(raw_string)) = record.get("raw_string") else {
+        return;
+    };
+    let Ok(packet) = raw_string.parse::<AprsPacket>() else {
+        return;
+    };
+    if !matches!(
+        packet.data,
+        AprsData::Telemetry(_) | AprsData::Item(_) | AprsData::ThirdParty(_)
+    ) {
+        return;
+    }
+    if let Some(Value::Object(aprs_packet)) = record.get_mut("aprs_packet") {
+        if aprs_packet.get("data").and_then(Value::as_str) == Some("Unknown") {
+            aprs_packet.insert(
+                "data".to_string(),
+                serde_json::to_value(&packet.data).unwrap(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+
+    #[test]
+    fn test_current_records_are_stamped() {
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+            .parse::<Message>()
+            .unwrap();
+        let record = serde_json::to_value(&message).unwrap();
+        assert_eq!(schema_version(&record), Ok(SCHEMA_VERSION));
+        assert_eq!(migrate(record.clone()), Ok(record));
+    }
+
+    #[test]
+    fn test_migrate_v0() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"raw_string": "ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!", "position_comment": null}"#,
+        )
+        .unwrap();
+        assert_eq!(schema_version(&record), Ok(0));
+
+        let migrated = migrate(record).unwrap();
+        assert_eq!(schema_version(&migrated), Ok(SCHEMA_VERSION));
+        assert_eq!(migrated["embedded_message"], Value::Null);
+        assert_eq!(migrated["raw_data"], Value::Null);
+        assert!(migrated.get("telemetry_definition").is_some());
+        assert!(migrated.get("weather_comment").is_some());
+        assert!(migrated.get("server_comment").is_some());
+        assert!(migrated.get("flavor").is_some());
+        assert_eq!(migrated["provenance"], Value::Null);
+        assert_eq!(
+            migrated["raw_string"],
+            "ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+        );
+    }
+
+    #[test]
+    fn test_migrate_position_comment() {
+        let record =
+            serde_json::from_str::<Value>(r#"{"position_comment": {"altitude": 3054}}"#).unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["position_comment"]["uas"], Value::Null);
+        assert!(migrated["position_comment"].get("uas").is_some());
+        assert!(migrated["position_comment"].get("balloon").is_some());
+        assert!(migrated["position_comment"].get("frame_counter").is_some());
+        assert!(migrated["position_comment"].get("bearing_report").is_some());
+        assert!(migrated["position_comment"]
+            .get("gps_constellations")
+            .is_some());
+        assert!(migrated["position_comment"].get("derived_motion").is_some());
+        assert!(migrated["position_comment"].get("fanet").is_some());
+        assert!(migrated["position_comment"].get("pilot_aware").is_some());
+        assert!(migrated["position_comment"].get("adsb").is_some());
+        assert!(migrated["position_comment"]
+            .get("external_tracker")
+            .is_some());
+        assert!(migrated["position_comment"].get("flymaster").is_some());
+        assert!(migrated["position_comment"]
+            .get("low_confidence_fields")
+            .is_some());
+
+        let record = serde_json::from_str::<Value>(r#"{"position_comment": null}"#).unwrap();
+        assert_eq!(migrate(record).unwrap()["position_comment"], Value::Null);
+    }
+
+    #[test]
+    fn test_migrate_voltages() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"status_comment": {"voltage": 3.3, "amperage": null}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(
+            migrated["status_comment"]["voltages"],
+            serde_json::from_str::<Value>("[3.3]").unwrap()
+        );
+
+        let record =
+            serde_json::from_str::<Value>(r#"{"status_comment": {"voltage": null}}"#).unwrap();
+        let migrated = migrate(record).unwrap();
+        assert!(migrated["status_comment"].get("voltages").is_some());
+        assert_eq!(migrated["status_comment"]["voltages"], Value::Null);
+        assert!(migrated["status_comment"].get("tuner_gain").is_some());
+        assert!(migrated["status_comment"]
+            .get("frequency_correction")
+            .is_some());
+        assert_eq!(migrated["status_comment"]["maintenance"], false);
+    }
+
+    #[test]
+    fn test_migrate_platform_kind() {
+        let record =
+            serde_json::from_str::<Value>(r#"{"status_comment": {"platform": "RPI-GPU"}}"#)
+                .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["status_comment"]["platform_kind"], "RaspberryPi");
+
+        let record =
+            serde_json::from_str::<Value>(r#"{"status_comment": {"platform": null}}"#).unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["status_comment"]["platform_kind"], Value::Null);
+    }
+
+    #[test]
+    fn test_migrate_position() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"aprs_packet": {"data": {"Position": {"latitude": 48.36}}}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        let position = &migrated["aprs_packet"]["data"]["Position"];
+        assert!(position.get("compression").is_some());
+        assert!(position.get("mic_e").is_some());
+        assert_eq!(position["ambiguity"], 0);
+        assert_eq!(position["latitude"], 48.36);
+    }
+
+    #[test]
+    fn test_migrate_message_kind() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"aprs_packet": {"data": {"Message": {"text": "ack42", "id": null}}}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        let message = &migrated["aprs_packet"]["data"]["Message"];
+        assert_eq!(message["kind"], "Ack");
+        assert_eq!(message["id"], 42);
+
+        let record = serde_json::from_str::<Value>(
+            r#"{"aprs_packet": {"data": {"Message": {"text": "ack42", "id": 3}}}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(
+            migrated["aprs_packet"]["data"]["Message"]["kind"],
+            "Message"
+        );
+    }
+
+    #[test]
+    fn test_migrate_flavor() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"aprs_packet": {"to": {"call": "OGNTRK", "ssid": null}}, "status_comment": {"voltage": 3.3, "unparsed": "9sat/1 1002.5hPa"}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["flavor"], "OgnTracker");
+        assert_eq!(migrated["status_comment"]["voltage"], 3.3);
+        assert_eq!(migrated["status_comment"]["tracker"]["satellites"], 9);
+        assert_eq!(migrated["status_comment"]["tracker"]["pressure"], 1002.5);
+        assert_eq!(migrated["status_comment"]["unparsed"], Value::Null);
+
+        let record = serde_json::from_str::<Value>(
+            r#"{"aprs_packet": {"to": {"call": "OGNSDR", "ssid": null}}, "status_comment": {"voltage": 3.3}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["flavor"], "Receiver");
+        assert_eq!(migrated["status_comment"]["voltage"], 3.3);
+        assert_eq!(migrated["status_comment"]["tracker"], Value::Null);
+
+        let record = serde_json::from_str::<Value>(r#"{"parser_error": "x"}"#).unwrap();
+        assert_eq!(migrate(record).unwrap()["flavor"], Value::Null);
+    }
+
+    #[test]
+    fn test_migrate_unknown_data() {
+        let record = serde_json::from_str::<Value>(
+            r#"{"raw_string": "FLRDDFAA3>APRS,qAS,Letzi:T#005,199,000,255,073,123,01101001", "aprs_packet": {"data": "Unknown"}}"#,
+        )
+        .unwrap();
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["aprs_packet"]["data"]["Telemetry"]["sequence"], 5);
+
+        let record = serde_json::json!({"raw_string": "FLRDDFAA3>APRS,qAS,Letzi:)AID #2!4903.50N/07201.75WA Aid station", "aprs_packet": {"data": "Unknown"}});
+        let migrated = migrate(record).unwrap();
+        assert_eq!(migrated["aprs_packet"]["data"]["Item"]["name"], "AID #2");
+
+        let record = serde_json::json!({"raw_string": r"FNB1103CE>OGNFNT,qAS,Letzi:}FNT1103CE>OGNFNT,TCPIP,FNB1103CE*:/183734h5057.94N/00801.00Eg355/002/A=001042", "aprs_packet": {"data": "Unknown"}});
+        let migrated = migrate(record).unwrap();
+        assert_eq!(
+            migrated["aprs_packet"]["data"]["ThirdParty"]["from"]["call"],
+            "FNT1103CE"
+        );
+
+        for raw_string in [
+            "FLRDDFAA3>APRS,qAS,Letzi:Test",
+            "FLRDDFAA3>APRS,qAS,Letzi:T#x",
+        ] {
+            let record =
+                serde_json::json!({"raw_string": raw_string, "aprs_packet": {"data": "Unknown"}});
+            assert_eq!(migrate(record).unwrap()["aprs_packet"]["data"], "Unknown");
+        }
+    }
+
+    #[test]
+    fn test_migrate_to() {
+        let record = serde_json::from_str::<Value>(r#"{"raw_string": ""}"#).unwrap();
+        assert_eq!(migrate_to(record.clone(), 0), Ok(record.clone()));
+        let migrated = migrate_to(record, 1).unwrap();
+        assert_eq!(schema_version(&migrated), Ok(1));
+        assert_eq!(migrate_to(migrated.clone(), 1), Ok(migrated));
+    }
+
+    #[test]
+    fn test_migrate_errors() {
+        assert_eq!(
+            migrate(Value::from("no record")),
+            Err(SchemaError::NotAnObject)
+        );
+        assert_eq!(
+            migrate(serde_json::from_str(r#"{"schema_version": "one"}"#).unwrap()),
+            Err(SchemaError::InvalidVersion(Value::from("one")))
+        );
+        assert_eq!(
+            migrate(serde_json::from_str(r#"{"schema_version": 99}"#).unwrap()),
+            Err(SchemaError::UnsupportedVersion(99))
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt::{Display, Formatter};
+use std::{convert::Infallible, str::FromStr};
+
+use serde::Serialize;
+
+use crate::time::{civil_from_days, days_from_civil};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// A comment line of the APRS server, e.g. the keepalive sent every 20 seconds:
+// # aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152
+// Other comments like the login response only have the text.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct ServerComment {
+    pub software: Option<String>,
+    pub version: Option<String>,
+    // Seconds since 1970-01-01
+    pub timestamp: Option<i64>,
+    pub server: Option<String>,
+    // address:port the client is connected to
+    pub endpoint: Option<String>,
+    // Everything after "#"
+    pub text: String,
+}
+
+impl ServerComment {
+    // The keepalive the servers send every 20 seconds, timestamp in seconds since 1970-01-01
+    pub fn keepalive(
+        software: &str,
+        version: &str,
+        timestamp: i64,
+        server: &str,
+        endpoint: &str,
+    ) -> ServerComment {
+        let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+        let seconds = timestamp.rem_euclid(86400);
+        ServerComment {
+            software: Some(software.to_string()),
+            version: Some(version.to_string()),
+            timestamp: Some(timestamp),
+            server: Some(server.to_string()),
+            endpoint: Some(endpoint.to_string()),
+            text: format!(
+                "{} {} {:02} {} {} {:02}:{:02}:{:02} GMT {} {}",
+                software,
+                version,
+                day,
+                MONTHS[month as usize - 1],
+                year,
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60,
+                server,
+                endpoint
+            ),
+        }
+    }
+
+    // The answer to the login line of a client
+    pub fn logresp(user: &str, verified: bool, server: &str) -> ServerComment {
+        let status = if verified { "verified" } else { "unverified" };
+        ServerComment {
+            software: None,
+            version: None,
+            timestamp: None,
+            server: None,
+            endpoint: None,
+            text: format!("logresp {} {}, server {}", user, status, server),
+        }
+    }
+}
+
+impl Display for ServerComment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "# {}", self.text)
+    }
+}
+
+impl FromStr for ServerComment {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.strip_prefix('#').unwrap_or(s).trim();
+        let mut server_comment = ServerComment {
+            software: None,
+            version: None,
+            timestamp: None,
+            server: None,
+            endpoint: None,
+            text: text.to_string(),
+        };
+        let parts = text.split_whitespace().collect::<Vec<_>>();
+        if let [software, version, day, month, year, time, "GMT", server, endpoint] = parts[..] {
+            if let Some(timestamp) = parse_timestamp(day, month, year, time) {
+                server_comment.software = Some(software.to_string());
+                server_comment.version = Some(version.to_string());
+                server_comment.timestamp = Some(timestamp);
+                server_comment.server = Some(server.to_string());
+                server_comment.endpoint = Some(endpoint.to_string());
+            }
+        }
+        Ok(server_comment)
+    }
+}
+
+// "29 Jun 2024 10:05:37"
+fn parse_timestamp(day: &str, month: &str, year: &str, time: &str) -> Option<i64> {
+    let day = day
+        .parse::<u32>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u32 + 1;
+    let year = year.parse::<i64>().ok()?;
+    let mut clock = time.splitn(3, ':').map(|value| value.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (clock.next()??, clock.next()??, clock.next()??);
+    if hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_comment() {
+        let server_comment =
+            "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152"
+                .parse::<ServerComment>()
+                .unwrap();
+        assert_eq!(
+            server_comment,
+            ServerComment {
+                software: Some("aprsc".to_string()),
+                version: Some("2.1.15-gc67551b".to_string()),
+                timestamp: Some(1719655537),
+                server: Some("GLIDERN3".to_string()),
+                endpoint: Some("51.178.19.212:10152".to_string()),
+                text: "aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152"
+                    .to_string(),
+            }
+        );
+
+        let server_comment = "# logresp N0CALL unverified, server GLIDERN3"
+            .parse::<ServerComment>()
+            .unwrap();
+        assert_eq!(server_comment.software, None);
+        assert_eq!(server_comment.timestamp, None);
+        assert_eq!(
+            server_comment.text,
+            "logresp N0CALL unverified, server GLIDERN3"
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let keepalive = ServerComment::keepalive(
+            "aprsc",
+            "2.1.15-gc67551b",
+            1719655537,
+            "GLIDERN3",
+            "51.178.19.212:10152",
+        );
+        assert_eq!(
+            keepalive.to_string(),
+            "# aprsc 2.1.15-gc67551b 29 Jun 2024 10:05:37 GMT GLIDERN3 51.178.19.212:10152"
+        );
+        assert_eq!(
+            keepalive.to_string().parse::<ServerComment>().unwrap(),
+            keepalive
+        );
+        // 2024-07-01 00:00:05
+        let keepalive =
+            ServerComment::keepalive("aprsc", "2.1.15", 1719792005, "MOCK", "::1:14580");
+        assert_eq!(
+            keepalive.text,
+            "aprsc 2.1.15 01 Jul 2024 00:00:05 GMT MOCK ::1:14580"
+        );
+        assert_eq!(
+            keepalive.text.parse::<ServerComment>().unwrap().timestamp,
+            Some(1719792005)
+        );
+
+        let logresp = ServerComment::logresp("N0CALL", true, "GLIDERN3");
+        assert_eq!(
+            logresp.to_string(),
+            "# logresp N0CALL verified, server GLIDERN3"
+        );
+        assert_eq!(
+            logresp.to_string().parse::<ServerComment>().unwrap(),
+            logresp
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cursor::FieldCursor;
+use crate::error::AprsError;
+use crate::status_comment::StatusComment;
+use crate::timestamp::Timestamp;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AprsStatus {
+    pub timestamp: Option<Timestamp>,
+    pub comment: String,
+}
+
+impl AprsStatus {
+    // The status of a receiver, written in the layout of the receiver software
+    pub fn from_status_comment(
+        timestamp: Option<Timestamp>,
+        status_comment: &StatusComment,
+    ) -> AprsStatus {
+        AprsStatus {
+            timestamp,
+            comment: format!(" {}", status_comment),
+        }
+    }
+}
+
+impl FromStr for AprsStatus {
+    type Err = AprsError;
+
+    // Parses the information field without the data type identifier '>',
+    // the timestamp is optional
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cursor = FieldCursor::new(s);
+        match cursor
+            .take_n(7)
+            .and_then(|timestamp| timestamp.parse::<Timestamp>().ok())
+        {
+            Some(timestamp) => Ok(AprsStatus {
+                timestamp: Some(timestamp),
+                comment: cursor.rest().to_string(),
+            }),
+            None => Ok(AprsStatus {
+                timestamp: None,
+                comment: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for AprsStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, ">")?;
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, "{}", timestamp)?;
+        }
+        write!(f, "{}", self.comment)
+    }
+}
+
+#[test]
+fn test_status() {
+    let status = "312359zStatus seems okay!".parse::<AprsStatus>().unwrap();
+    assert_eq!(status.timestamp, Some(Timestamp::DDHHMM(31, 23, 59)));
+    assert_eq!(status.comment, "Status seems okay!");
+    assert_eq!(status.to_string(), ">312359zStatus seems okay!");
+
+    let status = "v0.2.7.RPI-GPU CPU:0.7".parse::<AprsStatus>().unwrap();
+    assert_eq!(status.timestamp, None);
+    assert_eq!(status.comment, "v0.2.7.RPI-GPU CPU:0.7");
+    assert_eq!(status.to_string(), ">v0.2.7.RPI-GPU CPU:0.7");
+
+    // Not a timestamp, so part of the comment
+    let status = "312359xStatus".parse::<AprsStatus>().unwrap();
+    assert_eq!(status.timestamp, None);
+    assert_eq!(status.comment, "312359xStatus");
+}
+
+#[test]
+fn test_from_status_comment() {
+    let comment = "v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C";
+    let status = AprsStatus::from_status_comment(
+        Some(Timestamp::HHMMSS(7, 46, 0)),
+        &comment.parse::<StatusComment>().unwrap(),
+    );
+    assert_eq!(
+        status.to_string(),
+        ">074600h v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C"
+    );
+    assert_eq!(status.to_string()[1..].parse::<AprsStatus>(), Ok(status));
+}
+
+#[test]
+fn test_deserialize() {
+    let status = "312359zStatus seems okay!".parse::<AprsStatus>().unwrap();
+    let json = serde_json::to_string(&status).unwrap();
+    assert_eq!(
+        json,
+        r#"{"timestamp":{"DDHHMM":[31,23,59]},"comment":"Status seems okay!"}"#
+    );
+    assert_eq!(serde_json::from_str::<AprsStatus>(&json).unwrap(), status);
+    assert_eq!(
+        serde_json::from_str::<Timestamp>(r#"{"Unsupported":"074849/"}"#).unwrap(),
+        Timestamp::Unsupported("074849/".into())
+    );
+}
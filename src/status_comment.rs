@@ -1,18 +1,78 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 use std::{convert::Infallible, str::FromStr};
 
-use crate::utils::{extract_values, split_value_unit};
+use crate::cursor::FieldCursor;
+use crate::grammar::{comment_grammar, decimal, digits, float, signed_decimal};
+use crate::utils::{extract_values, split_value_unit, Unit};
 
-#[derive(Debug, PartialEq, Default, Clone, Serialize)]
+// The normalized platform of a receiver, the raw platform strings vary a lot
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum PlatformKind {
+    RaspberryPi,
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    Other,
+}
+
+impl PlatformKind {
+    // e.g. RPI-GPU, x86_64, ARM, aarch64-linux
+    pub fn from_platform(platform: &str) -> PlatformKind {
+        let platform = platform.to_ascii_lowercase();
+        let starts_with =
+            |prefixes: &[&str]| prefixes.iter().any(|prefix| platform.starts_with(prefix));
+        if starts_with(&["rpi", "raspberry"]) {
+            PlatformKind::RaspberryPi
+        } else if starts_with(&["x86_64", "x86-64", "x64", "amd64"]) {
+            PlatformKind::X64
+        } else if starts_with(&["x86", "i386", "i486", "i586", "i686"]) {
+            PlatformKind::X86
+        } else if starts_with(&["aarch64", "arm64"]) {
+            PlatformKind::Arm64
+        } else if starts_with(&["arm"]) {
+            PlatformKind::Arm
+        } else {
+            PlatformKind::Other
+        }
+    }
+}
+
+// The status an OGN tracker sends in addition to the voltage, e.g.
+// h0D v01 12sat/0 98m 1006.3hPa +22.3degC 56% 3.98V 14/-115.0dBm 12/min
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
+pub struct TrackerStatus {
+    pub hardware_version: Option<u8>,
+    pub software_version: Option<u8>,
+    pub satellites: Option<u8>,
+    pub fix_quality: Option<u8>,
+    // GPS altitude in m
+    pub altitude: Option<i32>,
+    // Readings of the barometric sensor in hPa, °C and %
+    pub pressure: Option<f32>,
+    pub temperature: Option<f32>,
+    pub humidity: Option<u8>,
+    // Transmitter power and receiver noise in dBm
+    pub tx_power: Option<f32>,
+    pub rx_noise: Option<f32>,
+    // Received packets per minute
+    pub packet_rate: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
 pub struct StatusComment {
     pub version: Option<String>,
     pub platform: Option<String>,
+    pub platform_kind: Option<PlatformKind>,
     pub cpu_load: Option<f32>,
     pub ram_free: Option<f32>,
     pub ram_total: Option<f32>,
     pub ntp_offset: Option<f32>,
     pub ntp_correction: Option<f32>,
     pub voltage: Option<f32>,
+    // All voltage readings in the order of the comment, e.g. idle and under load
+    pub voltages: Option<Vec<f32>>,
     pub amperage: Option<f32>,
     pub cpu_temperature: Option<f32>,
     pub visible_senders: Option<u16>,
@@ -26,202 +86,528 @@ pub struct StatusComment {
     pub good_senders_signal_quality: Option<f32>,
     pub good_senders: Option<u16>,
     pub good_and_bad_senders: Option<u16>,
+    // Configuration of the RTL-SDR tuner, the measured corrections are in rf_correction_*
+    pub tuner_gain: Option<f32>,
+    pub frequency_correction: Option<f32>,
+    // The operator announced planned downtime, e.g. with "MAINT" in the comment
+    pub maintenance: bool,
+    // Only for the statuses of trackers
+    pub tracker: Option<TrackerStatus>,
     pub unparsed: Option<String>,
 }
 
 impl FromStr for StatusComment {
     type Err = Infallible;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut status_comment = StatusComment {
-            ..Default::default()
+        let mut status_comment = parse_modern(s);
+        if let (Some(unparsed), Some(legacy)) = (&status_comment.unparsed, parse_legacy(s)) {
+            let count = |unparsed: Option<&String>| unparsed.map_or(0, |s| s.split(' ').count());
+            if count(legacy.unparsed.as_ref()) < count(Some(unparsed)) {
+                status_comment = legacy;
+            }
+        }
+
+        crate::profiling::record(
+            "status",
+            &status_comment,
+            status_comment.unparsed.as_deref(),
+        );
+        Ok(status_comment)
+    }
+}
+
+// The keywords are matched case-insensitively against whole tokens
+pub const MAINTENANCE_KEYWORDS: [&str; 3] = ["MAINT", "MAINTENANCE", "TEST"];
+
+fn is_keyword<S: AsRef<str>>(part: &str, keywords: &[S]) -> bool {
+    keywords
+        .iter()
+        .any(|keyword| part.eq_ignore_ascii_case(keyword.as_ref()))
+}
+
+impl StatusComment {
+    // Sets the maintenance flag if one of the operator-defined keywords is in the
+    // unparsed tokens, the keyword is removed from them
+    pub fn apply_maintenance_keywords(&mut self, keywords: &[&str]) -> bool {
+        if let Some(unparsed) = &self.unparsed {
+            let (matched, rest): (Vec<_>, Vec<_>) = unparsed
+                .split(' ')
+                .partition(|part| is_keyword(part, keywords));
+            if !matched.is_empty() {
+                self.maintenance = true;
+                self.unparsed = (!rest.is_empty()).then(|| rest.join(" "));
+            }
+        }
+        self.maintenance
+    }
+
+    // Like apply_maintenance_keywords, but the comment is left as it is
+    pub fn is_maintenance<S: AsRef<str>>(&self, keywords: &[S]) -> bool {
+        self.maintenance
+            || self
+                .unparsed
+                .as_ref()
+                .is_some_and(|unparsed| unparsed.split(' ').any(|part| is_keyword(part, keywords)))
+    }
+
+    // Takes the tokens of the tracker layout from the unparsed tokens, the generic
+    // parser has already taken the voltage
+    pub(crate) fn parse_tracker_tokens(&mut self) {
+        let Some(unparsed) = self.unparsed.take() else {
+            return;
         };
-        let mut unparsed: Vec<_> = vec![];
-        for part in s.split_whitespace() {
-            if &part[0..1] == "v"
-                && part.matches('.').count() == 3
-                && status_comment.version.is_none()
+        let rest = unparsed
+            .split_ascii_whitespace()
+            .filter(|part| match_tracker_token(self, part) != Some(true))
+            .collect::<Vec<_>>();
+        self.unparsed = (!rest.is_empty()).then(|| rest.join(" "));
+    }
+}
+
+// Writes the modern layout of the receiver software, parsing it gives the same comment:
+// v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 7/8Acfts[1h] RF:+54-1.1ppm/-0.16dB
+impl Display for StatusComment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        match (&self.version, &self.platform) {
+            (Some(version), Some(platform)) => parts.push(format!("v{}.{}", version, platform)),
+            (Some(version), None) => parts.push(format!("v{}", version)),
+            _ => {}
+        }
+        if let Some(cpu_load) = self.cpu_load {
+            parts.push(format!("CPU:{}", cpu_load));
+        }
+        if let (Some(ram_free), Some(ram_total)) = (self.ram_free, self.ram_total) {
+            parts.push(format!("RAM:{}/{}MB", ram_free, ram_total));
+        }
+        if let (Some(ntp_offset), Some(ntp_correction)) = (self.ntp_offset, self.ntp_correction) {
+            parts.push(format!("NTP:{}ms/{}ppm", ntp_offset, ntp_correction));
+        }
+        if let Some(tracker) = &self.tracker {
+            if let Some(hardware_version) = tracker.hardware_version {
+                parts.push(format!("h{:02X}", hardware_version));
+            }
+            if let Some(software_version) = tracker.software_version {
+                parts.push(format!("v{:02X}", software_version));
+            }
+            if let (Some(satellites), Some(fix_quality)) = (tracker.satellites, tracker.fix_quality)
             {
-                let (first, second) = part
-                    .match_indices('.')
-                    .nth(2)
-                    .map(|(idx, _)| part.split_at(idx))
-                    .unwrap();
-                status_comment.version = Some(first[1..].into());
-                status_comment.platform = Some(second[1..].into());
-            } else if part.len() > 4
-                && part.starts_with("CPU:")
-                && status_comment.cpu_load.is_none()
+                parts.push(format!("{}sat/{}", satellites, fix_quality));
+            }
+            if let Some(altitude) = tracker.altitude {
+                parts.push(format!("{}m", altitude));
+            }
+            if let Some(pressure) = tracker.pressure {
+                parts.push(format!("{:.1}hPa", pressure));
+            }
+            if let Some(temperature) = tracker.temperature {
+                parts.push(format!("{:+.1}degC", temperature));
+            }
+            if let Some(humidity) = tracker.humidity {
+                parts.push(format!("{}%", humidity));
+            }
+        }
+        match &self.voltages {
+            Some(voltages) => parts.extend(voltages.iter().map(|voltage| format!("{}V", voltage))),
+            None => parts.extend(self.voltage.map(|voltage| format!("{}V", voltage))),
+        }
+        if let Some(amperage) = self.amperage {
+            parts.push(format!("{}A", amperage));
+        }
+        if let Some(cpu_temperature) = self.cpu_temperature {
+            parts.push(format!("{:+}C", cpu_temperature));
+        }
+        if let (Some(visible_senders), Some(senders)) = (self.visible_senders, self.senders) {
+            parts.push(format!("{}/{}Acfts[1h]", visible_senders, senders));
+        }
+        if let Some(latency) = self.latency {
+            parts.push(format!("Lat:{}s", latency));
+        }
+        if let Some(tuner_gain) = self.tuner_gain {
+            parts.push(format!("Gain:{}dB", tuner_gain));
+        }
+        if let Some(frequency_correction) = self.frequency_correction {
+            parts.push(format!("PPM:{:+}", frequency_correction));
+        }
+        if let (Some(manual), Some(automatic), Some(noise)) = (
+            self.rf_correction_manual,
+            self.rf_correction_automatic,
+            self.noise,
+        ) {
+            let mut rf = format!("RF:{:+}{:+}ppm/{:+}dB", manual, automatic, noise);
+            if let (Some(signal_quality), Some(messages)) =
+                (self.senders_signal_quality, self.senders_messages)
             {
-                if let Ok(cpu_load) = part[4..].parse::<f32>() {
-                    status_comment.cpu_load = Some(cpu_load);
-                } else {
-                    unparsed.push(part);
+                rf.push_str(&format!("/{:+}dB@10km[{}]", signal_quality, messages));
+                if let (Some(good_signal_quality), Some(good), Some(good_and_bad)) = (
+                    self.good_senders_signal_quality,
+                    self.good_senders,
+                    self.good_and_bad_senders,
+                ) {
+                    rf.push_str(&format!(
+                        "/{:+}dB@10km[{}/{}]",
+                        good_signal_quality, good, good_and_bad
+                    ));
                 }
-            } else if part.len() > 6
-                && part.starts_with("RAM:")
-                && part.ends_with("MB")
-                && part.find('/').is_some()
-                && status_comment.ram_free.is_none()
-            {
-                let subpart = &part[4..part.len() - 2];
-                let split_point = subpart.find('/').unwrap();
-                let (first, second) = subpart.split_at(split_point);
-                let ram_free = first.parse::<f32>().ok();
-                let ram_total = second[1..].parse::<f32>().ok();
-                if ram_free.is_some() && ram_total.is_some() {
-                    status_comment.ram_free = ram_free;
-                    status_comment.ram_total = ram_total;
-                } else {
-                    unparsed.push(part);
-                }
-            } else if part.len() > 6
-                && part.starts_with("NTP:")
-                && part.find('/').is_some()
-                && status_comment.ntp_offset.is_none()
-            {
-                let subpart = &part[4..part.len() - 3];
-                let split_point = subpart.find('/').unwrap();
-                let (first, second) = subpart.split_at(split_point);
-                let ntp_offset = first[0..first.len() - 2].parse::<f32>().ok();
-                let ntp_correction = second[1..].parse::<f32>().ok();
-                if ntp_offset.is_some() && ntp_correction.is_some() {
-                    status_comment.ntp_offset = ntp_offset;
-                    status_comment.ntp_correction = ntp_correction;
+            }
+            parts.push(rf);
+        }
+        if let Some(tracker) = &self.tracker {
+            if let (Some(tx_power), Some(rx_noise)) = (tracker.tx_power, tracker.rx_noise) {
+                parts.push(format!("{}/{:.1}dBm", tx_power, rx_noise));
+            }
+            if let Some(packet_rate) = tracker.packet_rate {
+                parts.push(format!("{}/min", packet_rate));
+            }
+        }
+        if self.maintenance {
+            parts.push(MAINTENANCE_KEYWORDS[0].to_string());
+        }
+        parts.extend(self.unparsed.clone());
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+comment_grammar! {
+    fn match_status_token(StatusComment) {
+        // CPU:load
+        ("CPU:", "", 1.., float) => cpu_load,
+        // Gain:gaindB
+        ("Gain:", "dB", 1.., decimal) => tuner_gain,
+        // PPM:correction, the configured frequency correction in ppm
+        ("PPM:", "", 1.., explicitly_signed_decimal) => frequency_correction,
+        // Lat:latencys
+        ("Lat:", "s", 1.., float) => latency,
+    }
+}
+
+comment_grammar! {
+    fn match_tracker_grammar(StatusComment) {
+        // hHH and vHH, the hex revisions of hardware and software
+        ("h", "", 2..=2, hex) => tracker.hardware_version,
+        ("v", "", 2..=2, hex) => tracker.software_version,
+        ("", "hPa", 1.., decimal) => tracker.pressure,
+        ("", "degC", 1.., explicitly_signed_decimal) => tracker.temperature,
+        ("", "%", 1..=3, digits, |humidity: &u8| *humidity <= 100) => tracker.humidity,
+        ("", "/min", 1.., digits) => tracker.packet_rate,
+        ("", "m", 1..=6, altitude) => tracker.altitude,
+    }
+}
+
+// The tokens with two values are matched before the grammar, "14/-110.5dBm"
+// would be taken as an invalid altitude
+fn match_tracker_token(status_comment: &mut StatusComment, part: &str) -> Option<bool> {
+    let tracker = status_comment.tracker.get_or_insert_with(Default::default);
+    let matched =
+        match_tracker_pair(tracker, part).or_else(|| match_tracker_grammar(status_comment, part));
+    if status_comment.tracker == Some(TrackerStatus::default()) {
+        status_comment.tracker = None;
+    }
+    matched
+}
+
+// satellites/fix quality, e.g. 9sat/1, and tx power/rx noise, e.g. 14/-110.5dBm
+fn match_tracker_pair(tracker: &mut TrackerStatus, part: &str) -> Option<bool> {
+    if let Some((satellites, fix_quality)) = part.split_once("sat/") {
+        if tracker.satellites.is_some() {
+            return None;
+        }
+        let (Some(satellites), Some(fix_quality)) = (digits(satellites), digits(fix_quality))
+        else {
+            return Some(false);
+        };
+        tracker.satellites = Some(satellites);
+        tracker.fix_quality = Some(fix_quality);
+        Some(true)
+    } else if let Some((tx_power, rx_noise)) = part
+        .strip_suffix("dBm")
+        .and_then(|powers| powers.split_once('/'))
+    {
+        if tracker.tx_power.is_some() {
+            return None;
+        }
+        let (Some(tx_power), Some(rx_noise)) = (signed_decimal(tx_power), signed_decimal(rx_noise))
+        else {
+            return Some(false);
+        };
+        tracker.tx_power = Some(tx_power);
+        tracker.rx_noise = Some(rx_noise);
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn explicitly_signed_decimal(raw: &str) -> Option<f32> {
+    signed_decimal(raw.strip_prefix('+').unwrap_or(raw))
+}
+
+fn hex(raw: &str) -> Option<u8> {
+    raw.bytes()
+        .all(|byte| byte.is_ascii_hexdigit())
+        .then(|| u8::from_str_radix(raw, 16).ok())
+        .flatten()
+}
+
+fn altitude(raw: &str) -> Option<i32> {
+    match raw.strip_prefix('-') {
+        Some(depth) => digits::<i32>(depth).map(|depth| -depth),
+        None => digits(raw),
+    }
+}
+
+fn parse_modern(s: &str) -> StatusComment {
+    let mut status_comment = StatusComment {
+        ..Default::default()
+    };
+    let mut unparsed: Vec<_> = vec![];
+    for part in s.split_whitespace() {
+        if part.starts_with('v')
+            && part.matches('.').count() == 3
+            && status_comment.version.is_none()
+        {
+            let (first, second) = part
+                .match_indices('.')
+                .nth(2)
+                .map(|(idx, _)| part.split_at(idx))
+                .unwrap();
+            status_comment.version = Some(first[1..].into());
+            status_comment.platform = Some(second[1..].into());
+            status_comment.platform_kind = Some(PlatformKind::from_platform(&second[1..]));
+        } else if let Some(parsed) = match_status_token(&mut status_comment, part) {
+            if !parsed {
+                unparsed.push(part);
+            }
+        } else if is_keyword(part, &MAINTENANCE_KEYWORDS) {
+            status_comment.maintenance = true;
+        } else if part.len() > 6
+            && part.starts_with("RAM:")
+            && part.ends_with("MB")
+            && part.find('/').is_some()
+            && status_comment.ram_free.is_none()
+        {
+            // RAM:free/totalMB
+            let mut cursor = FieldCursor::new(&part[4..]);
+            let ram_free = cursor
+                .take_until("/")
+                .and_then(|free| free.parse::<f32>().ok());
+            let ram_total = cursor
+                .take_until("MB")
+                .filter(|_| cursor.is_empty())
+                .and_then(|total| total.parse::<f32>().ok());
+            if ram_free.is_some() && ram_total.is_some() {
+                status_comment.ram_free = ram_free;
+                status_comment.ram_total = ram_total;
+            } else {
+                unparsed.push(part);
+            }
+        } else if part.len() > 6
+            && part.starts_with("NTP:")
+            && part.find('/').is_some()
+            && status_comment.ntp_offset.is_none()
+        {
+            // NTP:offsetms/correctionppm
+            let mut cursor = FieldCursor::new(&part[4..]);
+            let ntp_offset = cursor
+                .take_until("ms/")
+                .and_then(|offset| offset.parse::<f32>().ok());
+            let ntp_correction = cursor
+                .take_until("ppm")
+                .filter(|_| cursor.is_empty())
+                .and_then(|correction| correction.parse::<f32>().ok());
+            if ntp_offset.is_some() && ntp_correction.is_some() {
+                status_comment.ntp_offset = ntp_offset;
+                status_comment.ntp_correction = ntp_correction;
+            } else {
+                unparsed.push(part);
+            }
+        } else if part.len() >= 11
+            && part.ends_with("Acfts[1h]")
+            && part.find('/').is_some()
+            && status_comment.visible_senders.is_none()
+        {
+            // visible/totalAcfts[1h]
+            let mut cursor = FieldCursor::new(part);
+            let visible_senders = cursor
+                .take_until("/")
+                .and_then(|visible| visible.parse::<u16>().ok());
+            let senders = cursor
+                .take_until("Acfts[1h]")
+                .filter(|_| cursor.is_empty())
+                .and_then(|senders| senders.parse::<u16>().ok());
+            if visible_senders.is_some() && senders.is_some() {
+                status_comment.visible_senders = visible_senders;
+                status_comment.senders = senders;
+            } else {
+                unparsed.push(part);
+            }
+        } else if part.len() >= 11
+            && part.starts_with("RF:")
+            && status_comment.rf_correction_manual.is_none()
+        {
+            let values = extract_values(part);
+
+            if values.len() == 3 {
+                let rf_correction_manual = values[0].parse::<i16>().ok();
+                let rf_correction_automatic = values[1].parse::<f32>().ok();
+                let noise = values[2].parse::<f32>().ok();
+
+                if rf_correction_manual.is_some()
+                    && rf_correction_automatic.is_some()
+                    && noise.is_some()
+                {
+                    status_comment.rf_correction_manual = rf_correction_manual;
+                    status_comment.rf_correction_automatic = rf_correction_automatic;
+                    status_comment.noise = noise;
                 } else {
                     unparsed.push(part);
+                    continue;
                 }
-            } else if part.len() >= 11
-                && part.ends_with("Acfts[1h]")
-                && part.find('/').is_some()
-                && status_comment.visible_senders.is_none()
-            {
-                let subpart = &part[0..part.len() - 9];
-                let split_point = subpart.find('/').unwrap();
-                let (first, second) = subpart.split_at(split_point);
-                let visible_senders = first.parse::<u16>().ok();
-                let senders = second[1..].parse::<u16>().ok();
-                if visible_senders.is_some() && senders.is_some() {
-                    status_comment.visible_senders = visible_senders;
-                    status_comment.senders = senders;
-                } else {
-                    unparsed.push(part);
-                }
-            } else if part.len() > 5
-                && part.starts_with("Lat:")
-                && part.ends_with("s")
-                && status_comment.latency.is_none()
-            {
-                let latency = part[4..part.len() - 1].parse::<f32>().ok();
-                if latency.is_some() {
-                    status_comment.latency = latency;
+            } else if values.len() == 6 {
+                let rf_correction_manual = values[0].parse::<i16>().ok();
+                let rf_correction_automatic = values[1].parse::<f32>().ok();
+                let noise = values[2].parse::<f32>().ok();
+                let senders_signal_quality = values[3].parse::<f32>().ok();
+                let senders_messages = values[5].parse::<u32>().ok();
+                if rf_correction_manual.is_some()
+                    && rf_correction_automatic.is_some()
+                    && noise.is_some()
+                    && senders_signal_quality.is_some()
+                    && senders_messages.is_some()
+                {
+                    status_comment.rf_correction_manual = rf_correction_manual;
+                    status_comment.rf_correction_automatic = rf_correction_automatic;
+                    status_comment.noise = noise;
+                    status_comment.senders_signal_quality = senders_signal_quality;
+                    status_comment.senders_messages = senders_messages;
                 } else {
                     unparsed.push(part);
+                    continue;
                 }
-            } else if part.len() >= 11
-                && part.starts_with("RF:")
-                && status_comment.rf_correction_manual.is_none()
-            {
-                let values = extract_values(part);
-
-                if values.len() == 3 {
-                    let rf_correction_manual = values[0].parse::<i16>().ok();
-                    let rf_correction_automatic = values[1].parse::<f32>().ok();
-                    let noise = values[2].parse::<f32>().ok();
-
-                    if rf_correction_manual.is_some()
-                        && rf_correction_automatic.is_some()
-                        && noise.is_some()
-                    {
-                        status_comment.rf_correction_manual = rf_correction_manual;
-                        status_comment.rf_correction_automatic = rf_correction_automatic;
-                        status_comment.noise = noise;
-                    } else {
-                        unparsed.push(part);
-                        continue;
-                    }
-                } else if values.len() == 6 {
-                    let rf_correction_manual = values[0].parse::<i16>().ok();
-                    let rf_correction_automatic = values[1].parse::<f32>().ok();
-                    let noise = values[2].parse::<f32>().ok();
-                    let senders_signal_quality = values[3].parse::<f32>().ok();
-                    let senders_messages = values[5].parse::<u32>().ok();
-                    if rf_correction_manual.is_some()
-                        && rf_correction_automatic.is_some()
-                        && noise.is_some()
-                        && senders_signal_quality.is_some()
-                        && senders_messages.is_some()
-                    {
-                        status_comment.rf_correction_manual = rf_correction_manual;
-                        status_comment.rf_correction_automatic = rf_correction_automatic;
-                        status_comment.noise = noise;
-                        status_comment.senders_signal_quality = senders_signal_quality;
-                        status_comment.senders_messages = senders_messages;
-                    } else {
-                        unparsed.push(part);
-                        continue;
-                    }
-                } else if values.len() == 10 {
-                    let rf_correction_manual = values[0].parse::<i16>().ok();
-                    let rf_correction_automatic = values[1].parse::<f32>().ok();
-                    let noise = values[2].parse::<f32>().ok();
-                    let senders_signal_quality = values[3].parse::<f32>().ok();
-                    let senders_messages = values[5].parse::<u32>().ok();
-                    let good_senders_signal_quality = values[6].parse::<f32>().ok();
-                    let good_senders = values[8].parse::<u16>().ok();
-                    let good_and_bad_senders = values[9].parse::<u16>().ok();
-                    if rf_correction_manual.is_some()
-                        && rf_correction_automatic.is_some()
-                        && noise.is_some()
-                        && senders_signal_quality.is_some()
-                        && senders_messages.is_some()
-                        && good_senders_signal_quality.is_some()
-                        && good_senders.is_some()
-                        && good_and_bad_senders.is_some()
-                    {
-                        status_comment.rf_correction_manual = rf_correction_manual;
-                        status_comment.rf_correction_automatic = rf_correction_automatic;
-                        status_comment.noise = noise;
-                        status_comment.senders_signal_quality = senders_signal_quality;
-                        status_comment.senders_messages = senders_messages;
-                        status_comment.good_senders_signal_quality = good_senders_signal_quality;
-                        status_comment.good_senders = good_senders;
-                        status_comment.good_and_bad_senders = good_and_bad_senders;
-                    } else {
-                        unparsed.push(part);
-                        continue;
-                    }
+            } else if values.len() == 10 {
+                let rf_correction_manual = values[0].parse::<i16>().ok();
+                let rf_correction_automatic = values[1].parse::<f32>().ok();
+                let noise = values[2].parse::<f32>().ok();
+                let senders_signal_quality = values[3].parse::<f32>().ok();
+                let senders_messages = values[5].parse::<u32>().ok();
+                let good_senders_signal_quality = values[6].parse::<f32>().ok();
+                let good_senders = values[8].parse::<u16>().ok();
+                let good_and_bad_senders = values[9].parse::<u16>().ok();
+                if rf_correction_manual.is_some()
+                    && rf_correction_automatic.is_some()
+                    && noise.is_some()
+                    && senders_signal_quality.is_some()
+                    && senders_messages.is_some()
+                    && good_senders_signal_quality.is_some()
+                    && good_senders.is_some()
+                    && good_and_bad_senders.is_some()
+                {
+                    status_comment.rf_correction_manual = rf_correction_manual;
+                    status_comment.rf_correction_automatic = rf_correction_automatic;
+                    status_comment.noise = noise;
+                    status_comment.senders_signal_quality = senders_signal_quality;
+                    status_comment.senders_messages = senders_messages;
+                    status_comment.good_senders_signal_quality = good_senders_signal_quality;
+                    status_comment.good_senders = good_senders;
+                    status_comment.good_and_bad_senders = good_and_bad_senders;
                 } else {
                     unparsed.push(part);
                     continue;
                 }
-            } else if let Some((value, unit)) = split_value_unit(part) {
-                if unit == "C" && status_comment.cpu_temperature.is_none() {
+            } else {
+                unparsed.push(part);
+                continue;
+            }
+        } else if let Some((value, unit)) = split_value_unit(part) {
+            match Unit::from_suffix(unit) {
+                Some(Unit::Celsius) if status_comment.cpu_temperature.is_none() => {
                     status_comment.cpu_temperature = value.parse::<f32>().ok();
-                } else if unit == "V" && status_comment.voltage.is_none() {
-                    status_comment.voltage = value.parse::<f32>().ok();
-                } else if unit == "A" && status_comment.amperage.is_none() {
+                }
+                Some(Unit::Volt) => match value.parse::<f32>() {
+                    Ok(voltage) => {
+                        status_comment.voltage.get_or_insert(voltage);
+                        status_comment
+                            .voltages
+                            .get_or_insert_with(Vec::new)
+                            .push(voltage);
+                    }
+                    Err(_) => unparsed.push(part),
+                },
+                Some(Unit::Ampere) if status_comment.amperage.is_none() => {
                     status_comment.amperage = value.parse::<f32>().ok();
-                } else {
-                    unparsed.push(part);
                 }
-            } else {
-                unparsed.push(part);
+                _ => unparsed.push(part),
             }
-        }
-        status_comment.unparsed = if !unparsed.is_empty() {
-            Some(unparsed.join(" "))
         } else {
-            None
-        };
+            unparsed.push(part);
+        }
+    }
+    status_comment.unparsed = if !unparsed.is_empty() {
+        Some(unparsed.join(" "))
+    } else {
+        None
+    };
+    status_comment
+}
 
-        Ok(status_comment)
+// Receivers before v0.2.0 sent the version without platform and mixed case keys:
+// v0.1.4 Cpu:0.7 Ram:770.2/968.2MB Ntp:1.8ms/-3.3ppm +55.7C
+// The tokens are translated to the modern layout, None if there is no legacy token
+fn parse_legacy(s: &str) -> Option<StatusComment> {
+    let mut version = None;
+    let mut legacy = false;
+    let mut parts = vec![];
+    for part in s.split_whitespace() {
+        let is_version = part.len() > 5
+            && part.starts_with('v')
+            && part[1..].split('.').count() == 3
+            && part[1..]
+                .split('.')
+                .all(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()));
+        if is_version && version.is_none() {
+            version = Some(part[1..].to_string());
+            legacy = true;
+            continue;
+        }
+        let translated = ["Cpu:", "Ram:", "Ntp:"]
+            .iter()
+            .find(|prefix| part.starts_with(*prefix))
+            .map(|prefix| format!("{}{}", prefix.to_ascii_uppercase(), &part[4..]));
+        legacy |= translated.is_some();
+        parts.push(translated.unwrap_or_else(|| part.to_string()));
+    }
+    if !legacy {
+        return None;
     }
+    let mut status_comment = parse_modern(&parts.join(" "));
+    if status_comment.version.is_none() {
+        status_comment.version = version;
+    } else if let Some(version) = version {
+        // Both layouts, keep the legacy version token as unparsed
+        let unparsed = status_comment
+            .unparsed
+            .map_or(format!("v{}", version), |unparsed| {
+                format!("v{} {}", version, unparsed)
+            });
+        status_comment.unparsed = Some(unparsed);
+    }
+    Some(status_comment)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_deserialize() {
+        let result = "v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 7/8Acfts[1h] RF:+54-1.1ppm/-0.16dB/+7.1dB@10km[19481]/+16.8dB@10km[7/13] MAINT".parse::<StatusComment>().unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<StatusComment>(&json).unwrap(),
+            result
+        );
+    }
+
     #[test]
     fn test_sdr() {
         let result = "v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 7/8Acfts[1h] RF:+54-1.1ppm/-0.16dB/+7.1dB@10km[19481]/+16.8dB@10km[7/13]".parse::<StatusComment>().unwrap();
@@ -230,6 +616,7 @@ mod tests {
             StatusComment {
                 version: Some("0.2.7".into()),
                 platform: Some("RPI-GPU".into()),
+                platform_kind: Some(PlatformKind::RaspberryPi),
                 cpu_load: Some(0.7),
                 ram_free: Some(770.2),
                 ram_total: Some(968.2),
@@ -253,6 +640,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tracker() {
+        let comment = "h0D v01 12sat/0 98m 1006.3hPa +22.3degC 56% 3.98V 14/-115.0dBm 12/min";
+        let mut result = comment.parse::<StatusComment>().unwrap();
+        result.parse_tracker_tokens();
+        assert_eq!(
+            result,
+            StatusComment {
+                voltage: Some(3.98),
+                voltages: Some(vec![3.98]),
+                tracker: Some(TrackerStatus {
+                    hardware_version: Some(0x0D),
+                    software_version: Some(1),
+                    satellites: Some(12),
+                    fix_quality: Some(0),
+                    altitude: Some(98),
+                    pressure: Some(1006.3),
+                    temperature: Some(22.3),
+                    humidity: Some(56),
+                    tx_power: Some(14.0),
+                    rx_noise: Some(-115.0),
+                    packet_rate: Some(12),
+                }),
+                ..Default::default()
+            }
+        );
+        assert_eq!(result.to_string(), comment);
+
+        let mut result = "h00 v00 3.34V 1/min xyz".parse::<StatusComment>().unwrap();
+        result.parse_tracker_tokens();
+        assert_eq!(result.voltage, Some(3.34));
+        assert_eq!(result.tracker.unwrap().packet_rate, Some(1));
+        assert_eq!(result.unparsed, Some("xyz".into()));
+
+        let mut result = "3.34V xyz".parse::<StatusComment>().unwrap();
+        result.parse_tracker_tokens();
+        assert_eq!(result.tracker, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let comment = "v0.2.7.RPI-GPU CPU:0.7 RAM:770.2/968.2MB NTP:1.8ms/-3.3ppm +55.7C 7/8Acfts[1h] RF:+54-1.1ppm/-0.16dB/+7.1dB@10km[19481]/+16.8dB@10km[7/13]";
+        let result = comment.parse::<StatusComment>().unwrap();
+        assert_eq!(result.to_string(), comment);
+
+        for comment in [
+            "v0.2.6.ARM CPU:0.4 RAM:700.5/970.5MB NTP:0.5ms/+2.3ppm 4.048V 0.000A +38.5C Lat:0.02s RF:+0-1.1ppm/-0.16dB/+7.1dB@10km[19481]",
+            "v0.2.8.RPI-GPU Gain:48.0dB PPM:+52 RF:+51-0.3ppm/+1.94dB MAINT hello",
+            "v0.1.4 Cpu:0.7 Ram:770.2/968.2MB Ntp:1.8ms/-3.3ppm +55.7C",
+            "5.0V 4.8V",
+            "",
+        ] {
+            let result = comment.parse::<StatusComment>().unwrap();
+            assert_eq!(
+                result.to_string().parse::<StatusComment>().unwrap(),
+                result,
+                "{}",
+                comment
+            );
+        }
+        assert_eq!(
+            "v0.2.6.ARM 4.048V 0.000A"
+                .parse::<StatusComment>()
+                .unwrap()
+                .to_string(),
+            "v0.2.6.ARM 4.048V 0A"
+        );
+    }
+
     #[test]
     fn test_sdr_different_order() {
         let result = "NTP:1.8ms/-3.3ppm +55.7C CPU:0.7 RAM:770.2/968.2MB 7/8Acfts[1h] RF:+54-1.1ppm/-0.16dB/+7.1dB@10km[19481]/+16.8dB@10km[7/13] v0.2.7.RPI-GPU".parse::<StatusComment>().unwrap();
@@ -261,6 +717,7 @@ mod tests {
             StatusComment {
                 version: Some("0.2.7".into()),
                 platform: Some("RPI-GPU".into()),
+                platform_kind: Some(PlatformKind::RaspberryPi),
                 cpu_load: Some(0.7),
                 ram_free: Some(770.2),
                 ram_total: Some(968.2),
@@ -336,4 +793,126 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_platform_kind() {
+        for (platform, kind) in [
+            ("RPI-GPU", PlatformKind::RaspberryPi),
+            ("x86_64", PlatformKind::X64),
+            ("x86", PlatformKind::X86),
+            ("ARM", PlatformKind::Arm),
+            ("armv7l", PlatformKind::Arm),
+            ("aarch64-linux", PlatformKind::Arm64),
+            ("ARM64", PlatformKind::Arm64),
+            ("MIPS", PlatformKind::Other),
+        ] {
+            assert_eq!(PlatformKind::from_platform(platform), kind, "{}", platform);
+        }
+
+        let result = "v0.2.7.RPI-GPU".parse::<StatusComment>().unwrap();
+        assert_eq!(result.platform_kind, Some(PlatformKind::RaspberryPi));
+        let result = "CPU:0.7".parse::<StatusComment>().unwrap();
+        assert_eq!(result.platform_kind, None);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let result = "v0.1.4 Cpu:0.7 Ram:770.2/968.2MB Ntp:1.8ms/-3.3ppm +55.7C"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(
+            result,
+            StatusComment {
+                version: Some("0.1.4".into()),
+                cpu_load: Some(0.7),
+                ram_free: Some(770.2),
+                ram_total: Some(968.2),
+                ntp_offset: Some(1.8),
+                ntp_correction: Some(-3.3),
+                cpu_temperature: Some(55.7),
+                ..Default::default()
+            }
+        );
+
+        // The modern layout is kept if the legacy one doesn't parse more
+        let result = "v0.2.7.RPI-GPU CPU:0.7 foo"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(result.platform, Some("RPI-GPU".into()));
+        assert_eq!(result.unparsed, Some("foo".into()));
+        let result = "Cpu:abc".parse::<StatusComment>().unwrap();
+        assert_eq!(result.cpu_load, None);
+        assert_eq!(result.unparsed, Some("Cpu:abc".into()));
+    }
+
+    #[test]
+    fn test_tuner() {
+        let result = "v0.2.8.RPI-GPU Gain:48.0dB PPM:+52 RF:+51-0.3ppm/+1.94dB"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(result.tuner_gain, Some(48.0));
+        assert_eq!(result.frequency_correction, Some(52.0));
+        assert_eq!(result.rf_correction_manual, Some(51));
+        assert_eq!(result.unparsed, None);
+
+        let result = "PPM:-1.5".parse::<StatusComment>().unwrap();
+        assert_eq!(result.frequency_correction, Some(-1.5));
+
+        let result = "Gain:-4dB Gain:autodB PPM:+ PPM:inf"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(result.tuner_gain, None);
+        assert_eq!(result.frequency_correction, None);
+        assert_eq!(
+            result.unparsed,
+            Some("Gain:-4dB Gain:autodB PPM:+ PPM:inf".into())
+        );
+    }
+
+    #[test]
+    fn test_voltages() {
+        let result = "v0.2.7.RPI-GPU 4.12V 3.95V 0.52A"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(result.voltage, Some(4.12));
+        assert_eq!(result.voltages, Some(vec![4.12, 3.95]));
+        assert_eq!(result.amperage, Some(0.52));
+        assert_eq!(result.unparsed, None);
+
+        let result = "3.3V".parse::<StatusComment>().unwrap();
+        assert_eq!(result.voltages, Some(vec![3.3]));
+        let result = "v0.2.7.RPI-GPU".parse::<StatusComment>().unwrap();
+        assert_eq!(result.voltages, None);
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let result = "°v0.2.7.RPI-GPU NTP:1.8m°s/-3.3p°pm NTP:°/ +55.7°C 3.3V"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert_eq!(result.voltage, Some(3.3));
+        assert_eq!(
+            result.unparsed,
+            Some("°v0.2.7.RPI-GPU NTP:1.8m°s/-3.3p°pm NTP:°/ +55.7°C".into())
+        );
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let result = "v0.2.7.RPI-GPU CPU:0.7 maint"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert!(result.maintenance);
+        assert_eq!(result.unparsed, None);
+
+        let mut result = "v0.2.7.RPI-GPU CPU:0.7 WARTUNG antenna"
+            .parse::<StatusComment>()
+            .unwrap();
+        assert!(!result.maintenance);
+        assert!(!result.is_maintenance(&["OFFLINE"]));
+        assert!(result.is_maintenance(&["wartung".to_string()]));
+        assert!(!result.apply_maintenance_keywords(&["OFFLINE"]));
+        assert!(result.apply_maintenance_keywords(&["Wartung"]));
+        assert_eq!(result.unparsed, Some("antenna".into()));
+    }
 }
@@ -0,0 +1,149 @@
+use crate::callsign::Callsign;
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::pseudonym::Pseudonymizer;
+use crate::timestamp::Timestamp;
+
+// Re-encodes beacons for relaying to third parties while honouring the privacy flags of the id:
+// "notrack" beacons are dropped, "stealth" beacons lose their identity and their exact time.
+pub struct StealthEncoder {
+    pseudonymizer: Pseudonymizer,
+    time_resolution: u32,
+}
+
+impl StealthEncoder {
+    // key: of the HMAC of the pseudonyms, keep it secret and stable for consistent tracks
+    pub fn new(key: &[u8]) -> Self {
+        StealthEncoder {
+            pseudonymizer: Pseudonymizer::new(key),
+            time_resolution: 60,
+        }
+    }
+
+    // Timestamps of stealth beacons are rounded down to this many seconds
+    pub fn with_time_resolution(mut self, seconds: u32) -> Self {
+        self.time_resolution = seconds.max(1);
+        self
+    }
+
+    // Returns the packet to relay, or None if it must not be relayed at all
+    pub fn encode(&self, message: &Message) -> Option<String> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let id = message
+            .position_comment
+            .as_ref()
+            .and_then(|comment| comment.id.as_ref());
+        match id {
+            Some(id) if id.is_notrack => None,
+            Some(id) if id.is_stealth => {
+                // The sender can have any callsign, it is replaced by the one of the id,
+                // e.g. FLRDDFAA3, which gets the same pseudonym as the id
+                let mut packet = packet.clone();
+                packet.from = Callsign::new(id.device_id(), None);
+                if let AprsData::Position(position) = &mut packet.data {
+                    position.timestamp = position
+                        .timestamp
+                        .as_ref()
+                        .map(|timestamp| self.degrade(timestamp));
+                }
+                Some(self.pseudonymizer.line(&packet.to_string()))
+            }
+            _ => Some(message.raw_string.clone()),
+        }
+    }
+
+    fn degrade(&self, timestamp: &Timestamp) -> Timestamp {
+        match *timestamp {
+            Timestamp::HHMMSS(hours, minutes, seconds) => {
+                let seconds_of_day = hours as u32 * 3600 + minutes as u32 * 60 + seconds as u32;
+                let rounded = seconds_of_day - seconds_of_day % self.time_resolution;
+                Timestamp::HHMMSS(
+                    (rounded / 3600) as u8,
+                    (rounded / 60 % 60) as u8,
+                    (rounded % 60) as u8,
+                )
+            }
+            Timestamp::DDHHMM(day, hours, minutes) => {
+                let resolution = (self.time_resolution / 60).max(1);
+                let minutes_of_day = hours as u32 * 60 + minutes as u32;
+                let rounded = minutes_of_day - minutes_of_day % resolution;
+                Timestamp::DDHHMM(day, (rounded / 60) as u8, (rounded % 60) as u8)
+            }
+            Timestamp::Unsupported(_) => timestamp.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beacon(id: &str) -> Message {
+        beacon_from("FLRDDFAA3", id)
+    }
+
+    fn beacon_from(callsign: &str, id: &str) -> Message {
+        format!(
+            r"{}>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id{}DDFAA3 -019fpm",
+            callsign, id
+        )
+        .parse::<Message>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_regular_beacons_are_unchanged() {
+        let message = beacon("06");
+        let encoder = StealthEncoder::new(b"secret");
+        assert_eq!(encoder.encode(&message), Some(message.raw_string.clone()));
+    }
+
+    #[test]
+    fn test_notrack_beacons_are_dropped() {
+        // 0x46: notrack flag
+        assert_eq!(StealthEncoder::new(b"secret").encode(&beacon("46")), None);
+    }
+
+    #[test]
+    fn test_stealth_beacons_are_degraded() {
+        // 0x86: stealth flag
+        let encoder = StealthEncoder::new(b"secret").with_time_resolution(300);
+        let encoded = encoder.encode(&beacon("86")).unwrap();
+        assert!(!encoded.contains("DDFAA3"));
+        assert!(encoded.contains("/074500h5111.32N"));
+
+        // The pseudonym is stable, so the track stays consistent
+        let message = encoded.parse::<Message>().unwrap();
+        let callsign = message.aprs_packet.unwrap().from.call;
+        // The HMAC pseudonym of DDFAA3 with the key
+        assert_eq!(callsign, "FLR6A198F");
+        assert!(message
+            .position_comment
+            .unwrap()
+            .id
+            .is_some_and(|id| id.is_stealth && format!("FLR{:06X}", id.address) == callsign));
+        assert_eq!(encoder.encode(&beacon("86")), Some(encoded.clone()));
+
+        // Other callsigns are replaced by the pseudonym of the id too
+        for callsign in ["HBKOF", "FLRDDFAA3-2", "ICA4B0E3C"] {
+            assert_eq!(
+                encoder.encode(&beacon_from(callsign, "86")).as_ref(),
+                Some(&encoded)
+            );
+        }
+    }
+
+    #[test]
+    fn test_degrade() {
+        let encoder = StealthEncoder::new(b"");
+        assert_eq!(
+            encoder.degrade(&Timestamp::HHMMSS(23, 59, 59)),
+            Timestamp::HHMMSS(23, 59, 0)
+        );
+        let encoder = encoder.with_time_resolution(600);
+        assert_eq!(
+            encoder.degrade(&Timestamp::DDHHMM(31, 23, 59)),
+            Timestamp::DDHHMM(31, 23, 50)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use crate::track::Fix;
+
+const MAGIC: &[u8; 4] = b"OGNF";
+const VERSION: u8 = 1;
+
+// Latitude and longitude are stored in micro degrees (about 0.1 m)
+const COORDINATE_SCALE: f64 = 1_000_000.0;
+
+// Longer aircraft names are rejected, so a corrupt length can't allocate without bound
+const MAX_AIRCRAFT_LENGTH: usize = 255;
+
+const FLAG_ALTITUDE: u8 = 0b0000_0001;
+const FLAG_COURSE: u8 = 0b0000_0010;
+const FLAG_SPEED: u8 = 0b0000_0100;
+const FLAG_CLIMB_RATE: u8 = 0b0000_1000;
+const FLAG_SYNTHETIC: u8 = 0b0001_0000;
+
+// The previous fix of an aircraft in the stored (quantized) units
+#[derive(Default, Clone, Copy)]
+struct State {
+    time: i64,
+    latitude: i64,
+    longitude: i64,
+    altitude: i64,
+}
+
+// Compact archive format: every fix is stored as the difference to the previous fix of the
+// same aircraft, variable length encoded. Aircraft names are only written on first sight.
+// Altitude, course, speed and climb rate are rounded to whole units of the beacons.
+pub struct FixEncoder<W: Write> {
+    writer: W,
+    aircraft: HashMap<String, (u64, State)>,
+}
+
+impl<W: Write> FixEncoder<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(FixEncoder {
+            writer,
+            aircraft: HashMap::new(),
+        })
+    }
+
+    pub fn write(&mut self, aircraft: &str, fix: &Fix) -> io::Result<()> {
+        let mut buffer = vec![];
+        let next_id = self.aircraft.len() as u64;
+        let (id, previous) = match self.aircraft.get(aircraft) {
+            Some((id, previous)) => (*id, *previous),
+            None if aircraft.len() > MAX_AIRCRAFT_LENGTH => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Aircraft name too long",
+                ));
+            }
+            None => {
+                write_varint(&mut buffer, next_id);
+                write_varint(&mut buffer, aircraft.len() as u64);
+                buffer.extend_from_slice(aircraft.as_bytes());
+                (next_id, State::default())
+            }
+        };
+        if id != next_id {
+            write_varint(&mut buffer, id);
+        }
+
+        let current = State {
+            time: fix.time,
+            latitude: (fix.latitude * COORDINATE_SCALE).round() as i64,
+            longitude: (fix.longitude * COORDINATE_SCALE).round() as i64,
+            altitude: fix
+                .altitude
+                .map_or(previous.altitude, |altitude| altitude.round() as i64),
+        };
+        let flags = [
+            (fix.altitude.is_some(), FLAG_ALTITUDE),
+            (fix.course.is_some(), FLAG_COURSE),
+            (fix.speed.is_some(), FLAG_SPEED),
+            (fix.climb_rate.is_some(), FLAG_CLIMB_RATE),
+            (fix.synthetic, FLAG_SYNTHETIC),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+        buffer.push(flags);
+        write_signed(&mut buffer, current.time - previous.time);
+        write_signed(&mut buffer, current.latitude - previous.latitude);
+        write_signed(&mut buffer, current.longitude - previous.longitude);
+        if fix.altitude.is_some() {
+            write_signed(&mut buffer, current.altitude - previous.altitude);
+        }
+        for value in [fix.course, fix.speed, fix.climb_rate]
+            .into_iter()
+            .flatten()
+        {
+            write_signed(&mut buffer, value.round() as i64);
+        }
+
+        self.writer.write_all(&buffer)?;
+        self.aircraft.insert(aircraft.to_string(), (id, current));
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct FixDecoder<R: Read> {
+    reader: R,
+    aircraft: Vec<(String, State)>,
+}
+
+impl<R: Read> FixDecoder<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid_data("Not a fix archive of a supported version"));
+        }
+        Ok(FixDecoder {
+            reader,
+            aircraft: vec![],
+        })
+    }
+
+    fn read_fix(&mut self, id: u64) -> io::Result<(String, Fix)> {
+        let id = id as usize;
+        if id == self.aircraft.len() {
+            let length = read_varint(&mut self.reader)?.ok_or_else(truncated)?;
+            if length > MAX_AIRCRAFT_LENGTH as u64 {
+                return Err(invalid_data("Aircraft name too long"));
+            }
+            let mut name = vec![0; length as usize];
+            self.reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| invalid_data("Invalid aircraft"))?;
+            self.aircraft.push((name, State::default()));
+        } else if id > self.aircraft.len() {
+            return Err(invalid_data("Unknown aircraft"));
+        }
+
+        let reader = &mut self.reader;
+        let mut flags = [0];
+        reader.read_exact(&mut flags)?;
+        let flags = flags[0];
+        let (name, previous) = &self.aircraft[id];
+        let mut current = State {
+            time: add_delta(previous.time, read_signed(reader)?)?,
+            latitude: add_delta(previous.latitude, read_signed(reader)?)?,
+            longitude: add_delta(previous.longitude, read_signed(reader)?)?,
+            altitude: previous.altitude,
+        };
+        let mut optional = |flag: u8| -> io::Result<Option<i64>> {
+            match flags & flag {
+                0 => Ok(None),
+                _ => read_signed(reader).map(Some),
+            }
+        };
+        let altitude = optional(FLAG_ALTITUDE)?
+            .map(|delta| add_delta(previous.altitude, delta))
+            .transpose()?;
+        let course = optional(FLAG_COURSE)?;
+        let speed = optional(FLAG_SPEED)?;
+        let climb_rate = optional(FLAG_CLIMB_RATE)?;
+        if let Some(altitude) = altitude {
+            current.altitude = altitude;
+        }
+
+        let fix = Fix {
+            time: current.time,
+            latitude: current.latitude as f64 / COORDINATE_SCALE,
+            longitude: current.longitude as f64 / COORDINATE_SCALE,
+            altitude: altitude.map(|altitude| altitude as f64),
+            course: course.map(|course| course as f64),
+            speed: speed.map(|speed| speed as f64),
+            climb_rate: climb_rate.map(|climb_rate| climb_rate as f64),
+            synthetic: flags & FLAG_SYNTHETIC != 0,
+        };
+        let name = name.clone();
+        self.aircraft[id].1 = current;
+        Ok((name, fix))
+    }
+}
+
+impl<R: Read> Iterator for FixDecoder<R> {
+    type Item = io::Result<(String, Fix)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match read_varint(&mut self.reader) {
+            Ok(Some(id)) => Some(self.read_fix(id)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+// Zigzag encoding, so small negative values stay small
+fn write_signed(buffer: &mut Vec<u8>, value: i64) {
+    write_varint(buffer, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+// None at the end of the stream
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<Option<u64>> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+            return match shift {
+                0 => Ok(None),
+                _ => Err(truncated()),
+            };
+        }
+        value |= u64::from(byte[0] & 0x7F) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    Err(invalid_data("Varint too long"))
+}
+
+fn read_signed<R: Read>(reader: &mut R) -> io::Result<i64> {
+    let value = read_varint(reader)?.ok_or_else(truncated)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+// Corrupt deltas can overflow
+fn add_delta(previous: i64, delta: i64) -> io::Result<i64> {
+    previous
+        .checked_add(delta)
+        .ok_or_else(|| invalid_data("Delta out of range"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated fix archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixes() -> Vec<(String, Fix)> {
+        (0..1000)
+            .map(|idx| {
+                let aircraft = format!("FLRDDFAA{}", idx % 3);
+                let fix = Fix {
+                    time: 1722498348 + idx,
+                    latitude: 48.0 + idx as f64 * 0.0001,
+                    longitude: -11.0 - idx as f64 * 0.0002,
+                    altitude: (idx % 10 != 0).then_some(1000.0 + (idx % 50) as f64),
+                    course: Some((idx % 360) as f64),
+                    speed: Some(80.0),
+                    climb_rate: Some(-((idx % 7) as f64) * 20.0),
+                    synthetic: idx % 5 == 0,
+                };
+                (aircraft, fix)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let fixes = fixes();
+        let mut encoder = FixEncoder::new(vec![]).unwrap();
+        for (aircraft, fix) in &fixes {
+            encoder.write(aircraft, fix).unwrap();
+        }
+        let encoded = encoder.into_inner();
+
+        let decoded = FixDecoder::new(encoded.as_slice())
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(decoded.len(), fixes.len());
+        for ((aircraft, fix), (decoded_aircraft, decoded_fix)) in fixes.iter().zip(&decoded) {
+            assert_eq!(aircraft, decoded_aircraft);
+            assert!((fix.latitude - decoded_fix.latitude).abs() < 1e-6);
+            assert!((fix.longitude - decoded_fix.longitude).abs() < 1e-6);
+            assert_eq!(
+                (
+                    fix.time,
+                    fix.altitude,
+                    fix.course,
+                    fix.speed,
+                    fix.climb_rate
+                ),
+                (
+                    decoded_fix.time,
+                    decoded_fix.altitude,
+                    decoded_fix.course,
+                    decoded_fix.speed,
+                    decoded_fix.climb_rate
+                )
+            );
+            assert_eq!(fix.synthetic, decoded_fix.synthetic);
+        }
+
+        let ndjson = fixes
+            .iter()
+            .map(|(_, fix)| serde_json::to_string(fix).unwrap() + "\n")
+            .collect::<String>();
+        assert!(encoded.len() * 5 < ndjson.len());
+    }
+
+    #[test]
+    fn test_invalid_archives() {
+        assert!(FixDecoder::new(&b"NDJSON"[..]).is_err());
+
+        let mut encoder = FixEncoder::new(vec![]).unwrap();
+        encoder.write("FLRDDFAA3", &fixes()[0].1).unwrap();
+        let encoded = encoder.into_inner();
+        let result = FixDecoder::new(&encoded[..encoded.len() - 1])
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let decode = |body: &[u8]| {
+            let archive = [&MAGIC[..], &[VERSION], body].concat();
+            FixDecoder::new(archive.as_slice())
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap_err()
+                .kind()
+        };
+        // A new aircraft with a name of u64::MAX bytes
+        let mut body = vec![0];
+        write_varint(&mut body, u64::MAX);
+        assert_eq!(decode(&body), io::ErrorKind::InvalidData);
+
+        // Two fixes with time deltas of i64::MAX
+        let mut body = vec![0, 1, b'A', 0];
+        write_signed(&mut body, i64::MAX);
+        body.extend_from_slice(&[0, 0, 0, 0]);
+        write_signed(&mut body, i64::MAX);
+        body.extend_from_slice(&[0, 0]);
+        assert_eq!(decode(&body), io::ErrorKind::InvalidData);
+
+        let mut encoder = FixEncoder::new(vec![]).unwrap();
+        let name = "A".repeat(MAX_AIRCRAFT_LENGTH + 1);
+        assert!(encoder.write(&name, &fixes()[0].1).is_err());
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, -1, 63, -64, 64, i64::MAX, i64::MIN] {
+            let mut buffer = vec![];
+            write_signed(&mut buffer, value);
+            assert_eq!(read_signed(&mut buffer.as_slice()).unwrap(), value);
+        }
+    }
+}
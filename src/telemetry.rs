@@ -0,0 +1,247 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::aprs_message::AprsMessage;
+use crate::callsign::Callsign;
+use crate::error::AprsError;
+
+// A telemetry frame "T#sss,aaa,aaa,aaa,aaa,aaa,bbbbbbbbcomment"
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AprsTelemetry {
+    // None for "MIC"
+    pub sequence: Option<u16>,
+    // Up to 5 analog values, raw as transmitted
+    pub values: Vec<f32>,
+    // 8 digital values, the first one is the most significant bit
+    pub bits: Option<u8>,
+    pub comment: String,
+}
+
+impl AprsTelemetry {
+    // Applies the coefficients of an EQNS definition: a * x^2 + b * x + c
+    pub fn scaled_values(&self, equations: &[[f32; 3]]) -> Vec<f32> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| match equations.get(idx) {
+                Some([a, b, c]) => a * x * x + b * x + c,
+                None => *x,
+            })
+            .collect()
+    }
+}
+
+impl FromStr for AprsTelemetry {
+    type Err = AprsError;
+
+    // Parses the information field without the data type identifier 'T'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AprsError::InvalidTelemetry(s.to_string());
+        let mut fields = s.strip_prefix('#').ok_or_else(invalid)?.splitn(7, ',');
+        let sequence = match fields.next() {
+            Some("MIC") => None,
+            Some(sequence) => Some(sequence.parse::<u16>().map_err(|_| invalid())?),
+            None => return Err(invalid()),
+        };
+        let mut values = vec![];
+        let (mut bits, mut comment) = (None, "");
+        for (idx, field) in fields.enumerate() {
+            if idx < 5 {
+                let value = field.trim().parse::<f32>().map_err(|_| invalid())?;
+                if !value.is_finite() {
+                    return Err(invalid());
+                }
+                values.push(value);
+            } else {
+                let digits = field.get(..8).ok_or_else(invalid)?;
+                if !digits.bytes().all(|byte| byte == b'0' || byte == b'1') {
+                    return Err(invalid());
+                }
+                bits = u8::from_str_radix(digits, 2).ok();
+                comment = &field[8..];
+            }
+        }
+        Ok(AprsTelemetry {
+            sequence,
+            values,
+            bits,
+            comment: comment.to_string(),
+        })
+    }
+}
+
+impl Display for AprsTelemetry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.sequence {
+            Some(sequence) => write!(f, "T#{:03}", sequence)?,
+            None => write!(f, "T#MIC")?,
+        }
+        for value in &self.values {
+            if value.fract() == 0.0 && (0.0..1000.0).contains(value) {
+                write!(f, ",{:03}", *value as u16)?;
+            } else {
+                write!(f, ",{}", value)?;
+            }
+        }
+        if let Some(bits) = self.bits {
+            write!(f, ",{:08b}", bits)?;
+        }
+        write!(f, "{}", self.comment)
+    }
+}
+
+// The definitions of a telemetry station, sent as messages to the station itself
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum TelemetryDefinition {
+    // PARM.Battery,Temperature,...
+    Parameters(Vec<String>),
+    // UNIT.V,deg.C,...
+    Units(Vec<String>),
+    // EQNS.a,b,c,a,b,c,...: 3 coefficients for each analog value
+    Equations(Vec<[f32; 3]>),
+    // BITS.11111111,Project title: the sense of the digital values
+    Bits { active: u8, project: String },
+}
+
+impl TelemetryDefinition {
+    // from: the sender of the message, definitions are only valid when sent to itself
+    pub fn from_message(from: &Callsign, message: &AprsMessage) -> Option<TelemetryDefinition> {
+        if message.addressee.trim_end() != from.to_string() {
+            return None;
+        }
+        let (kind, list) = message.text.split_once('.')?;
+        match kind {
+            "PARM" => Some(TelemetryDefinition::Parameters(names(list))),
+            "UNIT" => Some(TelemetryDefinition::Units(names(list))),
+            "EQNS" => {
+                let coefficients = list
+                    .split(',')
+                    .map(|coefficient| coefficient.trim().parse::<f32>().ok())
+                    .collect::<Option<Vec<_>>>()?;
+                if coefficients.len() % 3 != 0 {
+                    return None;
+                }
+                let equations = coefficients
+                    .chunks(3)
+                    .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+                    .collect();
+                Some(TelemetryDefinition::Equations(equations))
+            }
+            "BITS" => {
+                let (bits, project) = list.split_once(',').unwrap_or((list, ""));
+                if bits.len() != 8 || !bits.bytes().all(|byte| byte == b'0' || byte == b'1') {
+                    return None;
+                }
+                Some(TelemetryDefinition::Bits {
+                    active: u8::from_str_radix(bits, 2).ok()?,
+                    project: project.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn names(list: &str) -> Vec<String> {
+    list.split(',').map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_telemetry() {
+    let telemetry = "#005,199,000,255,073,123,01101001"
+        .parse::<AprsTelemetry>()
+        .unwrap();
+    assert_eq!(
+        telemetry,
+        AprsTelemetry {
+            sequence: Some(5),
+            values: vec![199.0, 0.0, 255.0, 73.0, 123.0],
+            bits: Some(0b01101001),
+            comment: "".to_string(),
+        }
+    );
+    assert_eq!(telemetry.to_string(), "T#005,199,000,255,073,123,01101001");
+    assert_eq!(
+        telemetry.scaled_values(&[[0.0, 0.075, 0.0], [0.0, 1.0, -20.0]]),
+        vec![14.925, -20.0, 255.0, 73.0, 123.0]
+    );
+
+    let telemetry = "#MIC,12.5,2".parse::<AprsTelemetry>().unwrap();
+    assert_eq!(telemetry.sequence, None);
+    assert_eq!(telemetry.values, vec![12.5, 2.0]);
+    assert_eq!(telemetry.bits, None);
+    assert_eq!(telemetry.to_string(), "T#MIC,12.5,002");
+
+    let telemetry = "#001,1,2,3,4,5,10000000 Solar"
+        .parse::<AprsTelemetry>()
+        .unwrap();
+    assert_eq!(telemetry.bits, Some(128));
+    assert_eq!(telemetry.comment, " Solar");
+
+    for invalid in [
+        "005,1",
+        "#abc,1",
+        "#001,x",
+        "#001,1,2,3,4,5,0101",
+        "#001,inf",
+    ] {
+        assert_eq!(
+            invalid.parse::<AprsTelemetry>(),
+            Err(AprsError::InvalidTelemetry(invalid.to_string()))
+        );
+    }
+}
+
+#[test]
+fn test_telemetry_definitions() {
+    let definition = |text: &str| {
+        let message = format!("OGNDDFAA3:{}", text)
+            .parse::<AprsMessage>()
+            .unwrap();
+        TelemetryDefinition::from_message(&Callsign::new("OGNDDFAA3", None), &message)
+    };
+    assert_eq!(
+        definition("PARM.Battery,Temp"),
+        Some(TelemetryDefinition::Parameters(vec![
+            "Battery".to_string(),
+            "Temp".to_string()
+        ]))
+    );
+    assert_eq!(
+        definition("UNIT.V,deg.C"),
+        Some(TelemetryDefinition::Units(vec![
+            "V".to_string(),
+            "deg.C".to_string()
+        ]))
+    );
+    assert_eq!(
+        definition("EQNS.0,0.075,0,0,1,-20"),
+        Some(TelemetryDefinition::Equations(vec![
+            [0.0, 0.075, 0.0],
+            [0.0, 1.0, -20.0]
+        ]))
+    );
+    assert_eq!(
+        definition("BITS.11110000,Balloon"),
+        Some(TelemetryDefinition::Bits {
+            active: 0b11110000,
+            project: "Balloon".to_string()
+        })
+    );
+    assert_eq!(definition("EQNS.0,0.075"), None);
+    assert_eq!(definition("BITS.111"), None);
+    assert_eq!(definition("Hello world"), None);
+
+    // Sent to another station
+    let message = "OGNDDFAA3:UNIT.V,deg.C".parse::<AprsMessage>().unwrap();
+    assert_eq!(
+        TelemetryDefinition::from_message(&Callsign::new("OGNDDFAA4", None), &message),
+        None
+    );
+    let message = "OGNDDF-15:UNIT.V".parse::<AprsMessage>().unwrap();
+    assert!(
+        TelemetryDefinition::from_message(&Callsign::new("OGNDDF", Some("15")), &message).is_some()
+    );
+}
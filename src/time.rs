@@ -0,0 +1,375 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::timestamp::Timestamp;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// The source of the current time of the time-dependent components, in seconds since
+// 1970-01-01. Tests use a SimulatedClock, replays set it to the time of the log lines.
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+// The wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        now()
+    }
+}
+
+// A clock that only moves when it is told to, it can be shared between threads
+#[derive(Debug, Default)]
+pub struct SimulatedClock(AtomicI64);
+
+impl SimulatedClock {
+    pub fn new(time: i64) -> Self {
+        SimulatedClock(AtomicI64::new(time))
+    }
+
+    pub fn set(&self, time: i64) {
+        self.0.store(time, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, seconds: i64) {
+        self.0.fetch_add(seconds, Ordering::Relaxed);
+    }
+
+    // Moves forward to the time, e.g. of a log line, but never back:
+    // out of order lines don't turn back the clock
+    pub fn advance_to(&self, time: i64) {
+        self.0.fetch_max(time, Ordering::Relaxed);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> i64 {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> i64 {
+        (**self).now()
+    }
+}
+
+// APRS timestamps are incomplete (HHMMSSh has no date, DDHHMMz has no month),
+// so we take the point in time closest to the reference (e.g. the receive time)
+pub fn resolve(timestamp: &Timestamp, reference: i64) -> Option<i64> {
+    match *timestamp {
+        Timestamp::HHMMSS(hours, minutes, seconds) => {
+            if hours > 23 || minutes > 59 || seconds > 59 {
+                return None;
+            }
+            let day_start = reference.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY;
+            let time = day_start + hours as i64 * 3600 + minutes as i64 * 60 + seconds as i64;
+            [time - SECONDS_PER_DAY, time, time + SECONDS_PER_DAY]
+                .into_iter()
+                .min_by_key(|candidate| (candidate - reference).abs())
+        }
+        Timestamp::DDHHMM(day, hours, minutes) => {
+            ddhhmm_candidates(day, hours, minutes, reference)?
+                .min_by_key(|candidate| (candidate - reference).abs())
+        }
+        _ => None,
+    }
+}
+
+// The offset of a time zone to UTC at a point in time, so the local wall clock can be converted
+pub trait TimeZone {
+    // Seconds to add to the UTC time to get the local time
+    fn utc_offset(&self, time: i64) -> i64;
+}
+
+// A time zone without daylight saving time, in seconds east of UTC
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct FixedOffset(pub i64);
+
+impl TimeZone for FixedOffset {
+    fn utc_offset(&self, _time: i64) -> i64 {
+        self.0
+    }
+}
+
+#[cfg(feature = "chrono-tz")]
+impl TimeZone for chrono_tz::Tz {
+    fn utc_offset(&self, time: i64) -> i64 {
+        use chrono::{Offset, TimeZone as _};
+        chrono::DateTime::from_timestamp(time, 0).map_or(0, |datetime| {
+            self.offset_from_utc_datetime(&datetime.naive_utc())
+                .fix()
+                .local_minus_utc() as i64
+        })
+    }
+}
+
+// Resolves the local time variant DDHHMM/ (Timestamp::Unsupported) in the time zone of the sender.
+// Around DST changes the wall clock is converted with the offset valid at that time: skipped local
+// times are moved forward, repeated local times resolve to the occurrence closest to the reference.
+// UTC timestamps are resolved like with resolve().
+pub fn resolve_local<T: TimeZone>(
+    timestamp: &Timestamp,
+    reference: i64,
+    time_zone: &T,
+) -> Option<i64> {
+    let Timestamp::Unsupported(s) = timestamp else {
+        return resolve(timestamp, reference);
+    };
+    let digits = s.strip_suffix('/')?;
+    if digits.len() != 6 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let value = |range: std::ops::Range<usize>| digits[range].parse::<u8>().ok();
+    let (day, hours, minutes) = (value(0..2)?, value(2..4)?, value(4..6)?);
+
+    let local_reference = reference + time_zone.utc_offset(reference);
+    ddhhmm_candidates(day, hours, minutes, local_reference)?
+        .flat_map(|local| local_to_utc(local, time_zone))
+        .min_by_key(|candidate| (candidate - reference).abs())
+}
+
+// The wall clock time in the days of the three months around the reference
+fn ddhhmm_candidates(
+    day: u8,
+    hours: u8,
+    minutes: u8,
+    reference: i64,
+) -> Option<impl Iterator<Item = i64>> {
+    if day == 0 || day > 31 || hours > 23 || minutes > 59 {
+        return None;
+    }
+    let (year, month, _) = civil_from_days(reference.div_euclid(SECONDS_PER_DAY));
+    Some([-1, 0, 1].into_iter().filter_map(move |offset| {
+        let (year, month) = add_months(year, month, offset);
+        (day as u32 <= days_in_month(year, month)).then(|| {
+            days_from_civil(year, month, day as u32) * SECONDS_PER_DAY
+                + hours as i64 * 3600
+                + minutes as i64 * 60
+        })
+    }))
+}
+
+// All UTC times showing this local time, two during the repeated hour of a DST change
+fn local_to_utc<T: TimeZone>(local: i64, time_zone: &T) -> Vec<i64> {
+    // No time zone changes its offset more than once within a day
+    let before = time_zone.utc_offset(local - SECONDS_PER_DAY / 2);
+    let after = time_zone.utc_offset(local + SECONDS_PER_DAY / 2);
+    let mut times = [before, after]
+        .into_iter()
+        .map(|offset| local - offset)
+        .filter(|time| local - time_zone.utc_offset(*time) == *time)
+        .collect::<Vec<_>>();
+    times.dedup();
+    if times.is_empty() {
+        // Skipped by a DST change, the clock was still on the offset before
+        times.push(local - before);
+    }
+    times
+}
+
+// The time of day in seconds, enough to compare beacons which are close in time
+pub fn seconds_of_day(timestamp: &Timestamp) -> Option<i64> {
+    match *timestamp {
+        Timestamp::HHMMSS(hours, minutes, seconds) => {
+            Some(hours as i64 * 3600 + minutes as i64 * 60 + seconds as i64)
+        }
+        Timestamp::DDHHMM(_, hours, minutes) => Some(hours as i64 * 3600 + minutes as i64 * 60),
+        _ => None,
+    }
+}
+
+// Formats unix seconds as ISO-8601 in UTC, e.g. 2024-08-01T07:48:49Z
+pub fn to_iso8601(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let time = seconds.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// Parses a date "YYYY-MM-DD" to the seconds since 1970-01-01 at its start (UTC)
+pub fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY)
+}
+
+fn add_months(year: i64, month: u32, offset: i64) -> (i64, u32) {
+    let months = year * 12 + month as i64 - 1 + offset;
+    (months.div_euclid(12), (months.rem_euclid(12) + 1) as u32)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let (next_year, next_month) = add_months(year, month, 1);
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u32
+}
+
+// Days since 1970-01-01 in the proleptic gregorian calendar (Howard Hinnant's algorithm)
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-08-01T12:00:00Z
+    const REFERENCE: i64 = 1722513600;
+
+    #[test]
+    fn test_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 8, 1), 19936);
+        assert_eq!(civil_from_days(19936), (2024, 8, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(to_iso8601(REFERENCE), "2024-08-01T12:00:00Z");
+    }
+
+    #[test]
+    fn test_simulated_clock() {
+        let clock = Arc::new(SimulatedClock::new(REFERENCE));
+        let shared: Arc<dyn Clock> = clock.clone();
+        clock.advance(60);
+        assert_eq!(shared.now(), REFERENCE + 60);
+        clock.advance_to(REFERENCE);
+        assert_eq!(shared.now(), REFERENCE + 60);
+        clock.advance_to(REFERENCE + 120);
+        clock.set(REFERENCE);
+        assert_eq!(shared.now(), REFERENCE);
+        assert!(SystemClock.now() > REFERENCE);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2024-08-01"), Some(REFERENCE - 12 * 3600));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-08"), None);
+    }
+
+    #[test]
+    fn test_resolve_hhmmss() {
+        let resolved = resolve(&Timestamp::HHMMSS(7, 48, 49), REFERENCE).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-08-01T07:48:49Z");
+
+        // Shortly before midnight, seen shortly after midnight
+        let resolved = resolve(&Timestamp::HHMMSS(23, 59, 0), REFERENCE - 12 * 3600 + 60).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-07-31T23:59:00Z");
+
+        assert_eq!(resolve(&Timestamp::HHMMSS(24, 0, 0), REFERENCE), None);
+    }
+
+    #[test]
+    fn test_resolve_ddhhmm() {
+        let resolved = resolve(&Timestamp::DDHHMM(31, 23, 59), REFERENCE).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-07-31T23:59:00Z");
+
+        let resolved = resolve(&Timestamp::DDHHMM(1, 8, 0), REFERENCE).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-08-01T08:00:00Z");
+
+        assert_eq!(resolve(&Timestamp::DDHHMM(0, 8, 0), REFERENCE), None);
+    }
+
+    // Central European time with the DST changes of 2024
+    struct Berlin;
+
+    impl TimeZone for Berlin {
+        fn utc_offset(&self, time: i64) -> i64 {
+            // 2024-03-31T01:00:00Z until 2024-10-27T01:00:00Z
+            if (1711846800..1729990800).contains(&time) {
+                7200
+            } else {
+                3600
+            }
+        }
+    }
+
+    fn local(s: &str) -> Timestamp {
+        Timestamp::Unsupported(s.to_string())
+    }
+
+    #[test]
+    fn test_resolve_local() {
+        let resolved = resolve_local(&local("010945/"), REFERENCE, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-08-01T07:45:00Z");
+        let resolved = resolve_local(&local("010945/"), REFERENCE, &FixedOffset(-3600)).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-08-01T10:45:00Z");
+
+        // UTC timestamps don't depend on the time zone
+        assert_eq!(
+            resolve_local(&Timestamp::HHMMSS(7, 48, 49), REFERENCE, &Berlin),
+            resolve(&Timestamp::HHMMSS(7, 48, 49), REFERENCE)
+        );
+        assert_eq!(resolve_local(&local("320000/"), REFERENCE, &Berlin), None);
+        assert_eq!(resolve_local(&local("0100/"), REFERENCE, &Berlin), None);
+    }
+
+    #[test]
+    fn test_resolve_local_dst() {
+        // 2024-03-31T12:00:00Z: 02:30 local didn't exist, the clocks jumped from 02:00 to 03:00
+        let reference = 1711886400;
+        let resolved = resolve_local(&local("310230/"), reference, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-03-31T01:30:00Z");
+        let resolved = resolve_local(&local("310330/"), reference, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-03-31T01:30:00Z");
+        let resolved = resolve_local(&local("311200/"), reference, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-03-31T10:00:00Z");
+
+        // 2024-10-27: 02:30 local happened twice, at 00:30Z and at 01:30Z
+        let resolved = resolve_local(&local("270230/"), 1729989000, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-10-27T00:30:00Z");
+        let resolved = resolve_local(&local("270230/"), 1729992600, &Berlin).unwrap();
+        assert_eq!(to_iso8601(resolved), "2024-10-27T01:30:00Z");
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AprsError;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum Timestamp {
+    // Day, hours and minutes in UTC ("z")
+    DDHHMM(u8, u8, u8),
+    // Hours, minutes and seconds in UTC ("h")
+    HHMMSS(u8, u8, u8),
+    // Local time ("/"), needs the time zone of the sender, see time::resolve_local
+    Unsupported(String),
+}
+
+impl FromStr for Timestamp {
+    type Err = AprsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || AprsError::InvalidTimestamp(s.to_string());
+        if s.len() != 7 || !s.as_bytes()[..6].iter().all(u8::is_ascii_digit) {
+            return Err(error());
+        }
+        let value = |range: std::ops::Range<usize>| s[range].parse::<u8>().unwrap();
+        match &s[6..] {
+            "z" => Ok(Timestamp::DDHHMM(value(0..2), value(2..4), value(4..6))),
+            "h" => Ok(Timestamp::HHMMSS(value(0..2), value(2..4), value(4..6))),
+            "/" => Ok(Timestamp::Unsupported(s.to_string())),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timestamp::DDHHMM(day, hours, minutes) => {
+                write!(f, "{:02}{:02}{:02}z", day, hours, minutes)
+            }
+            Timestamp::HHMMSS(hours, minutes, seconds) => {
+                write!(f, "{:02}{:02}{:02}h", hours, minutes, seconds)
+            }
+            Timestamp::Unsupported(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[test]
+fn test_timestamp() {
+    assert_eq!("074849h".parse(), Ok(Timestamp::HHMMSS(7, 48, 49)));
+    assert_eq!("312359z".parse(), Ok(Timestamp::DDHHMM(31, 23, 59)));
+    assert_eq!(
+        "092345/".parse(),
+        Ok(Timestamp::Unsupported("092345/".to_string()))
+    );
+    assert_eq!(Timestamp::HHMMSS(7, 48, 49).to_string(), "074849h");
+    for invalid in ["07484h", "074849x", "07a849h", "0748499h"] {
+        assert_eq!(
+            invalid.parse::<Timestamp>(),
+            Err(AprsError::InvalidTimestamp(invalid.to_string()))
+        );
+    }
+}
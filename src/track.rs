@@ -0,0 +1,195 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
+
+use crate::geo::distance;
+use crate::message::Message;
+use crate::packet::AprsData;
+use crate::time;
+
+const METERS_PER_DEGREE: f64 = 111_195.0;
+
+// A single aircraft position in the units of the beacons
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Fix {
+    // Seconds since 1970-01-01
+    pub time: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    // in feet
+    pub altitude: Option<f64>,
+    // in degrees
+    pub course: Option<f64>,
+    // in knots
+    pub speed: Option<f64>,
+    // in feet per minute
+    pub climb_rate: Option<f64>,
+    // Fixes which were not received but computed
+    pub synthetic: bool,
+}
+
+impl Fix {
+    // reference_time: seconds since 1970-01-01 used to resolve the APRS timestamp
+    pub fn from_message(message: &Message, reference_time: i64) -> Option<Fix> {
+        let packet = message.aprs_packet.as_ref().ok()?;
+        let position = match &packet.data {
+            AprsData::Position(position) => position,
+            _ => return None,
+        };
+        let comment = message.position_comment.as_ref();
+        Some(Fix {
+            time: time::resolve(position.timestamp.as_ref()?, reference_time)?,
+            latitude: position.latitude,
+            longitude: position.longitude,
+            altitude: comment.and_then(|comment| comment.altitude).map(f64::from),
+            course: comment.and_then(|comment| comment.course).map(f64::from),
+            speed: comment.and_then(|comment| comment.speed).map(f64::from),
+            climb_rate: comment
+                .and_then(|comment| comment.climb_rate)
+                .map(f64::from),
+            synthetic: false,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AnonymizeOptions {
+    // Fixes within this distance in meters of the start and the end of the track are removed
+    pub truncate_radius: f64,
+    // The radius is randomly enlarged by up to this fraction, so the removed area
+    // can't be reconstructed from several tracks
+    pub radius_jitter: f64,
+    // The start and end locations are snapped to a grid with this cell size in meters
+    pub grid_size: f64,
+    pub salt: u64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AnonymizedTrack {
+    pub fixes: Vec<Fix>,
+    // The fuzzed takeoff and landing locations
+    pub start: Option<(f64, f64)>,
+    pub end: Option<(f64, f64)>,
+}
+
+pub fn anonymize_track(fixes: &[Fix], options: &AnonymizeOptions) -> AnonymizedTrack {
+    let (first, last) = match (fixes.first(), fixes.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return AnonymizedTrack {
+                fixes: vec![],
+                start: None,
+                end: None,
+            }
+        }
+    };
+
+    let radius = |fix: &Fix| {
+        let mut hasher = DefaultHasher::new();
+        (options.salt, fix.time).hash(&mut hasher);
+        let random = (hasher.finish() % 1_000_000) as f64 / 1_000_000.0;
+        options.truncate_radius * (1.0 + options.radius_jitter * random)
+    };
+    let outside = |center: &Fix, radius: f64, fix: &Fix| {
+        distance(
+            center.latitude,
+            center.longitude,
+            fix.latitude,
+            fix.longitude,
+        ) > radius
+    };
+    let (start_radius, end_radius) = (radius(first), radius(last));
+    let start = fixes
+        .iter()
+        .position(|fix| outside(first, start_radius, fix))
+        .unwrap_or(fixes.len());
+    let end = fixes
+        .iter()
+        .rposition(|fix| outside(last, end_radius, fix))
+        .map_or(start, |end| (end + 1).max(start));
+
+    AnonymizedTrack {
+        fixes: fixes[start..end].to_vec(),
+        start: Some(fuzz_location(
+            first.latitude,
+            first.longitude,
+            options.grid_size,
+        )),
+        end: Some(fuzz_location(
+            last.latitude,
+            last.longitude,
+            options.grid_size,
+        )),
+    }
+}
+
+// Snaps a location to the center of its grid cell (cell size in meters)
+pub fn fuzz_location(latitude: f64, longitude: f64, grid_size: f64) -> (f64, f64) {
+    if grid_size <= 0.0 {
+        return (latitude, longitude);
+    }
+    let snap = |value: f64, cell: f64| ((value / cell).floor() + 0.5) * cell;
+    let latitude_cell = grid_size / METERS_PER_DEGREE;
+    let latitude = snap(latitude, latitude_cell).clamp(-90.0, 90.0);
+    // Constant cell width in meters, but never wider than the whole globe
+    let longitude_cell =
+        (grid_size / (METERS_PER_DEGREE * latitude.to_radians().cos().max(0.01))).min(360.0);
+    (latitude, snap(longitude, longitude_cell))
+}
+
+#[test]
+fn test_fix_from_message() {
+    let message =
+        r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id0ADDFAA3 -019fpm"
+            .parse::<Message>()
+            .unwrap();
+    // 2024-08-01T12:00:00Z
+    let fix = Fix::from_message(&message, 1722513600).unwrap();
+    assert_eq!(time::to_iso8601(fix.time), "2024-08-01T07:45:48Z");
+    assert_eq!(fix.altitude, Some(607.0));
+    assert_eq!(fix.course, Some(86.0));
+    assert_eq!(fix.climb_rate, Some(-19.0));
+    assert!(!fix.synthetic);
+}
+
+#[test]
+fn test_anonymize_track() {
+    // A straight track to the north with a fix every ~111 m
+    let fixes = (0..100)
+        .map(|idx| Fix {
+            time: idx,
+            latitude: 48.0 + idx as f64 * 0.001,
+            longitude: 11.0,
+            altitude: None,
+            course: None,
+            speed: None,
+            climb_rate: None,
+            synthetic: false,
+        })
+        .collect::<Vec<_>>();
+    let options = AnonymizeOptions {
+        truncate_radius: 1000.0,
+        grid_size: 5000.0,
+        ..Default::default()
+    };
+    let track = anonymize_track(&fixes, &options);
+    assert_eq!(track.fixes.first().unwrap().time, 9);
+    assert_eq!(track.fixes.last().unwrap().time, 90);
+
+    let (latitude, longitude) = track.start.unwrap();
+    assert!(distance(latitude, longitude, 48.0, 11.0) < 5000.0);
+    assert_eq!(track.start, Some(fuzz_location(48.01, 11.01, 5000.0)));
+
+    // With jitter the radius is somewhere between 1000 and 2000 m
+    let options = AnonymizeOptions {
+        radius_jitter: 1.0,
+        ..options
+    };
+    let track = anonymize_track(&fixes, &options);
+    assert!((9..=18).contains(&track.fixes.first().unwrap().time));
+
+    // Short tracks are removed completely
+    assert!(anonymize_track(&fixes[..10], &options).fixes.is_empty());
+    assert_eq!(anonymize_track(&[], &options).start, None);
+}
@@ -1,65 +1,169 @@
+//! Tokenization helpers for the value/unit notation of OGN comments, e.g. "-019fpm" or
+//! "RF:+0-1.1ppm/-0.16dB". They are used by the parser and kept stable for other OGN text formats.
+//!
+//! Only ASCII digits, signs and '.' are part of a number, everything else (including any
+//! non-ASCII character) ends it, so the returned slices are always on character boundaries.
+
+/// The units of the value/unit tokens the parser knows, e.g. "fpm" of "-019fpm".
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Unit {
+    FeetPerMinute,
+    Rotation,
+    Decibel,
+    Kilohertz,
+    Errors,
+    DecibelMilliwatt,
+    Celsius,
+    Volt,
+    Ampere,
+}
+
+impl Unit {
+    /// Returns the unit of a suffix as split off by [`split_value_unit`], units are case sensitive.
+    ///
+    /// ```
+    /// use ognparser::utils::{split_value_unit, Unit};
+    ///
+    /// let (_, unit) = split_value_unit("-019fpm").unwrap();
+    /// assert_eq!(Unit::from_suffix(unit), Some(Unit::FeetPerMinute));
+    /// assert_eq!(Unit::from_suffix("FPM"), None);
+    /// ```
+    // A single match on the bytes instead of a chain of string compares
+    pub fn from_suffix(suffix: &str) -> Option<Unit> {
+        match suffix.as_bytes() {
+            b"fpm" => Some(Unit::FeetPerMinute),
+            b"rot" => Some(Unit::Rotation),
+            b"dB" => Some(Unit::Decibel),
+            b"kHz" => Some(Unit::Kilohertz),
+            b"e" => Some(Unit::Errors),
+            b"dBm" => Some(Unit::DecibelMilliwatt),
+            b"C" => Some(Unit::Celsius),
+            b"V" => Some(Unit::Volt),
+            b"A" => Some(Unit::Ampere),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a token into its leading number and the unit after it.
+///
+/// The number is an optional sign, digits and at most one '.', it needs at least one digit.
+/// Returns `None` if there is no number or no unit. The number is not validated any further,
+/// so `"-12."` is returned as is and left to `str::parse`.
+///
+/// ```
+/// use ognparser::utils::split_value_unit;
+///
+/// assert_eq!(split_value_unit("-3.5kHz"), Some(("-3.5", "kHz")));
+/// assert_eq!(split_value_unit("21°C"), Some(("21", "°C")));
+/// assert_eq!(split_value_unit("25"), None);
+/// assert_eq!(split_value_unit("dB"), None);
+/// ```
 pub fn split_value_unit(s: &str) -> Option<(&str, &str)> {
-    let length = s.len();
-    s.chars()
-        .enumerate()
-        .scan(
-            (false, false, false),
-            |(has_digits, is_signed, has_decimal), (idx, elem)| {
-                if idx == 0 && ['+', '-'].contains(&elem) {
-                    *is_signed = true;
-                    Some((idx, *has_digits))
-                } else if elem == '.' && !(*has_decimal) {
-                    *has_decimal = true;
-                    Some((idx, *has_digits))
-                } else if elem.is_ascii_digit() {
-                    *has_digits = true;
-                    Some((idx, *has_digits))
-                } else {
-                    None
-                }
-            },
-        )
-        .last()
-        .and_then(|(split_position, has_digits)| {
-            if has_digits && split_position != length - 1 {
-                Some((&s[..(split_position + 1)], &s[(split_position + 1)..]))
-            } else {
-                None
-            }
-        })
+    let bytes = s.as_bytes();
+    let (mut has_digits, mut has_decimal) = (false, false);
+    let mut split_position = 0;
+    for (idx, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'+' | b'-' if idx == 0 => {}
+            b'.' if !has_decimal => has_decimal = true,
+            b'0'..=b'9' => has_digits = true,
+            _ => break,
+        }
+        split_position = idx + 1;
+    }
+    if has_digits && split_position < bytes.len() {
+        Some((&s[..split_position], &s[split_position..]))
+    } else {
+        None
+    }
 }
 
+/// Extracts all numbers of a token in order.
+///
+/// A sign always starts a new number, any other character which is not a digit or '.'
+/// ends the current one. A sign without digits is returned as is.
+///
+/// ```
+/// use ognparser::utils::extract_values;
+///
+/// assert_eq!(
+///     extract_values("RF:+0-1.1ppm/-0.16dB"),
+///     vec!["+0", "-1.1", "-0.16"]
+/// );
+/// assert_eq!(extract_values("8/16"), vec!["8", "16"]);
+/// ```
 pub fn extract_values(part: &str) -> Vec<String> {
     let mut result = Vec::new();
-    let mut current_value = String::new();
+    let mut start = None;
 
-    for c in part.chars() {
-        if c == '+' || c == '-' {
-            if !current_value.is_empty() {
-                result.push(current_value.clone());
+    for (idx, byte) in part.bytes().enumerate() {
+        match byte {
+            b'+' | b'-' => {
+                if let Some(start) = start {
+                    result.push(part[start..idx].to_string());
+                }
+                start = Some(idx);
+            }
+            b'0'..=b'9' | b'.' => {
+                start.get_or_insert(idx);
+            }
+            _ => {
+                if let Some(start) = start.take() {
+                    result.push(part[start..idx].to_string());
+                }
             }
-            current_value = String::new();
-            current_value.push(c);
-        } else if char::is_numeric(c) || c == '.' {
-            current_value.push(c);
-        } else if !current_value.is_empty() {
-            result.push(current_value.clone());
-            current_value = String::new();
         }
     }
 
-    if !current_value.is_empty() {
-        result.push(current_value.clone());
+    if let Some(start) = start {
+        result.push(part[start..].to_string());
     }
     result
 }
 
+// Parses only plain ASCII digits, so "+1" isn't a number
+pub(crate) fn parse_digits<T: std::str::FromStr>(s: &str) -> Option<T> {
+    if s.is_empty() || !s.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// Parses only plain decimals like "12.34" (or "-12.34" if signed),
+// str::parse::<f32> also accepts "inf", "NaN" or "1e5"
+pub(crate) fn parse_decimal(s: &str, signed: bool) -> Option<f32> {
+    let unsigned = match s.strip_prefix('-') {
+        Some(unsigned) if signed => unsigned,
+        _ => s,
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty()
+        || ![integer, fraction]
+            .iter()
+            .all(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+    {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// Like split_whitespace, but with the byte offset of each part
+pub(crate) fn split_whitespace_with_offsets(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_whitespace()
+        .map(move |part| (part.as_ptr() as usize - s.as_ptr() as usize, part))
+}
+
 #[test]
 fn test_extract_values() {
     assert_eq!(
         extract_values("-1.2+3.4-5.6dB7km"),
         vec!["-1.2", "+3.4", "-5.6", "7"]
     );
+    // Non-ASCII digits and units are separators
+    assert_eq!(extract_values("+21.5°C/٣4²"), vec!["+21.5", "4"]);
+    assert_eq!(extract_values("°-3.3µs+"), vec!["-3.3", "+"]);
+    assert!(extract_values("").is_empty());
 }
 
 #[test]
@@ -71,4 +175,31 @@ fn test_split_value_unit() {
     assert_eq!(split_value_unit("-12.V"), Some(("-12.", "V")));
     assert_eq!(split_value_unit("+kVA"), None);
     assert_eq!(split_value_unit("25"), None);
+    assert_eq!(split_value_unit("1.2.3V"), Some(("1.2", ".3V")));
+    assert_eq!(split_value_unit("5°C"), Some(("5", "°C")));
+    assert_eq!(split_value_unit("-2.5µA"), Some(("-2.5", "µA")));
+    assert_eq!(split_value_unit("+°C"), None);
+    assert_eq!(split_value_unit("٣V"), None);
+    assert_eq!(split_value_unit(""), None);
+}
+
+#[test]
+fn test_parse_strict() {
+    assert_eq!(parse_digits::<u32>("001000"), Some(1000));
+    assert_eq!(parse_digits::<u32>("+01000"), None);
+    assert_eq!(parse_digits::<u8>(""), None);
+    assert_eq!(parse_decimal("12.34", false), Some(12.34));
+    assert_eq!(parse_decimal("-0.5", true), Some(-0.5));
+    assert_eq!(parse_decimal("-0.5", false), None);
+    for invalid in ["inf", "NaN", "1e5", "+1", ".5", "1.2.3", ""] {
+        assert_eq!(parse_decimal(invalid, true), None);
+    }
+}
+
+#[test]
+fn test_split_whitespace_with_offsets() {
+    assert_eq!(
+        split_whitespace_with_offsets(" ab  c d").collect::<Vec<_>>(),
+        vec![(1, "ab"), (5, "c"), (7, "d")]
+    );
 }
@@ -0,0 +1,137 @@
+use serde::Serialize;
+
+use crate::message::Message;
+use crate::position_comment::AddressType;
+
+// The device family, derived from the 3 letter prefix of the sender callsign
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum DeviceVendor {
+    Random,
+    Icao,
+    Flarm,
+    OgnTracker,
+    Fanet,
+    PilotAware,
+    FlyMaster,
+    Naviter,
+    Spidertracks,
+    Spot,
+    Inreach,
+    Lt24,
+    Capturs,
+    Skytraxx,
+    Unknown,
+}
+
+impl DeviceVendor {
+    pub fn from_callsign(callsign: &str) -> DeviceVendor {
+        match callsign.get(..3) {
+            Some("RND") => DeviceVendor::Random,
+            Some("ICA") => DeviceVendor::Icao,
+            Some("FLR") => DeviceVendor::Flarm,
+            Some("OGN") => DeviceVendor::OgnTracker,
+            Some("FNT") => DeviceVendor::Fanet,
+            Some("PAW") => DeviceVendor::PilotAware,
+            Some("FMT") => DeviceVendor::FlyMaster,
+            Some("NAV") => DeviceVendor::Naviter,
+            Some("SPI") => DeviceVendor::Spidertracks,
+            Some("SPO") => DeviceVendor::Spot,
+            Some("INR") => DeviceVendor::Inreach,
+            Some("LTE") => DeviceVendor::Lt24,
+            Some("CAP") => DeviceVendor::Capturs,
+            Some("SKY") => DeviceVendor::Skytraxx,
+            _ => DeviceVendor::Unknown,
+        }
+    }
+
+    // The address type of the id field.
+    // Other vendors use varying address types, so they can't be checked.
+    pub fn expected_address_type(&self) -> Option<AddressType> {
+        match self {
+            DeviceVendor::Random => Some(AddressType::Random),
+            DeviceVendor::Icao => Some(AddressType::Icao),
+            DeviceVendor::Flarm => Some(AddressType::Flarm),
+            DeviceVendor::OgnTracker => Some(AddressType::Ogn),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct VendorClassification {
+    pub vendor: DeviceVendor,
+    pub address_type: Option<AddressType>,
+    // The address type of the id doesn't match the callsign prefix
+    pub mismatch: bool,
+}
+
+pub fn classify(message: &Message) -> Option<VendorClassification> {
+    let packet = message.aprs_packet.as_ref().ok()?;
+    let vendor = DeviceVendor::from_callsign(&packet.from.call);
+    let address_type = message
+        .position_comment
+        .as_ref()
+        .and_then(|comment| comment.id.as_ref())
+        .map(|id| id.address_type);
+    let mismatch = match (vendor.expected_address_type(), address_type) {
+        (Some(expected), Some(actual)) => expected != actual,
+        _ => false,
+    };
+    Some(VendorClassification {
+        vendor,
+        address_type,
+        mismatch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_callsign() {
+        assert_eq!(
+            DeviceVendor::from_callsign("FLRDDFAA3"),
+            DeviceVendor::Flarm
+        );
+        assert_eq!(DeviceVendor::from_callsign("ICA3D17F2"), DeviceVendor::Icao);
+        assert_eq!(
+            DeviceVendor::from_callsign("FNT1103CE"),
+            DeviceVendor::Fanet
+        );
+        assert_eq!(DeviceVendor::from_callsign("Letzi"), DeviceVendor::Unknown);
+        assert_eq!(DeviceVendor::from_callsign("FL"), DeviceVendor::Unknown);
+    }
+
+    #[test]
+    fn test_classify() {
+        // id06: address type 2 (FLARM)
+        let message =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id06DDFAA3"
+                .parse::<Message>()
+                .unwrap();
+        assert_eq!(
+            classify(&message),
+            Some(VendorClassification {
+                vendor: DeviceVendor::Flarm,
+                address_type: Some(AddressType::Flarm),
+                mismatch: false,
+            })
+        );
+
+        // id05: address type 1 (ICAO)
+        let message =
+            r"FLRDDFAA3>APRS,qAS,Letzi:/074548h5111.32N/00102.04W'086/007/A=000607 id05DDFAA3"
+                .parse::<Message>()
+                .unwrap();
+        assert!(classify(&message).unwrap().mismatch);
+
+        let message = r"ICA3D17F2>APRS,qAS,dl4mea:>312359zStatus seems okay!"
+            .parse::<Message>()
+            .unwrap();
+        let classification = classify(&message).unwrap();
+        assert_eq!(classification.vendor, DeviceVendor::Icao);
+        assert_eq!(classification.address_type, None);
+        assert!(!classification.mismatch);
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt::{Display, Formatter};
+use std::{convert::Infallible, str::FromStr};
+
+use serde::Serialize;
+
+use crate::cursor::FieldCursor;
+use crate::error::AprsError;
+
+// A positionless weather report "_10090556c220s004g005t077r000p000P000h50b09900wRSW",
+// the weather data is parsed into a WeatherComment
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct AprsWeather {
+    // Month, day, hours and minutes in UTC: MMDDHHMM
+    pub timestamp: String,
+    pub comment: String,
+}
+
+impl FromStr for AprsWeather {
+    type Err = AprsError;
+
+    // Parses the information field without the data type identifier '_'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cursor = FieldCursor::new(s);
+        let timestamp = cursor
+            .take_n(8)
+            .filter(|timestamp| timestamp.bytes().all(|byte| byte.is_ascii_digit()))
+            .ok_or_else(|| AprsError::InvalidTimestamp(s.to_string()))?;
+        Ok(AprsWeather {
+            timestamp: timestamp.to_string(),
+            comment: cursor.rest().to_string(),
+        })
+    }
+}
+
+impl Display for AprsWeather {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "_{}{}", self.timestamp, self.comment)
+    }
+}
+
+// The weather data of positionless weather reports and of positions with the weather
+// station symbol '_' (e.g. FANET weather stations): 220/004g005t077r000p000P000h50b09900
+// Missing values are sent as "..." or spaces.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize)]
+pub struct WeatherComment {
+    // in degrees
+    pub wind_direction: Option<u16>,
+    // in mph
+    pub wind_speed: Option<u16>,
+    // Peak wind speed in the last 5 minutes in mph
+    pub wind_gust: Option<u16>,
+    // in degrees Fahrenheit
+    pub temperature: Option<i16>,
+    // in hundredths of an inch
+    pub rain_1h: Option<u16>,
+    pub rain_24h: Option<u16>,
+    pub rain_since_midnight: Option<u16>,
+    // in %
+    pub humidity: Option<u8>,
+    // in tenths of hPa
+    pub barometric_pressure: Option<u32>,
+    // in W/m²
+    pub luminosity: Option<u16>,
+    // The rest of positionless reports, e.g. software and unit "wRSW".
+    // The rest of positions is their position comment.
+    pub unparsed: Option<String>,
+}
+
+impl WeatherComment {
+    // Splits the weather data from the start of the comment, None if there is none
+    pub(crate) fn split(s: &str) -> Option<(WeatherComment, &str)> {
+        let mut weather = WeatherComment::default();
+        let mut cursor = FieldCursor::new(s);
+        let mut keys = String::new();
+
+        // Positions start with the wind: ddd/sss
+        let mut wind = cursor;
+        if let (Some(Some(direction)), Some(()), Some(Some(speed))) = (
+            wind.take_n(3).map(value::<u16>),
+            wind.expect("/"),
+            wind.take_n(3).map(value::<u16>),
+        ) {
+            weather.wind_direction = direction;
+            weather.wind_speed = speed;
+            keys.push_str("cs");
+            cursor = wind;
+        }
+
+        loop {
+            let mut field = cursor;
+            let Some(key) = field.take_n(1) else {
+                break;
+            };
+            let len = match key {
+                "h" => 2,
+                "b" => 5,
+                "c" | "s" | "g" | "t" | "r" | "p" | "P" | "L" | "l" => 3,
+                _ => break,
+            };
+            let Some(raw) = field.take_n(len).filter(|_| !keys.contains(key)) else {
+                break;
+            };
+            let assigned = match key {
+                "c" => value(raw).map(|v| weather.wind_direction = v),
+                "s" => value(raw).map(|v| weather.wind_speed = v),
+                "g" => value(raw).map(|v| weather.wind_gust = v),
+                "t" => value(raw).map(|v| weather.temperature = v),
+                "r" => value(raw).map(|v| weather.rain_1h = v),
+                "p" => value(raw).map(|v| weather.rain_24h = v),
+                "P" => value(raw).map(|v| weather.rain_since_midnight = v),
+                // h00 is 100%
+                "h" => value::<u8>(raw)
+                    .map(|v| weather.humidity = v.map(|h| if h == 0 { 100 } else { h })),
+                "b" => value(raw).map(|v| weather.barometric_pressure = v),
+                // L: below 1000 W/m², l: 1000 W/m² and above
+                "L" => value(raw).map(|v| weather.luminosity = v),
+                _ => value::<u16>(raw).map(|v| weather.luminosity = v.map(|l| l + 1000)),
+            };
+            if assigned.is_none() {
+                break;
+            }
+            keys.push_str(key);
+            cursor = field;
+        }
+        (!keys.is_empty()).then_some((weather, cursor.rest()))
+    }
+}
+
+impl FromStr for WeatherComment {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut weather, rest) =
+            WeatherComment::split(s).unwrap_or_else(|| (WeatherComment::default(), s));
+        weather.unparsed = match rest.trim() {
+            "" => None,
+            rest => Some(rest.to_string()),
+        };
+        Ok(weather)
+    }
+}
+
+// Some(None) for a missing value, None for an invalid one
+fn value<T: FromStr>(raw: &str) -> Option<Option<T>> {
+    if raw.bytes().all(|byte| byte == b'.' || byte == b' ') {
+        return Some(None);
+    }
+    let digits = raw.strip_prefix('-').unwrap_or(raw);
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    raw.parse().ok().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weather() {
+        let weather = "10090556c220s004g005t077r000p000P000h50b09900wRSW"
+            .parse::<AprsWeather>()
+            .unwrap();
+        assert_eq!(weather.timestamp, "10090556");
+        assert_eq!(
+            weather.to_string(),
+            "_10090556c220s004g005t077r000p000P000h50b09900wRSW"
+        );
+        assert_eq!(
+            weather.comment.parse::<WeatherComment>().unwrap(),
+            WeatherComment {
+                wind_direction: Some(220),
+                wind_speed: Some(4),
+                wind_gust: Some(5),
+                temperature: Some(77),
+                rain_1h: Some(0),
+                rain_24h: Some(0),
+                rain_since_midnight: Some(0),
+                humidity: Some(50),
+                barometric_pressure: Some(9900),
+                luminosity: None,
+                unparsed: Some("wRSW".to_string()),
+            }
+        );
+        assert_eq!(
+            "1009055".parse::<AprsWeather>(),
+            Err(AprsError::InvalidTimestamp("1009055".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_weather_comment() {
+        let (weather, rest) = WeatherComment::split("349/002g...t-05h00b10152l012 29.0dB").unwrap();
+        assert_eq!(weather.wind_direction, Some(349));
+        assert_eq!(weather.wind_speed, Some(2));
+        assert_eq!(weather.wind_gust, None);
+        assert_eq!(weather.temperature, Some(-5));
+        assert_eq!(weather.humidity, Some(100));
+        assert_eq!(weather.barometric_pressure, Some(10152));
+        assert_eq!(weather.luminosity, Some(1012));
+        assert_eq!(rest, " 29.0dB");
+
+        // Invalid or repeated fields end the weather data
+        let (weather, rest) = WeatherComment::split(".../...t0x5").unwrap();
+        assert_eq!(weather, WeatherComment::default());
+        assert_eq!(rest, "t0x5");
+        assert_eq!(WeatherComment::split("g005g006").unwrap().1, "g006");
+
+        assert_eq!(WeatherComment::split("id06DDFAA3"), None);
+        assert_eq!(
+            "hello".parse::<WeatherComment>().unwrap().unparsed,
+            Some("hello".to_string())
+        );
+    }
+}